@@ -9,6 +9,12 @@ fn main() {
             "openblas".to_string()
         }
     });
+    if env::var_os("CARGO_FEATURE_LAPACK").is_some() {
+        if let Ok(lapack) = env::var("LAPACK_VARIANT") {
+            println!("cargo:rustc-link-lib={}", lapack);
+        }
+    }
+
     let lib_dir = env::var("BLAS_LIB_DIR").ok();
     let include_dir = env::var("BLAS_INCLUDE_DIR").ok();
 
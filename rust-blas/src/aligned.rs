@@ -0,0 +1,160 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Buffers with a guaranteed alignment.
+//!
+//! BLAS itself accepts any pointer that is aligned for its element type, but
+//! most optimized backends process the first few elements of a misaligned
+//! buffer separately before their vector loops kick in, and some only use
+//! their fastest kernels on 32 or 64 byte boundaries. `aligned_vec`
+//! allocates storage starting on such a boundary.
+//!
+//! Alignment has to be a power of two. Requests below the natural alignment
+//! of `T` are rounded up to it. Only the start of the buffer is aligned, so
+//! a strided view or an offset into it generally is not.
+
+use crate::default::Default;
+use crate::vector::Vector;
+use num::traits::NumCast;
+use std::alloc::{self, Layout};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use std::slice;
+
+/// A fixed-length, heap-allocated buffer whose first element sits on an
+/// `align` byte boundary.
+///
+/// Dereferences to a slice and can be passed anywhere a `Vector` is taken.
+pub struct AlignedVec<T> {
+    ptr: NonNull<T>,
+    len: usize,
+    layout: Layout,
+}
+
+/// Allocates `len` zeros aligned to `align` bytes.
+///
+/// Panics if `align` is not a power of two or the size overflows.
+pub fn aligned_vec<T: Default>(len: usize, align: usize) -> AlignedVec<T> {
+    let layout = Layout::array::<T>(len)
+        .and_then(|l| l.align_to(align))
+        .expect("Invalid size or alignment");
+
+    let ptr = if layout.size() == 0 {
+        // Nothing is read through this, it only has to be non-null and aligned.
+        NonNull::new(layout.align() as *mut T).unwrap()
+    } else {
+        let raw = unsafe { alloc::alloc(layout) } as *mut T;
+        match NonNull::new(raw) {
+            Some(p) => p,
+            None => alloc::handle_alloc_error(layout),
+        }
+    };
+
+    for i in 0..len {
+        unsafe { ptr.as_ptr().add(i).write(Default::zero()) };
+    }
+
+    AlignedVec { ptr, len, layout }
+}
+
+impl<T> AlignedVec<T> {
+    /// The alignment of the buffer in bytes.
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+}
+
+impl<T> Drop for AlignedVec<T> {
+    fn drop(&mut self) {
+        unsafe {
+            if mem::needs_drop::<T>() {
+                for i in 0..self.len {
+                    self.ptr.as_ptr().add(i).drop_in_place();
+                }
+            }
+            if self.layout.size() != 0 {
+                alloc::dealloc(self.ptr.as_ptr() as *mut u8, self.layout);
+            }
+        }
+    }
+}
+
+impl<T> Deref for AlignedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for AlignedVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> Vector<T> for AlignedVec<T> {
+    fn len(&self) -> u32 {
+        let l: Option<u32> = NumCast::from(self.len);
+        match l {
+            Some(l) => l,
+            None => panic!(),
+        }
+    }
+
+    fn as_ptr(&self) -> *const T {
+        self.ptr.as_ptr()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+}
+
+unsafe impl<T: Send> Send for AlignedVec<T> {}
+unsafe impl<T: Sync> Sync for AlignedVec<T> {}
+
+#[cfg(test)]
+mod aligned_vec_tests {
+    use crate::aligned::aligned_vec;
+    use crate::vector::ops::{Axpy, Dot};
+    use num_complex::Complex64;
+
+    #[test]
+    fn alignment() {
+        for &align in &[16, 32, 64, 4096] {
+            let x = aligned_vec::<f32>(7, align);
+            assert_eq!(x.as_ptr() as usize % align, 0);
+            assert_eq!(x.align(), align);
+            assert_eq!(&x[..], &[0.0; 7]);
+        }
+
+        // Below the natural alignment of the element type.
+        let z = aligned_vec::<Complex64>(3, 1);
+        assert_eq!(z.as_ptr() as usize % std::mem::align_of::<Complex64>(), 0);
+
+        let empty = aligned_vec::<f64>(0, 64);
+        assert!(empty.is_empty());
+        assert_eq!(empty.as_ptr() as usize % 64, 0);
+    }
+
+    #[test]
+    fn blas_ops() {
+        let mut x = aligned_vec::<f64>(4, 64);
+        let mut y = aligned_vec::<f64>(4, 64);
+        x.copy_from_slice(&[1.0, 2.0, 3.0, 4.0]);
+        y.copy_from_slice(&[1.0, 1.0, 1.0, 1.0]);
+
+        Axpy::axpy(&2.0, &x, &mut y);
+        assert_eq!(&y[..], &[3.0, 5.0, 7.0, 9.0]);
+        assert_eq!(Dot::dot(&x, &y), 70.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn not_power_of_two() {
+        let _ = aligned_vec::<f32>(4, 24);
+    }
+}
@@ -4,21 +4,34 @@
 
 //! Various attributes of vectors and matrices.
 
+use libc::c_int;
+
+/// Memory layout of a matrix, passed to CBLAS as `CBLAS_ORDER`.
 #[repr(C)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Order {
     RowMajor = 101,
     ColMajor = 102,
 }
 
+/// Which of `A`, `A^T` or `A^H` a matrix operation uses as `op(A)`.
 #[repr(C)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Transpose {
     NoTrans = 111,
     Trans = 112,
     ConjTrans = 113,
 }
 
+impl Transpose {
+    /// The matching `CblasNoTrans`, `CblasTrans` or `CblasConjTrans`
+    /// constant.
+    pub fn as_cblas(self) -> c_int {
+        self as c_int
+    }
+}
+
+/// Which triangle of a symmetric, Hermitian or triangular matrix is stored.
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub enum Symmetry {
@@ -26,16 +39,57 @@ pub enum Symmetry {
     Lower = 122,
 }
 
+/// `Symmetry` under the name LAPACK and most BLAS documentation use for it.
+pub type Uplo = Symmetry;
+
+/// Whether a triangular matrix has an implicit unit diagonal, in which case
+/// the stored diagonal is not read.
 #[repr(C)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Diagonal {
     NonUnit = 131,
     Unit = 132,
 }
 
+impl Diagonal {
+    /// The matching `CblasNonUnit` or `CblasUnit` constant.
+    pub fn as_cblas(self) -> c_int {
+        self as c_int
+    }
+}
+
+/// `Diagonal` under its shorter LAPACK name.
+pub type Diag = Diagonal;
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub enum Side {
     Left = 141,
     Right = 142,
 }
+
+#[cfg(test)]
+mod transpose_tests {
+    use crate::attribute::Transpose;
+
+    #[test]
+    fn cblas_constants() {
+        assert_eq!(Transpose::NoTrans.as_cblas(), 111);
+        assert_eq!(Transpose::Trans.as_cblas(), 112);
+        assert_eq!(Transpose::ConjTrans.as_cblas(), 113);
+        assert_ne!(Transpose::Trans, Transpose::ConjTrans);
+    }
+}
+
+#[cfg(test)]
+mod diagonal_tests {
+    use crate::attribute::{Diag, Diagonal};
+
+    #[test]
+    fn cblas_constants() {
+        assert_eq!(Diag::NonUnit.as_cblas(), 131);
+        assert_eq!(Diag::Unit.as_cblas(), 132);
+        assert_eq!(Diag::Unit, Diagonal::Unit);
+        assert_ne!(Diagonal::Unit, Diagonal::NonUnit);
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Errors reported by fallible routines.
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlasError {
+    /// An operand had `found` elements along some dimension where `expected`
+    /// were required.
+    DimensionMismatch { expected: usize, found: usize },
+    /// A factorization or solve hit a zero (or, for Cholesky, non-positive)
+    /// pivot.
+    SingularMatrix,
+    /// An iterative LAPACK routine did not converge.
+    NoConvergence,
+    /// A vector was given a stride that the routine cannot use, such as
+    /// zero for a vector that is written to.
+    InvalidIncrement,
+}
+
+impl fmt::Display for BlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BlasError::DimensionMismatch { expected, found } => write!(
+                f,
+                "Dimension mismatch: expected {}, found {}",
+                expected, found
+            ),
+            BlasError::SingularMatrix => write!(f, "Matrix is singular"),
+            BlasError::NoConvergence => write!(f, "Algorithm did not converge"),
+            BlasError::InvalidIncrement => write!(f, "Invalid vector increment"),
+        }
+    }
+}
+
+impl Error for BlasError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::BlasError;
+    use std::error::Error;
+
+    #[test]
+    fn display() {
+        let mismatch = BlasError::DimensionMismatch {
+            expected: 3,
+            found: 2,
+        };
+        assert_eq!(
+            mismatch.to_string(),
+            "Dimension mismatch: expected 3, found 2"
+        );
+        assert_eq!(BlasError::SingularMatrix.to_string(), "Matrix is singular");
+        assert_eq!(
+            BlasError::NoConvergence.to_string(),
+            "Algorithm did not converge"
+        );
+        assert_eq!(
+            BlasError::InvalidIncrement.to_string(),
+            "Invalid vector increment"
+        );
+    }
+
+    #[test]
+    fn boxed() {
+        fn fails() -> Result<(), Box<dyn Error>> {
+            Err(BlasError::SingularMatrix)?;
+            Ok(())
+        }
+
+        let err = fails().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<BlasError>(),
+            Some(&BlasError::SingularMatrix)
+        );
+    }
+}
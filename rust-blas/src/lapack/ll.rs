@@ -0,0 +1,253 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Bindings for LAPACKE functions.
+
+pub mod lapacke_s {
+    use crate::attribute::Order;
+    use libc::{c_char, c_float, c_int};
+
+    pub use self::LAPACKE_sgeqrf as geqrf;
+    pub use self::LAPACKE_sgesv as gesv;
+    pub use self::LAPACKE_sgesvd as gesvd;
+    pub use self::LAPACKE_sgetrf as getrf;
+    pub use self::LAPACKE_sgetrs as getrs;
+    pub use self::LAPACKE_sorgqr as orgqr;
+    pub use self::LAPACKE_ssyev as syev;
+
+    extern "C" {
+        pub fn LAPACKE_sgeqrf(
+            layout: Order,
+            m: u32,
+            n: u32,
+            a: *mut c_float,
+            lda: u32,
+            tau: *mut c_float,
+        ) -> c_int;
+        pub fn LAPACKE_sorgqr(
+            layout: Order,
+            m: u32,
+            n: u32,
+            k: u32,
+            a: *mut c_float,
+            lda: u32,
+            tau: *const c_float,
+        ) -> c_int;
+        pub fn LAPACKE_sgesv(
+            layout: Order,
+            n: u32,
+            nrhs: u32,
+            a: *mut c_float,
+            lda: u32,
+            ipiv: *mut c_int,
+            b: *mut c_float,
+            ldb: u32,
+        ) -> c_int;
+        pub fn LAPACKE_sgetrf(
+            layout: Order,
+            m: u32,
+            n: u32,
+            a: *mut c_float,
+            lda: u32,
+            ipiv: *mut c_int,
+        ) -> c_int;
+        pub fn LAPACKE_sgetrs(
+            layout: Order,
+            trans: c_char,
+            n: u32,
+            nrhs: u32,
+            a: *const c_float,
+            lda: u32,
+            ipiv: *const c_int,
+            b: *mut c_float,
+            ldb: u32,
+        ) -> c_int;
+        pub fn LAPACKE_sgesvd(
+            layout: Order,
+            jobu: c_char,
+            jobvt: c_char,
+            m: u32,
+            n: u32,
+            a: *mut c_float,
+            lda: u32,
+            s: *mut c_float,
+            u: *mut c_float,
+            ldu: u32,
+            vt: *mut c_float,
+            ldvt: u32,
+            superb: *mut c_float,
+        ) -> c_int;
+        pub fn LAPACKE_ssyev(
+            layout: Order,
+            jobz: c_char,
+            uplo: c_char,
+            n: u32,
+            a: *mut c_float,
+            lda: u32,
+            w: *mut c_float,
+        ) -> c_int;
+    }
+}
+
+pub mod lapacke_d {
+    use crate::attribute::Order;
+    use libc::{c_char, c_double, c_int};
+
+    pub use self::LAPACKE_dgeqrf as geqrf;
+    pub use self::LAPACKE_dgesv as gesv;
+    pub use self::LAPACKE_dgesvd as gesvd;
+    pub use self::LAPACKE_dgetrf as getrf;
+    pub use self::LAPACKE_dgetrs as getrs;
+    pub use self::LAPACKE_dorgqr as orgqr;
+    pub use self::LAPACKE_dsyev as syev;
+
+    extern "C" {
+        pub fn LAPACKE_dgeqrf(
+            layout: Order,
+            m: u32,
+            n: u32,
+            a: *mut c_double,
+            lda: u32,
+            tau: *mut c_double,
+        ) -> c_int;
+        pub fn LAPACKE_dorgqr(
+            layout: Order,
+            m: u32,
+            n: u32,
+            k: u32,
+            a: *mut c_double,
+            lda: u32,
+            tau: *const c_double,
+        ) -> c_int;
+        pub fn LAPACKE_dgesv(
+            layout: Order,
+            n: u32,
+            nrhs: u32,
+            a: *mut c_double,
+            lda: u32,
+            ipiv: *mut c_int,
+            b: *mut c_double,
+            ldb: u32,
+        ) -> c_int;
+        pub fn LAPACKE_dgetrf(
+            layout: Order,
+            m: u32,
+            n: u32,
+            a: *mut c_double,
+            lda: u32,
+            ipiv: *mut c_int,
+        ) -> c_int;
+        pub fn LAPACKE_dgetrs(
+            layout: Order,
+            trans: c_char,
+            n: u32,
+            nrhs: u32,
+            a: *const c_double,
+            lda: u32,
+            ipiv: *const c_int,
+            b: *mut c_double,
+            ldb: u32,
+        ) -> c_int;
+        pub fn LAPACKE_dgesvd(
+            layout: Order,
+            jobu: c_char,
+            jobvt: c_char,
+            m: u32,
+            n: u32,
+            a: *mut c_double,
+            lda: u32,
+            s: *mut c_double,
+            u: *mut c_double,
+            ldu: u32,
+            vt: *mut c_double,
+            ldvt: u32,
+            superb: *mut c_double,
+        ) -> c_int;
+        pub fn LAPACKE_dsyev(
+            layout: Order,
+            jobz: c_char,
+            uplo: c_char,
+            n: u32,
+            a: *mut c_double,
+            lda: u32,
+            w: *mut c_double,
+        ) -> c_int;
+    }
+}
+
+pub mod lapacke_c {
+    use crate::attribute::Order;
+    use libc::{c_char, c_float, c_int, c_void};
+
+    pub use self::LAPACKE_cgeqrf as geqrf;
+    pub use self::LAPACKE_cheev as heev;
+    pub use self::LAPACKE_cungqr as ungqr;
+
+    extern "C" {
+        pub fn LAPACKE_cgeqrf(
+            layout: Order,
+            m: u32,
+            n: u32,
+            a: *mut c_void,
+            lda: u32,
+            tau: *mut c_void,
+        ) -> c_int;
+        pub fn LAPACKE_cungqr(
+            layout: Order,
+            m: u32,
+            n: u32,
+            k: u32,
+            a: *mut c_void,
+            lda: u32,
+            tau: *const c_void,
+        ) -> c_int;
+        pub fn LAPACKE_cheev(
+            layout: Order,
+            jobz: c_char,
+            uplo: c_char,
+            n: u32,
+            a: *mut c_void,
+            lda: u32,
+            w: *mut c_float,
+        ) -> c_int;
+    }
+}
+
+pub mod lapacke_z {
+    use crate::attribute::Order;
+    use libc::{c_char, c_double, c_int, c_void};
+
+    pub use self::LAPACKE_zgeqrf as geqrf;
+    pub use self::LAPACKE_zheev as heev;
+    pub use self::LAPACKE_zungqr as ungqr;
+
+    extern "C" {
+        pub fn LAPACKE_zgeqrf(
+            layout: Order,
+            m: u32,
+            n: u32,
+            a: *mut c_void,
+            lda: u32,
+            tau: *mut c_void,
+        ) -> c_int;
+        pub fn LAPACKE_zungqr(
+            layout: Order,
+            m: u32,
+            n: u32,
+            k: u32,
+            a: *mut c_void,
+            lda: u32,
+            tau: *const c_void,
+        ) -> c_int;
+        pub fn LAPACKE_zheev(
+            layout: Order,
+            jobz: c_char,
+            uplo: c_char,
+            n: u32,
+            a: *mut c_void,
+            lda: u32,
+            w: *mut c_double,
+        ) -> c_int;
+    }
+}
@@ -0,0 +1,18 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! LAPACK routines, through the LAPACKE C interface.
+//!
+//! Only available with the `lapack` feature. The layout argument of LAPACKE
+//! uses the same values as the CBLAS `Order`, so matrices are passed with
+//! their own order and leading dimension just like the BLAS wrappers do.
+
+pub mod ll;
+
+macro_rules! lapacke(
+    (f32, $f: ident) => (crate::lapack::ll::lapacke_s::$f);
+    (f64, $f: ident) => (crate::lapack::ll::lapacke_d::$f);
+    (Complex32, $f: ident) => (crate::lapack::ll::lapacke_c::$f);
+    (Complex64, $f: ident) => (crate::lapack::ll::lapacke_z::$f);
+);
@@ -29,8 +29,13 @@ mod prefix;
 mod pointer;
 mod scalar;
 
+pub mod aligned;
 pub mod attribute;
 pub mod default;
+pub mod error;
+#[cfg(feature = "lapack")]
+#[macro_use]
+pub mod lapack;
 pub mod matrix;
 pub mod matrix_vector;
 pub mod vector;
@@ -1,11 +1,14 @@
 // Copyright 2015 Michael Yang. All rights reserved.
 // Use of this source code is governed by a MIT-style
 // license that can be found in the LICENSE file.
+use crate::attribute::Order;
 use crate::math::Mat;
-use crate::matrix::BandMatrix;
+use crate::matrix::{row, row_mut, BandMatrix};
 use crate::vector::ops::Copy;
+use crate::vector::Strided;
 use crate::Matrix;
 use num::traits::NumCast;
+use num_complex::{Complex32, Complex64};
 use std::cmp::{max, min};
 use std::fmt;
 use std::fmt::Debug;
@@ -160,7 +163,10 @@ impl<T: std::marker::Copy> BandMat<T> {
 
             let i = (r * lda) + bandmat_offset;
             let i = i as usize;
-            (&mut v).copy_within(s..e, i);
+            // Rows of a tall matrix can lie entirely below the band.
+            if s < e {
+                (&mut v).copy_within(s..e, i);
+            }
         }
 
         BandMat {
@@ -204,42 +210,21 @@ impl<T: std::marker::Copy + Default> BandMat<T> {
         }
         let mut v = unsafe { Vec::from_raw_parts(bandmat.as_mut_ptr(), length, length) };
 
-        let num_of_last_row_terms = kl + 1 - (rows - min(rows, cols));
-
         /*
-         * Refer to the `from_matrix` method for explanations of the meanings of the variables, but
-         * now with respect to the band matrix. That is, s now represents the start point in the
-         * band matrix for a particular row. The offset variable just inverts the index of the row
-         * (if we have a total of 10 rows, row 7 will have offset 3).
-         *
-         * We have to iterate on the rows in reverse order, because we need to be careful not to
-         * overwrite anything from the space of the original band matrix and lose values.
+         * Element (r, j) of the band, for j from `first` to `last`, lives at `r * lda + kl + j - r`
+         * in band storage and moves to `r * cols + j`. Since `lda <= cols` a row never moves
+         * before its own start, so iterating on the rows in reverse order never overwrites band
+         * values that are still needed. Everything outside the band is then filled with "0".
          */
         for r in (0..rows).rev() {
-            let offset = rows - r - 1;
-
-            let s = max(
-                0,
-                -(kl as isize + 1)
-                    + (num_of_last_row_terms - (if rows > cols { 1 } else { 2 })) as isize
-                    + offset as isize,
-            );
-            let s = (r * lda) as isize + s;
-            let s = s as usize;
-
-            let e = min(lda, num_of_last_row_terms + offset);
-            let e = (r * lda) + e;
-
-            let original_mat_offset =
-                cols as isize - num_of_last_row_terms as isize - offset as isize;
-            let i = (r * cols) + max(0, original_mat_offset) as usize;
-
-            v.copy_within(s..e, i);
-
-            // Fill the rest of the values for that row with "0"
-            let l = e - s;
-            let zero_range = (r * cols)..max(0, i);
-            let zero_range = zero_range.chain(min((r + 1) * cols, i + l)..((r + 1) * cols));
+            let first = r.saturating_sub(kl);
+            let last = max(first, min(cols, r + ku + 1));
+            let i = (r * cols) + first;
+
+            v.copy_within((r * lda + kl + first - r)..(r * lda + kl + last - r), i);
+
+            let zero_range = (r * cols)..i;
+            let zero_range = zero_range.chain((i + last - first)..((r + 1) * cols));
             for i in zero_range {
                 v[i] = T::default();
             }
@@ -355,11 +340,201 @@ where
             result.data.set_len(len);
         }
 
-        Copy::copy_mat(a.as_matrix(), &mut result);
+        // Band storage is not addressed by row and column, so copy it whole.
+        let data = unsafe { Strided::new(a.as_ptr(), len as u32, 1) };
+        Copy::copy(&data, &mut result.data);
         result
     }
 }
 
+/// Conversions between dense matrices and BLAS band storage.
+///
+/// For an `m x n` matrix with `kl` sub-diagonals and `ku` super-diagonals
+/// the band storage has `lda = kl + ku + 1` entries per row. In row-major
+/// order, element `(i, j)` with `i - kl <= j <= i + ku` is stored at
+/// `i * lda + (kl + j - i)`, so each row of the band storage holds that
+/// row's band left to right with the main diagonal in column `kl`:
+///
+/// ```text
+/// | a00 a01  0   0  |        |  *  a00 a01 |
+/// | a10 a11 a12  0  |   ->   | a10 a11 a12 |
+/// |  0  a21 a22 a23 |        | a21 a22 a23 |
+/// |  0   0  a32 a33 |        | a32 a33  *  |
+/// ```
+///
+/// Slots marked `*` fall outside the matrix; BLAS never reads them and
+/// their contents are unspecified. In column-major order the same element
+/// lives at `j * lda + (ku + i - j)`. This is the layout expected by `gbmv`,
+/// `sbmv` and `tbmv`.
+///
+/// The band may be wider than the matrix, as for a full band or a `2 x 2`
+/// tridiagonal matrix. Only row-major band storage can be expanded.
+pub trait ToBanded: Sized {
+    /// Packs the band of `a` into band storage. Entries outside the band are
+    /// dropped.
+    fn to_banded(a: &dyn Matrix<Self>, kl: usize, ku: usize) -> BandMat<Self>;
+    /// Expands band storage back into a dense matrix, filling everything
+    /// outside the band with zeros.
+    fn from_banded(a: &dyn BandMatrix<Self>) -> Mat<Self>;
+}
+
+/// The columns `[first, last)` of row `i` that lie inside the band.
+fn band_range(i: usize, cols: usize, kl: usize, ku: usize) -> (usize, usize) {
+    let first = min(i.saturating_sub(kl), cols);
+    let last = max(first, min(cols, i + ku + 1));
+    (first, last)
+}
+
+macro_rules! to_banded_impl(($($t: ident), +) => (
+    $(
+        impl ToBanded for $t {
+            fn to_banded(a: &dyn Matrix<$t>, kl: usize, ku: usize) -> BandMat<$t> {
+                let (rows, cols) = (a.rows() as usize, a.cols() as usize);
+                let lda = kl + ku + 1;
+                // Never shorter than `rows * cols`, which the rest of `BandMat` assumes.
+                let mut data = vec![crate::default::Default::zero(); rows * max(lda, cols)];
+                for i in 0..rows {
+                    let (first, last) = band_range(i, cols, kl, ku);
+                    let start = i * lda + kl + first - i;
+                    let src = row(a, i).skip(first as u32).take((last - first) as u32);
+                    Copy::copy(&src, &mut data[start..start + last - first]);
+                }
+
+                BandMat {
+                    rows,
+                    cols,
+                    sub_diagonals: kl as u32,
+                    sup_diagonals: ku as u32,
+                    data,
+                }
+            }
+
+            fn from_banded(a: &dyn BandMatrix<$t>) -> Mat<$t> {
+                if a.order() != Order::RowMajor {
+                    panic!("Only row-major band storage can be expanded");
+                }
+
+                let (rows, cols) = (a.rows() as usize, a.cols() as usize);
+                let (kl, ku) = (a.sub_diagonals() as usize, a.sup_diagonals() as usize);
+                let lda = a.lead_dim() as usize;
+                let mut dense = Mat::fill(crate::default::Default::zero(), rows, cols);
+                for i in 0..rows {
+                    let (first, last) = band_range(i, cols, kl, ku);
+                    let n = (last - first) as u32;
+                    let src = unsafe { Strided::new(a.as_ptr().add(i * lda + kl + first - i), n, 1) };
+                    Copy::copy(&src, &mut row_mut(&mut dense, i).skip(first as u32).take(n));
+                }
+
+                dense
+            }
+        }
+    )+
+));
+
+to_banded_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod to_banded_tests {
+    use crate::attribute::Transpose;
+    use crate::mat;
+    use crate::math::bandmat::ToBanded;
+    use crate::math::Mat;
+    use crate::matrix_vector::ops::{Gbmv, Gemv};
+    use crate::Matrix;
+
+    #[test]
+    fn tridiagonal_round_trip() {
+        let a = mat![4f64, -1.0, 0.0, 0.0;
+                     2.0, 4.0, -1.0, 0.0;
+                     0.0, 2.0, 4.0, -1.0;
+                     0.0, 0.0, 2.0, 4.0];
+
+        let band = ToBanded::to_banded(&a, 1, 1);
+        assert_eq!(band.lead_dim(), 3);
+        let packed = unsafe { std::slice::from_raw_parts(band.as_ptr(), 12) };
+        assert_eq!(
+            packed[1..11],
+            [4.0, -1.0, 2.0, 4.0, -1.0, 2.0, 4.0, -1.0, 2.0, 4.0]
+        );
+
+        let dense = ToBanded::from_banded(&band);
+        assert_eq!(dense, a);
+    }
+
+    #[test]
+    fn drops_outside_band() {
+        let a = mat![1f32, 2.0, 3.0; 4.0, 5.0, 6.0];
+
+        let band = ToBanded::to_banded(&a, 0, 1);
+        let dense = ToBanded::from_banded(&band);
+
+        assert_eq!(dense, mat![1.0, 2.0, 0.0; 0.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn rows_below_band() {
+        let a = mat![1f64, 2.0; 3.0, 4.0; 5.0, 6.0; 7.0, 8.0];
+
+        let band = ToBanded::to_banded(&a, 1, 0);
+        let dense = ToBanded::from_banded(&band);
+
+        assert_eq!(dense, mat![1.0, 0.0; 3.0, 4.0; 0.0, 6.0; 0.0, 0.0]);
+    }
+
+    #[test]
+    fn band_wider_than_matrix() {
+        let a = mat![4f64, -1.0; 2.0, 4.0];
+
+        let band = ToBanded::to_banded(&a, 1, 1);
+        assert_eq!(band.lead_dim(), 3);
+        let packed = unsafe { std::slice::from_raw_parts(band.as_ptr(), 6) };
+        assert_eq!(packed[1..5], [4.0, -1.0, 2.0, 4.0]);
+        assert_eq!(ToBanded::from_banded(&band), a);
+
+        let x = vec![1.0, 2.0];
+        let mut y = vec![0.0; 2];
+        Gbmv::gbmv(Transpose::NoTrans, &1.0, &band, &x, &0.0, &mut y);
+        assert_eq!(y, vec![2.0, 10.0]);
+    }
+
+    #[test]
+    fn tall_and_narrow() {
+        let a = mat![1f64, 2.0; 3.0, 4.0; 5.0, 6.0; 7.0, 8.0];
+        let kept = mat![1f64, 2.0; 3.0, 4.0; 5.0, 6.0; 0.0, 8.0];
+        let x = vec![1.0, -1.0];
+        let t = Transpose::NoTrans;
+
+        let band = ToBanded::to_banded(&a, 2, 1);
+        assert_eq!(band.lead_dim(), 4);
+        assert_eq!(ToBanded::from_banded(&band), kept);
+
+        let mut dense = vec![0.0; 4];
+        Gemv::gemv(t, &1.0, &kept, &x, &0.0, &mut dense);
+        let mut banded = vec![0.0; 4];
+        Gbmv::gbmv(t, &1.0, &band, &x, &0.0, &mut banded);
+        assert_eq!(banded, dense);
+    }
+
+    #[test]
+    fn gbmv() {
+        let a = mat![4f64, -1.0, 0.0, 0.0;
+                     2.0, 4.0, -1.0, 0.0;
+                     0.0, 2.0, 4.0, -1.0;
+                     0.0, 0.0, 2.0, 4.0];
+        let x = vec![1.0, 2.0, 3.0, 4.0];
+        let t = Transpose::NoTrans;
+
+        let mut dense = vec![0.0; 4];
+        Gemv::gemv(t, &1.0, &a, &x, &0.0, &mut dense);
+
+        let band = ToBanded::to_banded(&a, 1, 1);
+        let mut banded = vec![0.0; 4];
+        Gbmv::gbmv(t, &1.0, &band, &x, &0.0, &mut banded);
+
+        assert_eq!(banded, dense);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,924 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Iterative and streaming methods that only touch a matrix through
+//! products with vectors.
+
+use crate::attribute::{Symmetry, Transpose};
+use crate::error::BlasError;
+use crate::matrix::Matrix;
+use crate::matrix_vector::ops::{Gemv, Ger, Symv};
+use crate::vector::ops::{Axpy, Copy, Dot, Nrm2, Scal};
+use crate::vector::{Strided, Vector};
+use num::traits::Float;
+
+/// One step of Oja's rule for tracking the leading principal component of a
+/// stream of samples.
+///
+/// With `y = x^T w`, the update is `w += lr * y * (x - y * w)`, which is done
+/// as a `scal` of `w` by `1 - lr * y^2` and an `axpy` of `x`. `w` is then
+/// rescaled to unit length, which the rule only approaches in the limit of
+/// small `lr`. A zero `w` stays zero.
+pub trait OjaUpdate: Sized {
+    fn oja_update<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(w: &mut V, x: &W, lr: Self);
+}
+
+macro_rules! oja_update_impl(($($t: ident), +) => (
+    $(
+        impl OjaUpdate for $t {
+            fn oja_update<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(w: &mut V, x: &W, lr: $t) {
+                if w.len() != x.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let y: $t = Dot::dot(x, w);
+                Scal::scal(&(1.0 - lr * y * y), w);
+                Axpy::axpy(&(lr * y), x, w);
+
+                let norm: $t = Nrm2::nrm2(w);
+                if norm > 0.0 {
+                    Scal::scal(&norm.recip(), w);
+                }
+            }
+        }
+    )+
+));
+
+oja_update_impl!(f32, f64);
+
+/// Computes `y = alpha * x + y` and returns the Euclidean norm of the new
+/// `y`.
+///
+/// For unit strides both happen in a single pass over memory, keeping the
+/// sum of squares scaled like the reference `nrm2` so it neither overflows
+/// nor underflows. Other strides fall back to an `axpy` followed by an
+/// `nrm2`.
+pub trait AxpyNrm2: Sized {
+    fn axpy_nrm2<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        alpha: &Self,
+        x: &V,
+        y: &mut W,
+    ) -> Self;
+}
+
+macro_rules! axpy_nrm2_impl(($($t: ident), +) => (
+    $(
+        impl AxpyNrm2 for $t {
+            fn axpy_nrm2<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(alpha: &$t, x: &V, y: &mut W) -> $t {
+                if x.len() != y.len() {
+                    panic!("Dimension mismatch");
+                }
+                if x.inc() != 1 || y.inc() != 1 {
+                    Axpy::axpy(alpha, x, y);
+                    return Nrm2::nrm2(y);
+                }
+
+                let x = unsafe { Strided::new(x.as_ptr(), x.len(), 1) };
+                let mut y = unsafe { Strided::new(y.as_mut_ptr(), y.len(), 1) };
+
+                // The norm is scale * sqrt(ssq).
+                let (mut scale, mut ssq): ($t, $t) = (0.0, 1.0);
+                for i in 0..y.len() {
+                    let v = alpha * x.get(i) + y.get(i);
+                    y.set(i, v);
+
+                    if v != 0.0 {
+                        let a = v.abs();
+                        if scale < a {
+                            ssq = 1.0 + ssq * (scale / a) * (scale / a);
+                            scale = a;
+                        } else {
+                            ssq += (a / scale) * (a / scale);
+                        }
+                    }
+                }
+
+                scale * ssq.sqrt()
+            }
+        }
+    )+
+));
+
+axpy_nrm2_impl!(f32, f64);
+
+/// Applies the degree `degree` Chebyshev polynomial filter of `a` to `x`.
+///
+/// With `bounds = (lo, hi)` mapped onto `[-1, 1]`, `out = T_n(B) x` where
+/// `B = (A - c I) / e`, `c = (hi + lo) / 2` and `e = (hi - lo) / 2`.
+/// Eigencomponents with eigenvalues inside `bounds` are kept at most their
+/// size, while those outside grow quickly with the degree, which is how
+/// Chebyshev-filtered subspace iteration picks out the wanted end of the
+/// spectrum. Each step of the three-term recurrence
+/// `y_{k+1} = 2 B y_k - y_{k-1}` is one `gemv` and one `axpy`.
+pub trait ChebyshevFilter: Sized {
+    fn chebyshev_filter<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        a: &dyn Matrix<Self>,
+        x: &V,
+        degree: usize,
+        bounds: (Self, Self),
+        out: &mut W,
+    );
+}
+
+macro_rules! chebyshev_filter_impl(($($t: ident), +) => (
+    $(
+        impl ChebyshevFilter for $t {
+            fn chebyshev_filter<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(a: &dyn Matrix<$t>, x: &V, degree: usize, bounds: ($t, $t), out: &mut W) {
+                if a.rows() != a.cols() || a.cols() != x.len() || x.len() != out.len() {
+                    panic!("Dimension mismatch");
+                }
+                let (lo, hi) = bounds;
+                if lo >= hi {
+                    panic!("Empty filter interval ({}, {})", lo, hi);
+                }
+
+                let (c, e) = ((hi + lo) / 2.0, (hi - lo) / 2.0);
+                let n = x.len() as usize;
+                let mut prev = vec![0.0; n];
+                Copy::copy(x, &mut prev);
+                if degree == 0 {
+                    Copy::copy(&prev, out);
+                    return;
+                }
+
+                let mut cur = vec![0.0; n];
+                Gemv::gemv(Transpose::NoTrans, &e.recip(), a, &prev, &0.0, &mut cur);
+                Axpy::axpy(&(-c / e), &prev, &mut cur);
+                for _ in 1..degree {
+                    // prev becomes y_{k+1} = 2 B y_k - y_{k-1}
+                    Gemv::gemv(Transpose::NoTrans, &(2.0 / e), a, &cur, &-1.0, &mut prev);
+                    Axpy::axpy(&(-2.0 * c / e), &cur, &mut prev);
+                    std::mem::swap(&mut prev, &mut cur);
+                }
+
+                Copy::copy(&cur, out);
+            }
+        }
+    )+
+));
+
+chebyshev_filter_impl!(f32, f64);
+
+/// One Arnoldi iteration, the inner step of GMRES.
+///
+/// `v` holds the orthonormal basis built so far, starting from the
+/// normalized right-hand side. The last basis vector is multiplied by `a`
+/// with `gemv`, and the product is orthogonalized against every basis vector
+/// in turn by modified Gram-Schmidt (`dot` and `axpy`). `h` is overwritten
+/// with the new column of the Hessenberg matrix: the `v.len()` projection
+/// coefficients followed by the norm of the remainder. Returns the remainder
+/// normalized to unit length, the next basis vector. On breakdown, when the
+/// Krylov space is invariant, the last entry of `h` is zero and so is the
+/// returned vector.
+pub trait ArnoldiStep: Sized {
+    fn arnoldi_step(a: &dyn Matrix<Self>, v: &[Vec<Self>], h: &mut Vec<Self>) -> Vec<Self>;
+}
+
+macro_rules! arnoldi_step_impl(($($t: ident), +) => (
+    $(
+        impl ArnoldiStep for $t {
+            fn arnoldi_step(a: &dyn Matrix<$t>, v: &[Vec<$t>], h: &mut Vec<$t>) -> Vec<$t> {
+                let n = a.rows() as usize;
+                let last = match v.last() {
+                    Some(last) => last,
+                    None => panic!("Arnoldi step needs a starting vector"),
+                };
+                if a.cols() as usize != n || v.iter().any(|q| q.len() != n) {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut w = vec![0.0; n];
+                Gemv::gemv(Transpose::NoTrans, &1.0, a, last, &0.0, &mut w);
+
+                h.clear();
+                for q in v {
+                    let coeff: $t = Dot::dot(q, &w);
+                    Axpy::axpy(&-coeff, q, &mut w);
+                    h.push(coeff);
+                }
+
+                let norm: $t = Nrm2::nrm2(&w);
+                h.push(norm);
+                if norm > 0.0 {
+                    Scal::scal(&norm.recip(), &mut w);
+                }
+
+                w
+            }
+        }
+    )+
+));
+
+arnoldi_step_impl!(f32, f64);
+
+/// One Lanczos iteration, tridiagonalizing a symmetric matrix.
+///
+/// With `w = A v_curr` from `symv` (only the upper triangle of `a` is read),
+/// `alpha = w.v_curr`, and `w - alpha v_curr - beta_prev v_prev` has norm
+/// `beta`; normalized, it is the next Lanczos vector. Returns
+/// `(alpha, beta, v_next)`. Start with `v_prev` zero and `beta_prev` zero.
+/// The `alpha`s and `beta`s are the diagonal and off-diagonal of the
+/// tridiagonal matrix whose eigenvalues approximate those of `a`. In
+/// floating point the vectors slowly lose orthogonality, which this step
+/// does nothing to correct. If `beta` is zero the Krylov space is invariant
+/// and `v_next` is returned unnormalized.
+pub trait LanczosStep: Sized {
+    fn lanczos_step<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        a: &dyn Matrix<Self>,
+        v_prev: &V,
+        v_curr: &W,
+        beta_prev: Self,
+    ) -> (Self, Self, Vec<Self>);
+}
+
+macro_rules! lanczos_step_impl(($($t: ident), +) => (
+    $(
+        impl LanczosStep for $t {
+            fn lanczos_step<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(a: &dyn Matrix<$t>, v_prev: &V, v_curr: &W, beta_prev: $t) -> ($t, $t, Vec<$t>) {
+                let n = a.rows() as usize;
+                if a.cols() as usize != n || v_prev.len() as usize != n || v_curr.len() as usize != n {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut w = vec![0.0; n];
+                Symv::symv(Symmetry::Upper, &1.0, a, v_curr, &0.0, &mut w);
+                let alpha: $t = Dot::dot(&w, v_curr);
+                Axpy::axpy(&-alpha, v_curr, &mut w);
+                Axpy::axpy(&-beta_prev, v_prev, &mut w);
+
+                let beta: $t = Nrm2::nrm2(&w);
+                if beta > 0.0 {
+                    Scal::scal(&beta.recip(), &mut w);
+                }
+
+                (alpha, beta, w)
+            }
+        }
+    )+
+));
+
+lanczos_step_impl!(f32, f64);
+
+/// One recursive least squares update for a new observation `x -> target`.
+///
+/// `p` is the inverse correlation matrix, symmetric, and `w` the current
+/// weights. With forgetting factor `lambda` (1 weights all observations
+/// equally, smaller values forget old ones), the gain is
+/// `k = P x / (lambda + x^T P x)`, the weights move by the prediction error
+/// `target - w^T x` along `k`, and `P = (P - k x^T P) / lambda`. That is a
+/// `gemv`, two `dot`s, an `axpy`, and a `ger` followed by a `scal`.
+pub trait RlsUpdate: Sized {
+    fn rls_update<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        p: &mut dyn Matrix<Self>,
+        w: &mut V,
+        x: &W,
+        target: Self,
+        lambda: Self,
+    );
+}
+
+macro_rules! rls_update_impl(($($t: ident), +) => (
+    $(
+        impl RlsUpdate for $t {
+            fn rls_update<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(p: &mut dyn Matrix<$t>, w: &mut V, x: &W, target: $t, lambda: $t) {
+                if p.rows() != p.cols() || p.rows() != x.len() || w.len() != x.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut px = vec![0.0; x.len() as usize];
+                Gemv::gemv(Transpose::NoTrans, &1.0, p, x, &0.0, &mut px);
+                let denom = lambda + Dot::dot(x, &px);
+                let error = target - Dot::dot(w, x);
+
+                Axpy::axpy(&(error / denom), &px, w);
+                Ger::ger(&-denom.recip(), &px, &px, p);
+                Scal::scal_mat(&lambda.recip(), p);
+            }
+        }
+    )+
+));
+
+rls_update_impl!(f32, f64);
+
+/// Decides when an iterative solver stops, from the norm of the current
+/// residual and the number of iterations completed so far.
+///
+/// Solvers ask before the first iteration too, with `iter` zero.
+pub trait StoppingCriterion<T> {
+    fn should_stop(&self, residual_norm: T, iter: usize) -> bool;
+}
+
+/// Stops once the residual norm is at most `tol`.
+pub struct AbsoluteTolerance<T> {
+    tol: T,
+}
+
+impl<T> AbsoluteTolerance<T> {
+    pub fn new(tol: T) -> AbsoluteTolerance<T> {
+        AbsoluteTolerance { tol }
+    }
+}
+
+impl<T: PartialOrd> StoppingCriterion<T> for AbsoluteTolerance<T> {
+    fn should_stop(&self, residual_norm: T, _: usize) -> bool {
+        residual_norm <= self.tol
+    }
+}
+
+/// Stops once the residual norm is at most `tol` times `reference`,
+/// usually the norm of the right-hand side.
+pub struct RelativeTolerance<T> {
+    tol: T,
+    reference: T,
+}
+
+impl<T> RelativeTolerance<T> {
+    pub fn new(tol: T, reference: T) -> RelativeTolerance<T> {
+        RelativeTolerance { tol, reference }
+    }
+}
+
+impl<T: Float> StoppingCriterion<T> for RelativeTolerance<T> {
+    fn should_stop(&self, residual_norm: T, _: usize) -> bool {
+        residual_norm <= self.tol * self.reference
+    }
+}
+
+/// Stops after `max_iter` iterations, whatever the residual.
+pub struct MaxIterations {
+    max_iter: usize,
+}
+
+impl MaxIterations {
+    pub fn new(max_iter: usize) -> MaxIterations {
+        MaxIterations { max_iter }
+    }
+}
+
+impl<T> StoppingCriterion<T> for MaxIterations {
+    fn should_stop(&self, _: T, iter: usize) -> bool {
+        iter >= self.max_iter
+    }
+}
+
+/// Stops as soon as any of the criteria does.
+pub struct AnyOf<'a, T> {
+    criteria: &'a [&'a dyn StoppingCriterion<T>],
+}
+
+impl<'a, T> AnyOf<'a, T> {
+    pub fn new(criteria: &'a [&'a dyn StoppingCriterion<T>]) -> AnyOf<'a, T> {
+        AnyOf { criteria }
+    }
+}
+
+impl<'a, T: std::marker::Copy> StoppingCriterion<T> for AnyOf<'a, T> {
+    fn should_stop(&self, residual_norm: T, iter: usize) -> bool {
+        self.criteria
+            .iter()
+            .any(|c| c.should_stop(residual_norm, iter))
+    }
+}
+
+/// Solves `A x = b` for a general square `A` by BiCGStab, starting from the
+/// value of `x` passed in.
+///
+/// Each iteration is two `gemv`s plus `dot`, `axpy`, `scal` and `nrm2`
+/// calls on vectors. Returns `NoConvergence` if the method breaks down on a
+/// vanishing denominator; `x` then holds the last iterate.
+pub trait Bicgstab: Sized {
+    /// Stops once the residual norm is at most `tol` times the norm of `b`
+    /// and returns the number of iterations taken, zero if the starting `x`
+    /// already solves the system. Returns `NoConvergence` if `max_iter`
+    /// iterations are not enough.
+    fn bicgstab<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        a: &dyn Matrix<Self>,
+        b: &V,
+        x: &mut W,
+        max_iter: usize,
+        tol: Self,
+    ) -> Result<usize, BlasError>;
+    /// Iterates until `stop` says to, and returns the number of iterations
+    /// taken and the norm of the final residual.
+    ///
+    /// The residual is checked after each half step as well, with the
+    /// number of iterations completed before it, so `MaxIterations` lets
+    /// the last iteration finish. Stopping at a half step still counts the
+    /// iteration as taken. `stop` has to end the loop eventually, for
+    /// example by including `MaxIterations`.
+    fn bicgstab_until<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        a: &dyn Matrix<Self>,
+        b: &V,
+        x: &mut W,
+        stop: &dyn StoppingCriterion<Self>,
+    ) -> Result<(usize, Self), BlasError>;
+}
+
+macro_rules! bicgstab_impl(($($t: ident), +) => (
+    $(
+        impl Bicgstab for $t {
+            fn bicgstab<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(a: &dyn Matrix<$t>, b: &V, x: &mut W, max_iter: usize, tol: $t) -> Result<usize, BlasError> {
+                let relative = RelativeTolerance::new(tol, Nrm2::nrm2(b));
+                let limit = MaxIterations::new(max_iter);
+                let criteria: [&dyn StoppingCriterion<$t>; 2] = [&relative, &limit];
+
+                let (iters, residual) = Bicgstab::bicgstab_until(a, b, x, &AnyOf::new(&criteria))?;
+                if relative.should_stop(residual, iters) {
+                    Ok(iters)
+                } else {
+                    Err(BlasError::NoConvergence)
+                }
+            }
+
+            fn bicgstab_until<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(a: &dyn Matrix<$t>, b: &V, x: &mut W, stop: &dyn StoppingCriterion<$t>) -> Result<(usize, $t), BlasError> {
+                let n = a.rows() as usize;
+                if a.cols() as usize != n {
+                    return Err(BlasError::DimensionMismatch { expected: n, found: a.cols() as usize });
+                }
+                if b.len() as usize != n || x.len() as usize != n {
+                    return Err(BlasError::DimensionMismatch { expected: n, found: b.len().max(x.len()) as usize });
+                }
+
+                let nt = Transpose::NoTrans;
+                let mut r = vec![0.0; n];
+                Copy::copy(b, &mut r);
+                Gemv::gemv(nt, &-1.0, a, x, &1.0, &mut r);
+                let mut residual: $t = Nrm2::nrm2(&r);
+                if stop.should_stop(residual, 0) {
+                    return Ok((0, residual));
+                }
+
+                let r0 = r.clone();
+                let r0_norm = residual;
+                let (mut rho, mut alpha, mut omega): ($t, $t, $t) = (1.0, 1.0, 1.0);
+                let (mut p, mut v, mut t) = (vec![0.0; n], vec![0.0; n], vec![0.0; n]);
+                let mut iter = 0;
+                loop {
+                    iter += 1;
+                    let rho_next: $t = Dot::dot(&r0, &r);
+                    if rho_next.abs() <= <$t>::EPSILON * r0_norm * residual {
+                        return Err(BlasError::NoConvergence);
+                    }
+
+                    // p = r + beta * (p - omega * v)
+                    let beta = (rho_next / rho) * (alpha / omega);
+                    Axpy::axpy(&-omega, &v, &mut p);
+                    Scal::scal(&beta, &mut p);
+                    Axpy::axpy(&1.0, &r, &mut p);
+                    rho = rho_next;
+
+                    Gemv::gemv(nt, &1.0, a, &p, &0.0, &mut v);
+                    let denom: $t = Dot::dot(&r0, &v);
+                    if denom.abs() <= <$t>::EPSILON * r0_norm * Nrm2::nrm2(&v) {
+                        return Err(BlasError::NoConvergence);
+                    }
+                    alpha = rho / denom;
+
+                    // s = r - alpha * v, kept in r.
+                    Axpy::axpy(&-alpha, &v, &mut r);
+                    Axpy::axpy(&alpha, &p, x);
+                    // Only the full iterations count towards a limit.
+                    residual = Nrm2::nrm2(&r);
+                    if stop.should_stop(residual, iter - 1) {
+                        return Ok((iter, residual));
+                    }
+
+                    Gemv::gemv(nt, &1.0, a, &r, &0.0, &mut t);
+                    let tt: $t = Dot::dot(&t, &t);
+                    if tt == 0.0 {
+                        return Err(BlasError::NoConvergence);
+                    }
+                    omega = Dot::dot(&t, &r) / tt;
+                    if omega == 0.0 {
+                        return Err(BlasError::NoConvergence);
+                    }
+
+                    Axpy::axpy(&omega, &r, x);
+                    Axpy::axpy(&-omega, &t, &mut r);
+                    residual = Nrm2::nrm2(&r);
+                    if stop.should_stop(residual, iter) {
+                        return Ok((iter, residual));
+                    }
+                }
+            }
+        }
+    )+
+));
+
+bicgstab_impl!(f32, f64);
+
+#[cfg(test)]
+mod oja_update_tests {
+    use crate::math::iterative::OjaUpdate;
+
+    #[test]
+    fn dominant_direction() {
+        let (d, perp) = ([0.6f64, 0.8], [-0.8f64, 0.6]);
+        let mut w = vec![1f64, 0.0];
+
+        // A fixed linear congruential sequence keeps the test deterministic.
+        let mut state = 12345u64;
+        let mut uniform = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+        };
+
+        for _ in 0..2000 {
+            let (a, b) = (3.0 * uniform(), 0.3 * uniform());
+            let x = vec![a * d[0] + b * perp[0], a * d[1] + b * perp[1]];
+            OjaUpdate::oja_update(&mut w, &x, 0.01);
+        }
+
+        let norm = (w[0] * w[0] + w[1] * w[1]).sqrt();
+        let alignment = (w[0] * d[0] + w[1] * d[1]).abs();
+        assert!((norm - 1.0).abs() < 1e-12);
+        assert!(alignment > 0.999, "alignment {}", alignment);
+    }
+
+    #[test]
+    fn single_step() {
+        let mut w = vec![1f32, 0.0];
+        let x = vec![1f32, 1.0];
+
+        // y = 1, so w becomes (1 - 0.5) * [1, 0] + 0.5 * [1, 1] = [1, 0.5],
+        // then is normalized.
+        OjaUpdate::oja_update(&mut w, &x, 0.5);
+        let n = 1.25f32.sqrt();
+        assert!((w[0] - 1.0 / n).abs() < 1e-6);
+        assert!((w[1] - 0.5 / n).abs() < 1e-6);
+    }
+}
+
+#[cfg(test)]
+mod axpy_nrm2_tests {
+    use crate::math::iterative::AxpyNrm2;
+    use crate::vector::ops::Nrm2;
+    use crate::vector::Strided;
+
+    #[test]
+    fn real() {
+        let x = vec![1f64, -2.0, 0.5, 3.0];
+        let mut y = vec![2f64, 1.0, 0.0, -4.0];
+
+        let norm = AxpyNrm2::axpy_nrm2(&2.0, &x, &mut y);
+        assert_eq!(y, vec![4.0, -3.0, 1.0, 2.0]);
+        assert!((norm - Nrm2::nrm2(&y)).abs() < 1e-12);
+        assert!((norm - 30f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn no_overflow() {
+        let x = vec![f32::MAX / 2.0; 2];
+        let mut y = vec![0f32; 2];
+
+        let norm = AxpyNrm2::axpy_nrm2(&1.0, &x, &mut y);
+        assert!(norm.is_finite());
+        assert!((norm / Nrm2::nrm2(&y) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn strided() {
+        let x = [3f32, 9.0, 4.0, 9.0];
+        let mut y = vec![0f32; 2];
+        let evens = unsafe { Strided::new(x.as_ptr(), 2, 2) };
+
+        let norm = AxpyNrm2::axpy_nrm2(&1.0, &evens, &mut y);
+        assert_eq!(y, vec![3.0, 4.0]);
+        assert_eq!(norm, 5.0);
+    }
+}
+
+#[cfg(test)]
+mod chebyshev_filter_tests {
+    use crate::math::iterative::ChebyshevFilter;
+    use crate::math::Mat;
+
+    fn diag(d: &[f64]) -> Mat<f64> {
+        let n = d.len();
+        let entries = (0..n * n).map(|e| if e % (n + 1) == 0 { d[e / n] } else { 0.0 });
+        Mat::new_from_data(n, n, entries.collect())
+    }
+
+    #[test]
+    fn amplifies_outside_bounds() {
+        let d = [0.1, 0.5, 0.9, 3.0];
+        let a = diag(&d);
+        let x = vec![1f64; 4];
+        let mut out = vec![0f64; 4];
+
+        ChebyshevFilter::chebyshev_filter(&a, &x, 5, (0.0, 1.0), &mut out);
+        for (o, l) in out.iter().zip(&d) {
+            // T_5 at the eigenvalue mapped onto [-1, 1].
+            let t = 2.0 * l - 1.0;
+            let expected = if t.abs() <= 1.0 {
+                (5.0 * t.acos()).cos()
+            } else {
+                (5.0 * t.acosh()).cosh()
+            };
+            assert!((o - expected).abs() < 1e-9 * expected.abs().max(1.0));
+        }
+        assert!(out[..3].iter().all(|o| o.abs() <= 1.0 + 1e-12));
+        assert!(out[3] > 1000.0);
+    }
+
+    #[test]
+    fn low_degrees() {
+        let a = diag(&[2.0, 4.0]);
+        let x = vec![1f64, -1.0];
+        let mut out = vec![0f64; 2];
+
+        ChebyshevFilter::chebyshev_filter(&a, &x, 0, (0.0, 2.0), &mut out);
+        assert_eq!(out, x);
+
+        ChebyshevFilter::chebyshev_filter(&a, &x, 1, (0.0, 2.0), &mut out);
+        assert_eq!(out, vec![1.0, -3.0]);
+    }
+}
+
+#[cfg(test)]
+mod arnoldi_step_tests {
+    use crate::mat;
+    use crate::math::iterative::ArnoldiStep;
+    use crate::math::Mat;
+
+    #[test]
+    fn orthonormal_basis() {
+        let a =
+            mat![4f64, 1.0, 0.0, 2.0; -1.0, 3.0, 1.0, 0.0; 0.5, 0.0, 2.0, 1.0; 1.0, -2.0, 0.0, 1.0];
+        let mut v = vec![vec![0.5f64; 4]];
+        let mut columns = vec![];
+
+        for _ in 0..3 {
+            let mut h = vec![];
+            let next = ArnoldiStep::arnoldi_step(&a, &v, &mut h);
+            assert_eq!(h.len(), v.len() + 1);
+            columns.push(h);
+            v.push(next);
+        }
+
+        for (i, p) in v.iter().enumerate() {
+            for (j, q) in v.iter().enumerate() {
+                let d: f64 = p.iter().zip(q).map(|(x, y)| x * y).sum();
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((d - expected).abs() < 1e-12);
+            }
+        }
+
+        // A v_k = sum_j h_jk v_j, the Arnoldi relation.
+        for (k, h) in columns.iter().enumerate() {
+            for r in 0..4 {
+                let av: f64 = (0..4).map(|c| a[r][c] * v[k][c]).sum();
+                let hv: f64 = h.iter().enumerate().map(|(j, hj)| hj * v[j][r]).sum();
+                assert!((av - hv).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn breakdown() {
+        let a = mat![2f32, 0.0; 0.0, 3.0];
+        let v = vec![vec![1f32, 0.0]];
+        let mut h = vec![];
+
+        let next = ArnoldiStep::arnoldi_step(&a, &v, &mut h);
+        assert_eq!(h, vec![2.0, 0.0]);
+        assert_eq!(next, vec![0.0, 0.0]);
+    }
+}
+
+#[cfg(test)]
+mod lanczos_step_tests {
+    use crate::mat;
+    use crate::math::iterative::LanczosStep;
+    use crate::math::Mat;
+
+    #[test]
+    fn orthonormal() {
+        let a = mat![
+            4f64, 1.0, 0.5, 0.0;
+            1.0, 3.0, -1.0, 0.2;
+            0.5, -1.0, 2.0, 1.0;
+            0.0, 0.2, 1.0, 1.0
+        ];
+        let mut vs = vec![vec![0f64; 4], vec![0.5f64; 4]];
+        let (mut alphas, mut beta) = (vec![], 0.0);
+        for k in 1..4 {
+            let (alpha, next_beta, v) = LanczosStep::lanczos_step(&a, &vs[k - 1], &vs[k], beta);
+            assert!(next_beta > 0.0);
+            alphas.push(alpha);
+            beta = next_beta;
+            vs.push(v);
+        }
+
+        let basis = &vs[1..];
+        for (i, p) in basis.iter().enumerate() {
+            for (j, q) in basis.iter().enumerate() {
+                let dot: f64 = p.iter().zip(q).map(|(x, y)| x * y).sum();
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot - expected).abs() < 1e-10);
+            }
+        }
+
+        // alpha is the Rayleigh quotient of the current vector.
+        let v = &basis[0];
+        let av: Vec<f64> = (0..4)
+            .map(|i| (0..4).map(|j| a[i][j] * v[j]).sum())
+            .collect();
+        let rayleigh: f64 = av.iter().zip(v).map(|(x, y)| x * y).sum();
+        assert!((alphas[0] - rayleigh).abs() < 1e-12);
+    }
+
+    #[test]
+    fn invariant_subspace() {
+        let a = mat![2f32, 0.0; 0.0, 3.0];
+        let v0 = vec![0f32; 2];
+        let v1 = vec![1f32, 0.0];
+
+        let (alpha, beta, _) = LanczosStep::lanczos_step(&a, &v0, &v1, 0.0);
+        assert_eq!((alpha, beta), (2.0, 0.0));
+    }
+}
+
+#[cfg(test)]
+mod rls_update_tests {
+    use crate::math::iterative::RlsUpdate;
+    use crate::math::Mat;
+
+    #[test]
+    fn converges() {
+        // y = 2 x0 - 3 x1 + 0.5
+        let truth = [2f64, -3.0, 0.5];
+        let mut p = Mat::new_from_data(3, 3, vec![1e6, 0.0, 0.0, 0.0, 1e6, 0.0, 0.0, 0.0, 1e6]);
+        let mut w = vec![0f64; 3];
+
+        for k in 0..50 {
+            let x = vec![(k as f64 * 0.7).sin(), (k as f64 * 1.3).cos(), 1.0];
+            let y: f64 = x.iter().zip(&truth).map(|(a, b)| a * b).sum();
+            RlsUpdate::rls_update(&mut p, &mut w, &x, y, 1.0);
+        }
+
+        for (wi, ti) in w.iter().zip(&truth) {
+            assert!((wi - ti).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn forgetting() {
+        // The model changes after 100 samples; forgetting lets the weight
+        // follow.
+        let mut p = Mat::new_from_data(1, 1, vec![100f64]);
+        let mut w = vec![0f64];
+
+        for k in 0..300 {
+            let slope = if k < 100 { 1.0 } else { -1.0 };
+            let x = vec![1.0 + (k % 3) as f64];
+            RlsUpdate::rls_update(&mut p, &mut w, &x, slope * x[0], 0.9);
+        }
+
+        assert!((w[0] + 1.0).abs() < 1e-6);
+    }
+}
+
+#[cfg(test)]
+mod stopping_criterion_tests {
+    use crate::math::iterative::{
+        AbsoluteTolerance, AnyOf, MaxIterations, RelativeTolerance, StoppingCriterion,
+    };
+
+    #[test]
+    fn single() {
+        assert!(AbsoluteTolerance::new(1e-3).should_stop(1e-4f64, 0));
+        assert!(!AbsoluteTolerance::new(1e-3).should_stop(1e-2f64, 100));
+
+        let relative = RelativeTolerance::new(1e-3f32, 10.0);
+        assert!(relative.should_stop(0.01, 0));
+        assert!(!relative.should_stop(0.1, 0));
+
+        let limit = MaxIterations::new(3);
+        assert!(!StoppingCriterion::<f64>::should_stop(&limit, 1.0, 2));
+        assert!(StoppingCriterion::<f64>::should_stop(&limit, 1.0, 3));
+    }
+
+    #[test]
+    fn any_of() {
+        let relative = RelativeTolerance::new(1e-6f64, 1.0);
+        let limit = MaxIterations::new(10);
+        let criteria: [&dyn StoppingCriterion<f64>; 2] = [&relative, &limit];
+        let stop = AnyOf::new(&criteria);
+
+        assert!(!stop.should_stop(1.0, 5));
+        assert!(stop.should_stop(1e-7, 5));
+        assert!(stop.should_stop(1.0, 10));
+        assert!(!AnyOf::new(&[]).should_stop(0f64, 0));
+    }
+}
+
+#[cfg(test)]
+mod bicgstab_tests {
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::iterative::{
+        AnyOf, Bicgstab, MaxIterations, RelativeTolerance, StoppingCriterion,
+    };
+    use crate::math::Mat;
+    use std::cell::RefCell;
+
+    #[test]
+    fn nonsymmetric() {
+        let a = mat![4f64, 1.0, 0.0; -2.0, 5.0, 1.0; 1.0, 0.5, 3.0];
+        let truth = [1f64, -2.0, 0.5];
+        let b: Vec<f64> = (0..3)
+            .map(|i| (0..3).map(|j| a[i][j] * truth[j]).sum())
+            .collect();
+        let mut x = vec![0f64; 3];
+
+        let iters = Bicgstab::bicgstab(&a, &b, &mut x, 50, 1e-12).unwrap();
+        assert!(iters > 0 && iters <= 50);
+        for (xi, ti) in x.iter().zip(&truth) {
+            assert!((xi - ti).abs() < 1e-10);
+        }
+
+        // Already solved.
+        assert_eq!(Bicgstab::bicgstab(&a, &b, &mut x, 50, 1e-6), Ok(0));
+    }
+
+    #[test]
+    fn too_few_iterations() {
+        let a =
+            mat![4f64, 1.0, 0.0, 0.0; -2.0, 5.0, 1.0, 0.0; 1.0, 0.5, 3.0, 1.0; 0.0, 1.0, -1.0, 6.0];
+        let b = vec![1f64, 2.0, 3.0, 4.0];
+        let mut x = vec![0f64; 4];
+
+        let result = Bicgstab::bicgstab(&a, &b, &mut x, 1, 1e-14);
+        assert_eq!(result, Err(BlasError::NoConvergence));
+    }
+
+    #[test]
+    fn until_first_criterion() {
+        let a =
+            mat![4f64, 1.0, 0.0, 0.0; -2.0, 5.0, 1.0, 0.0; 1.0, 0.5, 3.0, 1.0; 0.0, 1.0, -1.0, 6.0];
+        let b = vec![1f64, 2.0, 3.0, 4.0];
+
+        // The iteration limit comes first.
+        let relative = RelativeTolerance::new(1e-14, 30f64.sqrt());
+        let limit = MaxIterations::new(1);
+        let criteria: [&dyn StoppingCriterion<f64>; 2] = [&relative, &limit];
+        let mut x = vec![0f64; 4];
+        let (iters, residual) =
+            Bicgstab::bicgstab_until(&a, &b, &mut x, &AnyOf::new(&criteria)).unwrap();
+        assert_eq!(iters, 1);
+        assert!(residual > 1e-14 * 30f64.sqrt());
+
+        // The tolerance comes first.
+        let relative = RelativeTolerance::new(1e-2, 30f64.sqrt());
+        let limit = MaxIterations::new(100);
+        let criteria: [&dyn StoppingCriterion<f64>; 2] = [&relative, &limit];
+        let mut x = vec![0f64; 4];
+        let (iters, residual) =
+            Bicgstab::bicgstab_until(&a, &b, &mut x, &AnyOf::new(&criteria)).unwrap();
+        assert!(iters < 100);
+        assert!(residual <= 1e-2 * 30f64.sqrt());
+    }
+
+    struct Log(RefCell<Vec<usize>>);
+
+    impl StoppingCriterion<f64> for Log {
+        fn should_stop(&self, _: f64, iter: usize) -> bool {
+            self.0.borrow_mut().push(iter);
+            false
+        }
+    }
+
+    #[test]
+    fn limit_counts_full_iterations() {
+        let a =
+            mat![4f64, 1.0, 0.0, 0.0; -2.0, 5.0, 1.0, 0.0; 1.0, 0.5, 3.0, 1.0; 0.0, 1.0, -1.0, 6.0];
+        let b = vec![1f64, 2.0, 3.0, 4.0];
+
+        let log = Log(RefCell::new(Vec::new()));
+        let limit = MaxIterations::new(2);
+        let criteria: [&dyn StoppingCriterion<f64>; 2] = [&log, &limit];
+        let mut x = vec![0f64; 4];
+        let (iters, _) = Bicgstab::bicgstab_until(&a, &b, &mut x, &AnyOf::new(&criteria)).unwrap();
+        assert_eq!(iters, 2);
+        assert_eq!(*log.0.borrow(), vec![0, 0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn not_square() {
+        let a = mat![1f32, 2.0];
+        let mut x = vec![0f32; 2];
+
+        let result = Bicgstab::bicgstab(&a, &vec![1f32], &mut x, 10, 1e-6);
+        assert_eq!(
+            result,
+            Err(BlasError::DimensionMismatch {
+                expected: 1,
+                found: 2
+            })
+        );
+    }
+}
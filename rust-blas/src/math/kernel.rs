@@ -0,0 +1,688 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Distances, kernel matrices and kernel approximations between the rows of
+//! data matrices.
+
+use crate::attribute::{Symmetry, Transpose};
+use crate::error::BlasError;
+use crate::math::linalg::{AddToDiagonal, Cholesky};
+use crate::math::sparse::CsrMatrix;
+use crate::math::Mat;
+use crate::matrix::ops::{Gemm, Syrk};
+use crate::matrix::{col, get, row, row_mut, set, Matrix};
+use crate::matrix_vector::ops::Gemv;
+use crate::vector::ops::{Axpy, Copy, Dot, Nrm2, Scal};
+use crate::vector::Vector;
+
+/// Squared Euclidean distances between every row of `a` and every row of
+/// `b`.
+///
+/// Uses `|x - y|^2 = |x|^2 + |y|^2 - 2 x.y`, with all the cross terms from a
+/// single `gemm`. Cancellation can leave tiny negative values for nearly
+/// equal rows; these are clamped to zero.
+pub trait DistanceSq: Sized {
+    fn distance_sq(a: &dyn Matrix<Self>, b: &dyn Matrix<Self>) -> Mat<Self>;
+}
+
+macro_rules! distance_sq_impl(($($t: ident), +) => (
+    $(
+        impl DistanceSq for $t {
+            fn distance_sq(a: &dyn Matrix<$t>, b: &dyn Matrix<$t>) -> Mat<$t> {
+                if a.cols() != b.cols() {
+                    panic!("Dimension mismatch");
+                }
+
+                let (m, n) = (a.rows() as usize, b.rows() as usize);
+                let sq_norms = |x: &dyn Matrix<$t>| -> Vec<$t> {
+                    (0..x.rows() as usize)
+                        .map(|i| {
+                            let r = row(x, i);
+                            Dot::dot(&r, &r)
+                        })
+                        .collect()
+                };
+                let (na, nb) = (sq_norms(a), sq_norms(b));
+
+                let mut d = Mat::fill(0.0, m, n);
+                Gemm::gemm(&-2.0, Transpose::NoTrans, a, Transpose::Trans, b, &0.0, &mut d);
+                for i in 0..m {
+                    for j in 0..n {
+                        let v = get(&d, i, j) + na[i] + nb[j];
+                        set(&mut d, i, j, v.max(0.0));
+                    }
+                }
+
+                d
+            }
+        }
+    )+
+));
+
+distance_sq_impl!(f32, f64);
+
+/// Squared Euclidean distances between all pairs of rows of `a`.
+///
+/// The same identity as `DistanceSq`, but the cross terms are a single
+/// `syrk` filling one triangle, which is then mirrored. The diagonal is
+/// exactly zero and rounding below zero is clamped away.
+pub trait PairwiseDistances: Sized {
+    fn pairwise_distances(a: &dyn Matrix<Self>) -> Mat<Self>;
+}
+
+macro_rules! pairwise_distances_impl(($($t: ident), +) => (
+    $(
+        impl PairwiseDistances for $t {
+            fn pairwise_distances(a: &dyn Matrix<$t>) -> Mat<$t> {
+                let n = a.rows() as usize;
+                let sq_norms: Vec<$t> = (0..n)
+                    .map(|i| {
+                        let r = row(a, i);
+                        Dot::dot(&r, &r)
+                    })
+                    .collect();
+
+                let mut d = Mat::fill(0.0, n, n);
+                Syrk::syrk(Symmetry::Lower, Transpose::NoTrans, &-2.0, a, &0.0, &mut d);
+                for i in 0..n {
+                    set(&mut d, i, i, 0.0);
+                    for j in 0..i {
+                        let v = (get(&d, i, j) + sq_norms[i] + sq_norms[j]).max(0.0);
+                        set(&mut d, i, j, v);
+                        set(&mut d, j, i, v);
+                    }
+                }
+
+                d
+            }
+        }
+    )+
+));
+
+pairwise_distances_impl!(f32, f64);
+
+/// The Gaussian RBF kernel `exp(-gamma * |x - y|^2)`.
+///
+/// For single vectors the squared distance is the `dot` of `x - y` with
+/// itself, formed by `copy` and `axpy` so nearby points do not cancel.
+/// Between the rows of two matrices it comes from `distance_sq`, so the
+/// whole kernel matrix costs one `gemm`.
+pub trait RbfKernel: Sized {
+    fn rbf_kernel<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        x: &V,
+        y: &W,
+        gamma: Self,
+    ) -> Self;
+    /// The kernel between every row of `a` and every row of `b`.
+    fn rbf_kernel_matrix(a: &dyn Matrix<Self>, b: &dyn Matrix<Self>, gamma: Self) -> Mat<Self>;
+}
+
+macro_rules! rbf_kernel_impl(($($t: ident), +) => (
+    $(
+        impl RbfKernel for $t {
+            fn rbf_kernel<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, y: &W, gamma: $t) -> $t {
+                if x.len() != y.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut diff = vec![0.0; x.len() as usize];
+                Copy::copy(x, &mut diff);
+                Axpy::axpy(&-1.0, y, &mut diff);
+                let d: $t = Dot::dot(&diff, &diff);
+                (-gamma * d).exp()
+            }
+
+            fn rbf_kernel_matrix(a: &dyn Matrix<$t>, b: &dyn Matrix<$t>, gamma: $t) -> Mat<$t> {
+                let mut k = DistanceSq::distance_sq(a, b);
+                for i in 0..k.rows() {
+                    for j in 0..k.cols() {
+                        let v = (-gamma * get(&k, i, j)).exp();
+                        set(&mut k, i, j, v);
+                    }
+                }
+
+                k
+            }
+        }
+    )+
+));
+
+rbf_kernel_impl!(f32, f64);
+
+/// Nystroem feature map for the RBF kernel `exp(-gamma * |x - y|^2)`.
+///
+/// The rows of `data` listed in `landmarks` are the landmark points. With
+/// `K_mm` the kernel between landmarks, factored by Cholesky as `L * L^T`,
+/// and `K_nm` the kernel between every row and the landmarks, the features
+/// are `K_nm * L^-T`, one row per row of `data` and one column per landmark.
+/// Inner products of feature rows approximate the kernel, exactly so between
+/// landmarks.
+///
+/// Panics if a landmark index is out of range or the landmark kernel is not
+/// positive definite, as happens when a landmark is repeated.
+pub trait Nystroem: Sized {
+    fn nystroem(data: &dyn Matrix<Self>, landmarks: &[usize], gamma: Self) -> Mat<Self>;
+}
+
+macro_rules! nystroem_impl(($($t: ident), +) => (
+    $(
+        impl Nystroem for $t {
+            fn nystroem(data: &dyn Matrix<$t>, landmarks: &[usize], gamma: $t) -> Mat<$t> {
+                let (n, k) = (data.rows() as usize, landmarks.len());
+                if let Some(&i) = landmarks.iter().find(|&&i| i >= n) {
+                    panic!("Landmark {} out of range for {} rows", i, n);
+                }
+
+                let mut points = Mat::fill(0.0, k, data.cols() as usize);
+                for (r, &i) in landmarks.iter().enumerate() {
+                    Copy::copy(&row(data, i), &mut row_mut(&mut points, r));
+                }
+
+                let rbf = |d: Mat<$t>| {
+                    let (rows, cols) = (d.rows(), d.cols());
+                    let values = (0..rows * cols).map(|e| (-gamma * d[e / cols][e % cols]).exp());
+                    Mat::new_from_data(rows, cols, values.collect())
+                };
+                let mut factor = rbf(DistanceSq::distance_sq(&points, &points));
+                let cross = rbf(DistanceSq::distance_sq(data, &points));
+
+                if Cholesky::cholesky(&mut factor).is_err() {
+                    panic!("Landmark kernel is not positive definite");
+                }
+
+                // Invert L by forward substitution, one column at a time.
+                let mut inv = Mat::fill(0.0, k, k);
+                for j in 0..k {
+                    set(&mut inv, j, j, 1.0 / get(&factor, j, j));
+                    for i in (j + 1)..k {
+                        let s: $t = Dot::dot(
+                            &row(&factor, i).skip(j as u32).take((i - j) as u32),
+                            &col(&inv, j).skip(j as u32).take((i - j) as u32),
+                        );
+                        set(&mut inv, i, j, -s / get(&factor, i, i));
+                    }
+                }
+
+                let mut features = Mat::fill(0.0, n, k);
+                Gemm::gemm(&1.0, Transpose::NoTrans, &cross, Transpose::Trans, &inv, &0.0, &mut features);
+                features
+            }
+        }
+    )+
+));
+
+nystroem_impl!(f32, f64);
+
+/// Scales every row of `a` in place to unit Euclidean length.
+///
+/// All-zero rows have no direction and are left as they are.
+pub trait NormalizeRows: Sized {
+    fn normalize_rows(a: &mut dyn Matrix<Self>);
+}
+
+macro_rules! normalize_rows_impl(($($t: ident), +) => (
+    $(
+        impl NormalizeRows for $t {
+            fn normalize_rows(a: &mut dyn Matrix<$t>) {
+                for i in 0..a.rows() as usize {
+                    let mut r = row_mut(a, i);
+                    let norm: $t = Nrm2::nrm2(&r);
+                    if norm > 0.0 {
+                        Scal::scal(&norm.recip(), &mut r);
+                    }
+                }
+            }
+        }
+    )+
+));
+
+normalize_rows_impl!(f32, f64);
+
+/// Cosine similarities between every row of `queries` and every row of
+/// `corpus`.
+///
+/// Both sets of rows are copied and normalized with `normalize_rows`, after
+/// which all similarities are the entries of a single `gemm`, `Q * C^T`.
+/// Zero rows have similarity zero with everything.
+pub trait SimilarityMatrix: Sized {
+    fn similarity_matrix(queries: &dyn Matrix<Self>, corpus: &dyn Matrix<Self>) -> Mat<Self>;
+}
+
+macro_rules! similarity_matrix_impl(($($t: ident), +) => (
+    $(
+        impl SimilarityMatrix for $t {
+            fn similarity_matrix(queries: &dyn Matrix<$t>, corpus: &dyn Matrix<$t>) -> Mat<$t> {
+                if queries.cols() != corpus.cols() {
+                    panic!("Dimension mismatch");
+                }
+
+                let normalized = |x: &dyn Matrix<$t>| {
+                    let mut y = Mat::fill(0.0, x.rows() as usize, x.cols() as usize);
+                    for i in 0..x.rows() as usize {
+                        Copy::copy(&row(x, i), &mut row_mut(&mut y, i));
+                    }
+                    NormalizeRows::normalize_rows(&mut y);
+                    y
+                };
+                let (q, c) = (normalized(queries), normalized(corpus));
+
+                let mut sim = Mat::fill(0.0, q.rows(), c.rows());
+                Gemm::gemm(&1.0, Transpose::NoTrans, &q, Transpose::Trans, &c, &0.0, &mut sim);
+                sim
+            }
+        }
+    )+
+));
+
+similarity_matrix_impl!(f32, f64);
+
+/// The k-nearest-neighbor graph of the rows of `data` with Gaussian
+/// weights, as used for spectral clustering.
+///
+/// Distances come from `pairwise_distances`. Row `i` of the result has an
+/// entry for each of the `k_nearest` rows closest to row `i` (excluding `i`
+/// itself, ties going to the lower index), weighted
+/// `exp(-|x_i - x_j|^2 / (2 sigma^2))`, with column indices in increasing
+/// order. The graph is directed: `j` can be a neighbor of `i` without the
+/// converse, so symmetrize it (for instance with `max(W, W^T)`) before
+/// building a Laplacian.
+///
+/// Panics unless `k_nearest` is less than the number of rows.
+pub trait GaussianAffinity: Sized {
+    fn gaussian_affinity(data: &dyn Matrix<Self>, sigma: Self, k_nearest: usize)
+        -> CsrMatrix<Self>;
+}
+
+macro_rules! gaussian_affinity_impl(($($t: ident), +) => (
+    $(
+        impl GaussianAffinity for $t {
+            fn gaussian_affinity(data: &dyn Matrix<$t>, sigma: $t, k_nearest: usize) -> CsrMatrix<$t> {
+                let n = data.rows() as usize;
+                if k_nearest >= n.max(1) {
+                    panic!("{} neighbors out of range for {} points", k_nearest, n);
+                }
+
+                let d = PairwiseDistances::pairwise_distances(data);
+                let scale = -0.5 / (sigma * sigma);
+                let mut row_ptr = Vec::with_capacity(n + 1);
+                let mut col_indices = Vec::with_capacity(n * k_nearest);
+                let mut values = Vec::with_capacity(n * k_nearest);
+                row_ptr.push(0);
+
+                for i in 0..n {
+                    let mut others: Vec<usize> = (0..n).filter(|&j| j != i).collect();
+                    if k_nearest > 0 {
+                        let by_distance = |a: &usize, b: &usize| {
+                            d[i][*a].total_cmp(&d[i][*b]).then(a.cmp(b))
+                        };
+                        others.select_nth_unstable_by(k_nearest - 1, by_distance);
+                    }
+                    let mut nearest = others[..k_nearest].to_vec();
+                    nearest.sort_unstable();
+
+                    for j in nearest {
+                        col_indices.push(j);
+                        values.push((scale * d[i][j]).exp());
+                    }
+                    row_ptr.push(values.len());
+                }
+
+                CsrMatrix::new(n, n, row_ptr, col_indices, values)
+            }
+        }
+    )+
+));
+
+gaussian_affinity_impl!(f32, f64);
+
+/// A kernel ridge regression model fitted by `KernelRidge::krr_fit`.
+pub struct KrrModel<T> {
+    points: Mat<T>,
+    coefficients: Vec<T>,
+    gamma: T,
+}
+
+impl<T> KrrModel<T> {
+    /// The dual coefficients, one per training point.
+    pub fn coefficients(&self) -> &[T] {
+        &self.coefficients
+    }
+}
+
+/// Kernel ridge regression with the RBF kernel `exp(-gamma * |x - y|^2)`.
+///
+/// Fitting builds the kernel matrix `K` of the training rows from
+/// `pairwise_distances` and solves `(K + lambda * I) * alpha = y` by
+/// Cholesky. The model keeps a copy of the training rows, and a prediction
+/// is a `gemv` of `rbf_kernel_matrix` between the new rows and the training
+/// rows with `alpha`. Any positive `lambda` makes the system solvable; with
+/// `lambda = 0` repeated training rows give `SingularMatrix`.
+pub trait KernelRidge: Sized {
+    fn krr_fit<V: ?Sized + Vector<Self>>(
+        x: &dyn Matrix<Self>,
+        y: &V,
+        gamma: Self,
+        lambda: Self,
+    ) -> Result<KrrModel<Self>, BlasError>;
+    fn krr_predict(model: &KrrModel<Self>, x: &dyn Matrix<Self>) -> Vec<Self>;
+}
+
+macro_rules! kernel_ridge_impl(($($t: ident), +) => (
+    $(
+        impl KernelRidge for $t {
+            fn krr_fit<V: ?Sized + Vector<Self>>(x: &dyn Matrix<$t>, y: &V, gamma: $t, lambda: $t) -> Result<KrrModel<$t>, BlasError> {
+                let n = x.rows() as usize;
+                if y.len() as usize != n {
+                    return Err(BlasError::DimensionMismatch { expected: n, found: y.len() as usize });
+                }
+
+                let mut k = PairwiseDistances::pairwise_distances(x);
+                for i in 0..n {
+                    for j in 0..n {
+                        let v = (-gamma * get(&k, i, j)).exp();
+                        set(&mut k, i, j, v);
+                    }
+                }
+                AddToDiagonal::add_to_diagonal(&mut k, lambda);
+
+                let mut coefficients = vec![0.0; n];
+                Copy::copy(y, &mut coefficients);
+                Cholesky::cholesky(&mut k)?;
+                Cholesky::cholesky_solve(&k, &mut coefficients);
+
+                let mut points = Mat::fill(0.0, n, x.cols() as usize);
+                for i in 0..n {
+                    Copy::copy(&row(x, i), &mut row_mut(&mut points, i));
+                }
+
+                Ok(KrrModel { points, coefficients, gamma })
+            }
+
+            fn krr_predict(model: &KrrModel<$t>, x: &dyn Matrix<$t>) -> Vec<$t> {
+                let k = RbfKernel::rbf_kernel_matrix(x, &model.points, model.gamma);
+                let mut prediction = vec![0.0; x.rows() as usize];
+                Gemv::gemv(Transpose::NoTrans, &1.0, &k, &model.coefficients, &0.0, &mut prediction);
+                prediction
+            }
+        }
+    )+
+));
+
+kernel_ridge_impl!(f32, f64);
+
+#[cfg(test)]
+mod distance_sq_tests {
+    use crate::mat;
+    use crate::math::kernel::DistanceSq;
+    use crate::math::Mat;
+
+    #[test]
+    fn real() {
+        let a = mat![0f64, 0.0; 3.0, 4.0];
+        let b = mat![0f64, 0.0; 1.0, 1.0; 3.0, 4.0];
+
+        let d = DistanceSq::distance_sq(&a, &b);
+        assert_eq!(d, mat![0.0, 2.0, 25.0; 25.0, 13.0, 0.0]);
+    }
+}
+
+#[cfg(test)]
+mod pairwise_distances_tests {
+    use crate::mat;
+    use crate::math::kernel::PairwiseDistances;
+    use crate::math::Mat;
+    use crate::matrix::tests::Cm;
+
+    #[test]
+    fn brute_force() {
+        let a = mat![0f64, 0.0; 3.0, 4.0; -1.0, 2.0; 3.0, 4.0];
+
+        let d = PairwiseDistances::pairwise_distances(&a);
+        for i in 0..4 {
+            for j in 0..4 {
+                let (dx, dy) = (a[i][0] - a[j][0], a[i][1] - a[j][1]);
+                assert!((d[i][j] - (dx * dx + dy * dy)).abs() < 1e-12);
+            }
+        }
+        assert_eq!(d[1][3], 0.0);
+        assert_eq!(d[0][1], 25.0);
+    }
+
+    #[test]
+    fn column_major() {
+        let rows = mat![0f64, 0.0; 3.0, 4.0; -1.0, 2.0; 3.0, 4.0];
+        let cols = Cm(4, 2, vec![0f64, 3.0, -1.0, 3.0, 0.0, 4.0, 2.0, 4.0]);
+
+        let d = PairwiseDistances::pairwise_distances(&cols);
+        assert_eq!(d, PairwiseDistances::pairwise_distances(&rows));
+    }
+
+    #[test]
+    fn clamped() {
+        let a = mat![1e4f32, 1e4 + 1e-3; 1e4, 1e4 + 1e-3];
+
+        let d = PairwiseDistances::pairwise_distances(&a);
+        assert_eq!(d, Mat::fill(0.0, 2, 2));
+    }
+}
+
+#[cfg(test)]
+mod rbf_kernel_tests {
+    use crate::mat;
+    use crate::math::kernel::RbfKernel;
+    use crate::math::Mat;
+
+    #[test]
+    fn vectors() {
+        let x = vec![1f64, -2.0, 0.5];
+        assert_eq!(RbfKernel::rbf_kernel(&x, &x, 0.7), 1.0);
+
+        let near = vec![1f64, -2.0, 1.0];
+        let far = vec![3f64, -2.0, 1.0];
+        let (k_near, k_far) = (
+            RbfKernel::rbf_kernel(&x, &near, 0.7),
+            RbfKernel::rbf_kernel(&x, &far, 0.7),
+        );
+        assert!((k_near - (-0.7f64 * 0.25).exp()).abs() < 1e-12);
+        assert!(k_far < k_near && k_far > 0.0);
+    }
+
+    #[test]
+    fn nearby_large_vectors() {
+        let x = vec![1e8f64, 3.0];
+        let y = vec![1e8f64 + 1.0, 3.0];
+
+        let k = RbfKernel::rbf_kernel(&x, &y, 1.0);
+        assert!((k - (-1f64).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn matrix() {
+        let a = mat![0f32, 0.0; 3.0, 4.0];
+        let b = mat![0f32, 0.0; 0.0, 1.0];
+
+        let k = RbfKernel::rbf_kernel_matrix(&a, &b, 0.1);
+        assert_eq!((k.rows(), k.cols()), (2, 2));
+        assert_eq!(k[0][0], 1.0);
+        assert!((k[0][1] - (-0.1f32).exp()).abs() < 1e-6);
+        assert!((k[1][0] - (-2.5f32).exp()).abs() < 1e-6);
+        assert!((k[1][1] - (-1.8f32).exp()).abs() < 1e-6);
+    }
+}
+
+#[cfg(test)]
+mod nystroem_tests {
+    use crate::mat;
+    use crate::math::kernel::Nystroem;
+    use crate::math::Mat;
+
+    #[test]
+    fn real() {
+        let data = mat![0f64, 0.0; 1.0, 0.0; 0.0, 2.0; 1.0, 1.0];
+
+        let phi = Nystroem::nystroem(&data, &[0, 2, 3], 0.5);
+        assert_eq!((phi.rows(), phi.cols()), (4, 3));
+
+        let dot = |i: usize, j: usize| (0..3).map(|k| phi[i][k] * phi[j][k]).sum::<f64>();
+        for &l in &[0, 2, 3] {
+            assert!((dot(l, l) - 1.0).abs() < 1e-12);
+        }
+        // Between landmarks the approximation is exact.
+        assert!((dot(0, 2) - (-2f64).exp()).abs() < 1e-12);
+        // Elsewhere it is only approximate, but still a similarity.
+        assert!(dot(1, 1) > 0.0 && dot(1, 1) <= 1.0 + 1e-12);
+    }
+
+    #[test]
+    #[should_panic]
+    fn repeated_landmark() {
+        let data = mat![0f32, 0.0; 1.0, 0.0];
+
+        let _ = Nystroem::nystroem(&data, &[1, 1], 1.0);
+    }
+}
+
+#[cfg(test)]
+mod similarity_matrix_tests {
+    use crate::mat;
+    use crate::math::kernel::{NormalizeRows, SimilarityMatrix};
+    use crate::math::Mat;
+
+    #[test]
+    fn normalize_rows() {
+        let mut a = mat![3f64, 4.0; 0.0, 0.0; 0.0, -2.0];
+
+        NormalizeRows::normalize_rows(&mut a);
+        assert!((a[0][0] - 0.6).abs() < 1e-12 && (a[0][1] - 0.8).abs() < 1e-12);
+        assert_eq!(a[1], [0.0, 0.0]);
+        assert_eq!(a[2], [0.0, -1.0]);
+    }
+
+    #[test]
+    fn exact_match() {
+        let corpus = mat![1f64, 0.0, 0.0; 1.0, 2.0, 2.0; 0.0, -1.0, 0.0];
+        let queries = mat![2f64, 4.0, 4.0; 0.0, 1.0, 0.0];
+
+        let sim = SimilarityMatrix::similarity_matrix(&queries, &corpus);
+        assert_eq!((sim.rows(), sim.cols()), (2, 3));
+        assert!((sim[0][1] - 1.0).abs() < 1e-12);
+        assert!((sim[0][0] - 1.0 / 3.0).abs() < 1e-12);
+        assert!((sim[1][2] + 1.0).abs() < 1e-12);
+        for i in 0..2 {
+            for j in 0..3 {
+                assert!(sim[i][j].abs() <= 1.0 + 1e-12);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod gaussian_affinity_tests {
+    use crate::mat;
+    use crate::math::kernel::GaussianAffinity;
+    use crate::math::Mat;
+    use crate::matrix::set;
+
+    #[test]
+    fn k_per_row_and_symmetrized() {
+        // Two well separated groups on a line.
+        let points = mat![0f64; 1.0; 2.5; 10.0; 11.0; 13.0];
+        let k = 2;
+
+        let w = GaussianAffinity::gaussian_affinity(&points, 1.0, k);
+        assert_eq!((w.rows(), w.cols(), w.nnz()), (6, 6, 12));
+
+        let mut sym = Mat::fill(0f64, 6, 6);
+        for i in 0..6 {
+            let range = w.row_ptr()[i]..w.row_ptr()[i + 1];
+            assert_eq!(range.len(), k);
+            for (&j, &v) in w.col_indices()[range.clone()]
+                .iter()
+                .zip(&w.values()[range])
+            {
+                assert_ne!(i, j);
+                assert!(v > 0.0 && v <= 1.0);
+                // Nearest neighbors never cross between the groups.
+                assert_eq!(i < 3, j < 3);
+                let entry = v.max(sym[i][j]);
+                set(&mut sym, i, j, entry);
+                set(&mut sym, j, i, entry);
+            }
+        }
+        for i in 0..6 {
+            for j in 0..6 {
+                assert_eq!(sym[i][j], sym[j][i]);
+            }
+        }
+    }
+
+    #[test]
+    fn weights() {
+        let points = mat![0f32, 0.0; 3.0, 4.0; 0.0, 1.0];
+
+        let w = GaussianAffinity::gaussian_affinity(&points, 2.0, 1);
+        assert_eq!(w.col_indices(), [2, 2, 0]);
+        assert!((w.values()[0] - (-1f32 / 8.0).exp()).abs() < 1e-6);
+        assert!((w.values()[1] - (-18f32 / 8.0).exp()).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_many_neighbors() {
+        let points = mat![0f64; 1.0];
+
+        let _ = GaussianAffinity::gaussian_affinity(&points, 1.0, 2);
+    }
+}
+
+#[cfg(test)]
+mod kernel_ridge_tests {
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::kernel::KernelRidge;
+    use crate::math::Mat;
+
+    #[test]
+    fn interpolates_training_points() {
+        let xs = [0f64, 0.5, 1.0, 1.5, 2.0, 2.5];
+        let x = Mat::new_from_data(6, 1, xs.to_vec());
+        let y: Vec<f64> = xs.iter().map(|x| x.sin()).collect();
+
+        let model = KernelRidge::krr_fit(&x, &y, 1.0, 1e-9).unwrap();
+        assert_eq!(model.coefficients().len(), 6);
+
+        let fitted = KernelRidge::krr_predict(&model, &x);
+        for (f, t) in fitted.iter().zip(&y) {
+            assert!((f - t).abs() < 1e-6);
+        }
+
+        // Between the training points the fit follows the smooth function.
+        let between = KernelRidge::krr_predict(&model, &mat![1.25f64]);
+        assert!((between[0] - 1.25f64.sin()).abs() < 1e-2);
+    }
+
+    #[test]
+    fn regularization_shrinks() {
+        let x = mat![0f32; 1.0; 2.0];
+        let y = vec![1f32, 1.0, 1.0];
+
+        let model = KernelRidge::krr_fit(&x, &y, 1.0, 10.0).unwrap();
+        let fitted = KernelRidge::krr_predict(&model, &x);
+        assert!(fitted.iter().all(|&f| f > 0.0 && f < 1.0));
+    }
+
+    #[test]
+    fn mismatched_targets() {
+        let x = mat![0f64; 1.0];
+        let y = vec![1f64];
+
+        assert_eq!(
+            KernelRidge::krr_fit(&x, &y, 1.0, 1.0).err(),
+            Some(BlasError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,2253 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Building blocks for dense factorizations and solvers.
+
+#[cfg(feature = "lapack")]
+use crate::attribute::Order;
+use crate::attribute::{Diagonal, Symmetry, Transpose};
+use crate::default::Default;
+use crate::error::BlasError;
+use crate::math::Mat;
+use crate::matrix::ops::{Gemm, Syrk};
+use crate::matrix::{col, col_mut, get, row, row_mut, set, swap_rows, Matrix};
+use crate::matrix_vector::ops::{Gemv, Trsv};
+use crate::vector::ops::{Axpy, Copy, Dot, Iamax, Nrm2, Scal};
+use crate::vector::{Strided, Vector};
+use num_complex::{Complex32, Complex64};
+#[cfg(all(feature = "lapack", feature = "rand"))]
+use rand::Rng;
+
+/// Copies `a` into a freshly allocated row-major matrix.
+fn owned<T>(a: &dyn Matrix<T>) -> Mat<T>
+where
+    T: std::marker::Copy + Copy + Default,
+{
+    let rows = a.rows() as usize;
+    let mut result = Mat::fill(Default::zero(), rows, a.cols() as usize);
+    for i in 0..rows {
+        Copy::copy(&row(a, i), &mut row_mut(&mut result, i));
+    }
+    result
+}
+
+/// One column step of Gaussian elimination with partial pivoting.
+///
+/// The pivot is the entry of column `col` on or below the diagonal with the
+/// largest magnitude, as found by `iamax`. Its row is swapped into row `col`
+/// and a multiple of it is subtracted (`axpy`) from every row below, leaving
+/// the column zero under the diagonal. Returns the index the pivot row had
+/// before the swap.
+///
+/// If the column is already zero on and below the diagonal, no rows are
+/// touched and `col` is returned.
+pub trait EliminateColumn: Sized {
+    fn eliminate_column(a: &mut dyn Matrix<Self>, col: usize) -> usize;
+}
+
+macro_rules! eliminate_column_impl(($($t: ident), +) => (
+    $(
+        impl EliminateColumn for $t {
+            fn eliminate_column(a: &mut dyn Matrix<$t>, c: usize) -> usize {
+                let rows = a.rows() as usize;
+                if c >= rows || c >= a.cols() as usize {
+                    panic!("Column {} out of range for {}x{} matrix", c, rows, a.cols());
+                }
+
+                let pivot = c + Iamax::iamax(&col(a, c).skip(c as u32));
+                swap_rows(a, c, pivot);
+
+                let p = get(a, c, c);
+                if p == Default::zero() {
+                    return pivot;
+                }
+
+                let pivot_row = row(a, c).skip(c as u32 + 1);
+                for i in (c + 1)..rows {
+                    let factor = -(get(a, i, c) / p);
+                    Axpy::axpy(&factor, &pivot_row, &mut row_mut(a, i).skip(c as u32 + 1));
+                    set(a, i, c, Default::zero());
+                }
+
+                pivot
+            }
+        }
+    )+
+));
+
+eliminate_column_impl!(f32, f64, Complex32, Complex64);
+
+/// Multiplies a vector by `2^exp`.
+///
+/// Scaling by a power of two only changes the exponent, so no rounding
+/// happens unless the result leaves the representable range.
+pub trait ScalPow2: Sized {
+    fn scal_pow2<V: ?Sized + Vector<Self>>(exp: i32, x: &mut V);
+}
+
+macro_rules! scal_pow2_impl(($($t: ident), +) => (
+    $(
+        impl ScalPow2 for $t {
+            fn scal_pow2<V: ?Sized + Vector<Self>>(exp: i32, x: &mut V) {
+                Scal::scal(&(2.0 as $t).powi(exp), x);
+            }
+        }
+    )+
+));
+
+scal_pow2_impl!(f32, f64);
+
+/// Computes `C = A * B` without overflowing in intermediate results.
+///
+/// If `amax(A) * amax(B) * k` could exceed the largest finite value, both
+/// operands are copied and scaled down by powers of two before the `gemm`,
+/// and `C` is scaled back up afterwards. Otherwise this is a plain `gemm`.
+/// Entries of `C` that really are out of range still end up infinite, as do
+/// those computed from infinite inputs.
+pub trait GemmScaled: Sized {
+    fn gemm_scaled(a: &dyn Matrix<Self>, b: &dyn Matrix<Self>, c: &mut dyn Matrix<Self>);
+}
+
+macro_rules! gemm_scaled_impl(($($t: ident), +) => (
+    $(
+        impl GemmScaled for $t {
+            fn gemm_scaled(a: &dyn Matrix<$t>, b: &dyn Matrix<$t>, c: &mut dyn Matrix<$t>) {
+                if a.cols() != b.rows() || c.rows() != a.rows() || c.cols() != b.cols() {
+                    panic!("Dimension mismatch");
+                }
+
+                let t = Transpose::NoTrans;
+                if a.rows() == 0 || a.cols() == 0 || b.cols() == 0 {
+                    Gemm::gemm(&1.0, t, a, t, b, &0.0, c);
+                    return;
+                }
+
+                let amax = |m: &dyn Matrix<$t>| {
+                    (0..m.rows() as usize)
+                        .map(|i| {
+                            let r = row(m, i);
+                            r.get(Iamax::iamax(&r) as u32).abs()
+                        })
+                        .fold(0.0, $t::max)
+                };
+
+                let bound = amax(a).log2() + amax(b).log2() + (a.cols() as $t).log2();
+                let excess = (bound - $t::MAX.log2()).ceil();
+                // An infinite or NaN input has nothing to gain from scaling.
+                if !excess.is_finite() || excess < 0.0 {
+                    Gemm::gemm(&1.0, t, a, t, b, &0.0, c);
+                    return;
+                }
+
+                // One extra halving leaves headroom for rounding in the sums.
+                let total = excess as i32 + 1;
+                let (shift_a, shift_b) = (total - total / 2, total / 2);
+
+                let mut a = owned(a);
+                let mut b = owned(b);
+                for i in 0..a.rows() {
+                    ScalPow2::scal_pow2(-shift_a, &mut row_mut(&mut a, i));
+                }
+                for i in 0..b.rows() {
+                    ScalPow2::scal_pow2(-shift_b, &mut row_mut(&mut b, i));
+                }
+
+                // `2^total` itself can overflow, so undo the shifts one at a time.
+                Gemm::gemm(&1.0, t, &a, t, &b, &0.0, c);
+                for i in 0..c.rows() as usize {
+                    ScalPow2::scal_pow2(shift_a, &mut row_mut(c, i));
+                    ScalPow2::scal_pow2(shift_b, &mut row_mut(c, i));
+                }
+            }
+        }
+    )+
+));
+
+gemm_scaled_impl!(f32, f64);
+
+/// Multiplies one matrix `A` by a batch of vectors stored as the columns of
+/// `xs`, writing `A * xs[:, j]` to column `j` of `ys`.
+///
+/// The whole batch is a single `gemm`, which is far faster than one `gemv`
+/// per column. Any order or leading dimension is accepted for each operand.
+/// Panics unless `xs` has `A.cols()` rows, `ys` has `A.rows()` rows and both
+/// have the same number of columns.
+pub trait GemvBatched: Sized {
+    fn gemv_batched(a: &dyn Matrix<Self>, xs: &dyn Matrix<Self>, ys: &mut dyn Matrix<Self>);
+}
+
+macro_rules! gemv_batched_impl(($($t: ident), +) => (
+    $(
+        impl GemvBatched for $t {
+            fn gemv_batched(a: &dyn Matrix<$t>, xs: &dyn Matrix<$t>, ys: &mut dyn Matrix<$t>) {
+                if xs.rows() != a.cols() || ys.rows() != a.rows() || ys.cols() != xs.cols() {
+                    panic!("Dimension mismatch");
+                }
+
+                let t = Transpose::NoTrans;
+                Gemm::gemm(&Default::one(), t, a, t, xs, &Default::zero(), ys);
+            }
+        }
+    )+
+));
+
+gemv_batched_impl!(f32, f64, Complex32, Complex64);
+
+/// Adds `alpha` to every entry on the main diagonal of `a`.
+///
+/// The diagonal is a single strided vector `lead_dim + 1` apart in either
+/// order, so this is one `axpy`.
+pub trait AddToDiagonal: Sized {
+    fn add_to_diagonal(a: &mut dyn Matrix<Self>, alpha: Self);
+}
+
+macro_rules! add_to_diagonal_impl(($($t: ident), +) => (
+    $(
+        impl AddToDiagonal for $t {
+            fn add_to_diagonal(a: &mut dyn Matrix<$t>, alpha: $t) {
+                let n = a.rows().min(a.cols());
+                let ones: Vec<$t> = vec![Default::one(); n as usize];
+                let mut diag = unsafe { Strided::new(a.as_mut_ptr(), n, a.lead_dim() + 1) };
+                Axpy::axpy(&alpha, &ones, &mut diag);
+            }
+        }
+    )+
+));
+
+add_to_diagonal_impl!(f32, f64, Complex32, Complex64);
+
+/// Cholesky factorization `A = L * L^T` of a symmetric positive definite
+/// matrix, and solves with the resulting factor.
+pub trait Cholesky: Sized {
+    /// Overwrites the lower triangle of `a` with `L`.
+    ///
+    /// Only the lower triangle is read and the strict upper triangle is left
+    /// as it was. Returns `SingularMatrix` if a pivot is not positive, in
+    /// which case `a` is partially overwritten.
+    fn cholesky(a: &mut dyn Matrix<Self>) -> Result<(), BlasError>;
+    /// Solves `L * L^T * x = b` in place, given the factor from `cholesky`.
+    fn cholesky_solve<V: ?Sized + Vector<Self>>(l: &dyn Matrix<Self>, b: &mut V);
+}
+
+macro_rules! cholesky_impl(($($t: ident), +) => (
+    $(
+        impl Cholesky for $t {
+            fn cholesky(a: &mut dyn Matrix<$t>) -> Result<(), BlasError> {
+                let n = a.rows() as usize;
+                if a.cols() as usize != n {
+                    return Err(BlasError::DimensionMismatch { expected: n, found: a.cols() as usize });
+                }
+
+                for j in 0..n {
+                    let lj = row(a, j).take(j as u32);
+                    let d = get(a, j, j) - Dot::dot(&lj, &lj);
+                    if d.is_nan() || d <= 0.0 {
+                        return Err(BlasError::SingularMatrix);
+                    }
+                    let d = d.sqrt();
+                    set(a, j, j, d);
+
+                    for i in (j + 1)..n {
+                        let li = row(a, i).take(j as u32);
+                        let v = (get(a, i, j) - Dot::dot(&li, &lj)) / d;
+                        set(a, i, j, v);
+                    }
+                }
+
+                Ok(())
+            }
+
+            fn cholesky_solve<V: ?Sized + Vector<Self>>(l: &dyn Matrix<$t>, b: &mut V) {
+                let n = l.rows() as usize;
+                if l.cols() as usize != n || b.len() as usize != n {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut x = unsafe { Strided::new(b.as_mut_ptr(), b.len(), b.inc()) };
+                for i in 0..n {
+                    let head = unsafe { Strided::new(x.as_ptr(), i as u32, x.inc()) };
+                    let v = (x.get(i as u32) - Dot::dot(&row(l, i).take(i as u32), &head)) / get(l, i, i);
+                    x.set(i as u32, v);
+                }
+                for i in (0..n).rev() {
+                    let tail = unsafe { Strided::new(x.as_ptr(), n as u32, x.inc()) }.skip(i as u32 + 1);
+                    let v = (x.get(i as u32) - Dot::dot(&col(l, i).skip(i as u32 + 1), &tail)) / get(l, i, i);
+                    x.set(i as u32, v);
+                }
+            }
+        }
+    )+
+));
+
+cholesky_impl!(f32, f64);
+
+/// Solves the ridge regression problem
+/// `min |X * beta - y|^2 + lambda * |beta|^2`.
+///
+/// The normal equations `(X^T X + lambda * I) beta = X^T y` are formed with a
+/// `syrk` and a `gemv`, then solved by Cholesky. With `lambda = 0` this is
+/// ordinary least squares, which fails with `SingularMatrix` if the columns
+/// of `X` are linearly dependent; any positive `lambda` makes the system
+/// solvable. `beta` is only written on success.
+pub trait RidgeSolve: Sized {
+    fn ridge_solve<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        x: &dyn Matrix<Self>,
+        y: &V,
+        lambda: Self,
+        beta: &mut W,
+    ) -> Result<(), BlasError>;
+}
+
+macro_rules! ridge_solve_impl(($($t: ident), +) => (
+    $(
+        impl RidgeSolve for $t {
+            fn ridge_solve<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &dyn Matrix<$t>, y: &V, lambda: $t, beta: &mut W) -> Result<(), BlasError> {
+                let (m, p) = (x.rows() as usize, x.cols() as usize);
+                if y.len() as usize != m {
+                    return Err(BlasError::DimensionMismatch { expected: m, found: y.len() as usize });
+                }
+                if beta.len() as usize != p {
+                    return Err(BlasError::DimensionMismatch { expected: p, found: beta.len() as usize });
+                }
+
+                let mut gram = Mat::fill(0.0, p, p);
+                Syrk::syrk(Symmetry::Lower, Transpose::Trans, &1.0, x, &0.0, &mut gram);
+                AddToDiagonal::add_to_diagonal(&mut gram, lambda);
+
+                let mut rhs = vec![0.0; p];
+                Gemv::gemv(Transpose::Trans, &1.0, x, y, &0.0, &mut rhs);
+                Cholesky::cholesky(&mut gram)?;
+                Cholesky::cholesky_solve(&gram, &mut rhs);
+
+                Copy::copy(&rhs, beta);
+                Ok(())
+            }
+        }
+    )+
+));
+
+ridge_solve_impl!(f32, f64);
+
+/// Estimate of the 2-norm condition number `sigma_max / sigma_min` of `A`,
+/// without LAPACK.
+///
+/// `sigma_max^2` is found by `iters` steps of power iteration on `A^T A`,
+/// two `gemv`s per step. `sigma_min^2` is found by as many steps of inverse
+/// iteration on `A^T A = R^T R`, with `R` from `qr_mgs` and two `trsv`s per
+/// step. Working with `R` rather than forming `A^T A` keeps the condition
+/// number from being squared. Both start from the same fixed vector, so the
+/// estimate is a lower bound that tightens with `iters`. Returns infinity if
+/// `R` has a zero on its diagonal, and always when `A` has more columns than
+/// rows.
+pub trait Cond2Estimate: Sized {
+    fn cond_2_estimate(a: &dyn Matrix<Self>, iters: usize) -> Self;
+}
+
+macro_rules! cond_2_estimate_impl(($($t: ident), +) => (
+    $(
+        impl Cond2Estimate for $t {
+            fn cond_2_estimate(a: &dyn Matrix<$t>, iters: usize) -> $t {
+                let (m, n) = (a.rows() as usize, a.cols() as usize);
+                if n > m {
+                    return <$t>::INFINITY;
+                }
+                let (_, r) = QrMgs::qr_mgs(a);
+                if (0..n).any(|j| get(&r, j, j) == 0.0) {
+                    return <$t>::INFINITY;
+                }
+
+                let start: Vec<$t> = (0..n).map(|i| 1.0 / (i + 1) as $t).collect();
+                let normalize = |v: &mut Vec<$t>| {
+                    let norm: $t = Nrm2::nrm2(v);
+                    Scal::scal(&norm.recip(), v);
+                };
+                let mut av = vec![0.0; m];
+
+                let mut v = start.clone();
+                normalize(&mut v);
+                for _ in 0..iters {
+                    Gemv::gemv(Transpose::NoTrans, &1.0, a, &v, &0.0, &mut av);
+                    Gemv::gemv(Transpose::Trans, &1.0, a, &av, &0.0, &mut v);
+                    normalize(&mut v);
+                }
+                Gemv::gemv(Transpose::NoTrans, &1.0, a, &v, &0.0, &mut av);
+                let sigma_max: $t = Nrm2::nrm2(&av);
+
+                let mut v = start;
+                normalize(&mut v);
+                let (upper, unit) = (Symmetry::Upper, Diagonal::NonUnit);
+                for _ in 0..iters {
+                    Trsv::trsv(upper, Transpose::Trans, unit, &r, &mut v);
+                    Trsv::trsv(upper, Transpose::NoTrans, unit, &r, &mut v);
+                    normalize(&mut v);
+                }
+                Gemv::gemv(Transpose::NoTrans, &1.0, a, &v, &0.0, &mut av);
+                let sigma_min: $t = Nrm2::nrm2(&av);
+
+                sigma_max / sigma_min
+            }
+        }
+    )+
+));
+
+cond_2_estimate_impl!(f32, f64);
+
+/// Truncated SVD `A ~ U * diag(s) * V^T` of the leading `rank` singular
+/// triplets, by randomized range finding.
+///
+/// `A` is multiplied by a Gaussian random matrix with `rank + oversample`
+/// columns, the product is orthonormalized by QR into `Q`, and the small
+/// matrix `Q^T A` is decomposed by a dense SVD. The result is exact when
+/// `rank + oversample` is at least the rank of `A`, and otherwise accurate
+/// when the singular values beyond `rank` decay quickly. Returns `U` as
+/// `m x rank`, the singular values in decreasing order, and `V^T` as
+/// `rank x n`.
+#[cfg(all(feature = "lapack", feature = "rand"))]
+pub trait RandomizedSvd: Sized {
+    fn randomized_svd(
+        a: &dyn Matrix<Self>,
+        rank: usize,
+        oversample: usize,
+    ) -> (Mat<Self>, Vec<Self>, Mat<Self>);
+}
+
+#[cfg(all(feature = "lapack", feature = "rand"))]
+macro_rules! randomized_svd_impl(($($t: ident), +) => (
+    $(
+        impl RandomizedSvd for $t {
+            fn randomized_svd(a: &dyn Matrix<$t>, rank: usize, oversample: usize) -> (Mat<$t>, Vec<$t>, Mat<$t>) {
+                let (m, n) = (a.rows() as usize, a.cols() as usize);
+                if rank == 0 || rank > m.min(n) {
+                    panic!("Rank {} out of range for {}x{} matrix", rank, m, n);
+                }
+                let l = (rank + oversample).min(m.min(n));
+                let a = owned(a);
+                let t = Transpose::NoTrans;
+
+                // Standard normal samples by the Box-Muller transform.
+                let mut rng = rand::thread_rng();
+                let omega: Vec<$t> = (0..n * l)
+                    .map(|_| {
+                        let (u1, u2) = (1.0 - rng.gen::<$t>(), rng.gen::<$t>());
+                        (-2.0 * u1.ln()).sqrt() * (2.0 * std::$t::consts::PI * u2).cos()
+                    })
+                    .collect();
+                let omega = Mat::new_from_data(n, l, omega);
+
+                let mut q = Mat::fill(0.0, m, l);
+                Gemm::gemm(&1.0, t, &a, t, &omega, &0.0, &mut q);
+
+                let mut tau = vec![0.0; l];
+                unsafe {
+                    let info = lapacke!($t, geqrf)(Order::RowMajor, m as u32, l as u32, q.as_mut_ptr(), l as u32, tau.as_mut_ptr());
+                    assert_eq!(info, 0, "geqrf failed");
+                    let info = lapacke!($t, orgqr)(Order::RowMajor, m as u32, l as u32, l as u32, q.as_mut_ptr(), l as u32, tau.as_ptr());
+                    assert_eq!(info, 0, "orgqr failed");
+                }
+
+                let mut b = Mat::fill(0.0, l, n);
+                Gemm::gemm(&1.0, Transpose::Trans, &q, t, &a, &0.0, &mut b);
+
+                let mut s = vec![0.0; l];
+                let mut ub = Mat::fill(0.0, l, l);
+                let mut vt = Mat::fill(0.0, l, n);
+                let mut superb = vec![0.0; l];
+                unsafe {
+                    let info = lapacke!($t, gesvd)(Order::RowMajor, b'S' as _, b'S' as _,
+                        l as u32, n as u32, b.as_mut_ptr(), n as u32,
+                        s.as_mut_ptr(),
+                        ub.as_mut_ptr(), l as u32,
+                        vt.as_mut_ptr(), n as u32,
+                        superb.as_mut_ptr());
+                    assert_eq!(info, 0, "gesvd failed");
+                }
+
+                let mut qub = Mat::fill(0.0, m, l);
+                Gemm::gemm(&1.0, t, &q, t, &ub, &0.0, &mut qub);
+                let mut u = Mat::fill(0.0, m, rank);
+                for i in 0..m {
+                    Copy::copy(&row(&qub, i).take(rank as u32), &mut row_mut(&mut u, i));
+                }
+                s.truncate(rank);
+                let mut v = Mat::fill(0.0, rank, n);
+                for i in 0..rank {
+                    Copy::copy(&row(&vt, i), &mut row_mut(&mut v, i));
+                }
+
+                (u, s, v)
+            }
+        }
+    )+
+));
+
+#[cfg(all(feature = "lapack", feature = "rand"))]
+randomized_svd_impl!(f32, f64);
+
+/// Eigenvalues and eigenvectors of a symmetric, or for complex types
+/// Hermitian, matrix.
+///
+/// Only the triangle of `a` named by `uplo` is read. Returns the eigenvalues
+/// in ascending order and the matching orthonormal eigenvectors as the
+/// columns of a matrix, through LAPACK `?syev` or `?heev`.
+#[cfg(feature = "lapack")]
+pub trait SymmetricEig: Sized {
+    /// The type of the eigenvalues, which are real even for complex input.
+    type Real;
+
+    fn symmetric_eig(
+        a: &dyn Matrix<Self>,
+        uplo: Symmetry,
+    ) -> Result<(Vec<Self::Real>, Mat<Self>), BlasError>;
+}
+
+#[cfg(feature = "lapack")]
+macro_rules! symmetric_eig_impl(($f: ident, $real: ident, $($t: ident), +) => (
+    $(
+        impl SymmetricEig for $t {
+            type Real = $real;
+
+            fn symmetric_eig(a: &dyn Matrix<$t>, uplo: Symmetry) -> Result<(Vec<$real>, Mat<$t>), BlasError> {
+                let n = a.rows() as usize;
+                if a.cols() as usize != n {
+                    return Err(BlasError::DimensionMismatch { expected: n, found: a.cols() as usize });
+                }
+
+                let uplo = match uplo {
+                    Symmetry::Upper => b'U',
+                    Symmetry::Lower => b'L',
+                };
+                let mut v = owned(a);
+                let mut w = vec![0.0; n];
+                let info = unsafe {
+                    lapacke!($t, $f)(Order::RowMajor, b'V' as _, uplo as _, n as u32, v.as_mut_ptr() as *mut _, n as u32, w.as_mut_ptr())
+                };
+                match info {
+                    0 => Ok((w, v)),
+                    i if i > 0 => Err(BlasError::NoConvergence),
+                    i => panic!("{} failed with info {}", stringify!($f), i),
+                }
+            }
+        }
+    )+
+));
+
+#[cfg(feature = "lapack")]
+symmetric_eig_impl!(syev, f32, f32);
+#[cfg(feature = "lapack")]
+symmetric_eig_impl!(syev, f64, f64);
+#[cfg(feature = "lapack")]
+symmetric_eig_impl!(heev, f32, Complex32);
+#[cfg(feature = "lapack")]
+symmetric_eig_impl!(heev, f64, Complex64);
+
+/// Thin QR factorization `A = Q * R`.
+///
+/// For an `m x n` matrix `A` with `k = min(m, n)`, `Q` is `m x k` with
+/// orthonormal columns and `R` is `k x n` upper triangular (trapezoidal when
+/// `n > m`). LAPACK `?geqrf` computes the factorization as Householder
+/// reflectors, `R` is read off its upper triangle, and `?orgqr` (`?ungqr`
+/// for complex types) turns the reflectors into an explicit `Q`.
+#[cfg(feature = "lapack")]
+pub trait Qr: Sized {
+    fn qr(a: &dyn Matrix<Self>) -> Result<(Mat<Self>, Mat<Self>), BlasError>;
+}
+
+#[cfg(feature = "lapack")]
+macro_rules! qr_impl(($orgqr: ident, $($t: ident), +) => (
+    $(
+        impl Qr for $t {
+            fn qr(a: &dyn Matrix<$t>) -> Result<(Mat<$t>, Mat<$t>), BlasError> {
+                let (m, n) = (a.rows() as usize, a.cols() as usize);
+                let k = m.min(n);
+                let mut f = owned(a);
+                let mut tau: Vec<$t> = vec![Default::zero(); k];
+                let info = unsafe {
+                    lapacke!($t, geqrf)(Order::RowMajor, m as u32, n as u32, f.as_mut_ptr() as *mut _, n as u32, tau.as_mut_ptr() as *mut _)
+                };
+                if info != 0 {
+                    panic!("geqrf failed with info {}", info);
+                }
+
+                let mut r = Mat::fill(Default::zero(), k, n);
+                for i in 0..k {
+                    Copy::copy(&row(&f, i).skip(i as u32), &mut row_mut(&mut r, i).skip(i as u32));
+                }
+
+                let mut q = Mat::fill(Default::zero(), m, k);
+                for i in 0..m {
+                    Copy::copy(&row(&f, i).take(k as u32), &mut row_mut(&mut q, i));
+                }
+                let info = unsafe {
+                    lapacke!($t, $orgqr)(Order::RowMajor, m as u32, k as u32, k as u32, q.as_mut_ptr() as *mut _, k as u32, tau.as_ptr() as *const _)
+                };
+                if info != 0 {
+                    panic!("{} failed with info {}", stringify!($orgqr), info);
+                }
+
+                Ok((q, r))
+            }
+        }
+    )+
+));
+
+#[cfg(feature = "lapack")]
+qr_impl!(orgqr, f32, f64);
+#[cfg(feature = "lapack")]
+qr_impl!(ungqr, Complex32, Complex64);
+
+/// Thin QR factorization `A = Q * R` by modified Gram-Schmidt, for use
+/// without LAPACK.
+///
+/// For an `m x n` matrix `A`, `Q` is `m x n` and `R` is `n x n` upper
+/// triangular. Column `j` of `Q` is normalized with `nrm2` and `scal`, then
+/// its component is removed from every later column with `dot` and `axpy`.
+/// The loss of orthogonality in `Q` grows with the condition number of
+/// `A`, where Householder QR (`Qr`) stays orthogonal to working precision,
+/// so prefer `Qr` when it is available. A column that depends linearly on
+/// the ones before it leaves a zero, up to rounding, on the diagonal of `R`,
+/// and the matching column of `Q` is then meaningless.
+pub trait QrMgs: Sized {
+    fn qr_mgs(a: &dyn Matrix<Self>) -> (Mat<Self>, Mat<Self>);
+}
+
+macro_rules! qr_mgs_impl(($($t: ident), +) => (
+    $(
+        impl QrMgs for $t {
+            fn qr_mgs(a: &dyn Matrix<$t>) -> (Mat<$t>, Mat<$t>) {
+                let n = a.cols() as usize;
+                let mut q = owned(a);
+                let mut r = Mat::fill(0.0, n, n);
+
+                for j in 0..n {
+                    let norm: $t = Nrm2::nrm2(&col(&q, j));
+                    set(&mut r, j, j, norm);
+                    if norm > 0.0 {
+                        Scal::scal(&norm.recip(), &mut col_mut(&mut q, j));
+                    }
+
+                    for k in (j + 1)..n {
+                        let qj = col(&q, j);
+                        let rjk: $t = Dot::dot(&qj, &col(&q, k));
+                        set(&mut r, j, k, rjk);
+                        Axpy::axpy(&-rjk, &qj, &mut col_mut(&mut q, k));
+                    }
+                }
+
+                (q, r)
+            }
+        }
+    )+
+));
+
+qr_mgs_impl!(f32, f64);
+
+/// Fixes the arbitrary sign (or phase) of every column of `vectors`, in
+/// place, so that eigenvectors and singular vectors compare equal across
+/// runs and libraries.
+///
+/// Each column is scaled with `scal` so that its entry picked by `iamax`
+/// becomes real and positive. For complex columns `iamax` ranks entries by
+/// `|re| + |im|`, so a tie in true magnitude is broken the same way BLAS
+/// breaks it. Zero columns are left as they are.
+pub trait CanonicalizeSigns: Sized {
+    fn canonicalize_signs(vectors: &mut dyn Matrix<Self>);
+}
+
+macro_rules! canonicalize_signs_impl(($phase: expr, $($t: ident), +) => (
+    $(
+        impl CanonicalizeSigns for $t {
+            fn canonicalize_signs(vectors: &mut dyn Matrix<$t>) {
+                // The unit factor that rotates a nonzero `v` onto the positive
+                // real axis, or `None` for zero.
+                let phase: fn($t) -> Option<$t> = $phase;
+                for j in 0..vectors.cols() as usize {
+                    let c = col(vectors, j);
+                    if c.is_empty() {
+                        continue;
+                    }
+                    if let Some(scale) = phase(c.get(Iamax::iamax(&c) as u32)) {
+                        Scal::scal(&scale, &mut col_mut(vectors, j));
+                    }
+                }
+            }
+        }
+    )+
+));
+
+canonicalize_signs_impl!(|v| if v < 0.0 { Some(-1.0) } else { None }, f32, f64);
+canonicalize_signs_impl!(
+    |v| if v.norm() > 0.0 {
+        Some(v.conj() / v.norm())
+    } else {
+        None
+    },
+    Complex32,
+    Complex64
+);
+
+/// Solves `T x = b` where `T` is the symmetric Toeplitz matrix with first
+/// column `first_col`, by Levinson recursion in `O(n^2)` operations.
+///
+/// The recursion grows the solution one row at a time together with the
+/// vector `f` solving `T_k f = e_1`; each step costs two `dot`s and a few
+/// `axpy`s. It needs every leading block of `T` to be nonsingular, which
+/// holds for instance when `T` is positive definite, and returns
+/// `SingularMatrix` if one is not (in particular if `first_col[0]` is zero).
+/// `x` is only written on success.
+pub trait Levinson: Sized {
+    fn levinson<U, V, W>(first_col: &U, b: &V, x: &mut W) -> Result<(), BlasError>
+    where
+        U: ?Sized + Vector<Self>,
+        V: ?Sized + Vector<Self>,
+        W: ?Sized + Vector<Self>;
+}
+
+macro_rules! levinson_impl(($($t: ident), +) => (
+    $(
+        impl Levinson for $t {
+            fn levinson<U, V, W>(first_col: &U, b: &V, x: &mut W) -> Result<(), BlasError>
+            where
+                U: ?Sized + Vector<Self>,
+                V: ?Sized + Vector<Self>,
+                W: ?Sized + Vector<Self>,
+            {
+                let n = first_col.len() as usize;
+                for &found in &[b.len() as usize, x.len() as usize] {
+                    if found != n {
+                        return Err(BlasError::DimensionMismatch { expected: n, found });
+                    }
+                }
+                if n == 0 {
+                    return Ok(());
+                }
+
+                let mut r = vec![0.0; n];
+                Copy::copy(first_col, &mut r);
+                let mut rhs = vec![0.0; n];
+                Copy::copy(b, &mut rhs);
+                if r[0] == 0.0 {
+                    return Err(BlasError::SingularMatrix);
+                }
+                // Row k of T_{k+1} left of the diagonal is `rev[n - 1 - k..n - 1]`.
+                let rev: Vec<$t> = r.iter().rev().copied().collect();
+
+                let mut f = vec![0.0; n];
+                let mut back = vec![0.0; n];
+                let mut sol = vec![0.0; n];
+                f[0] = 1.0 / r[0];
+                back[0] = f[0];
+                sol[0] = rhs[0] / r[0];
+
+                for k in 1..n {
+                    let row = &rev[n - 1 - k..n - 1];
+                    let err = Dot::dot(row, &f[..k]);
+                    let denom = 1.0 - err * err;
+                    if denom == 0.0 {
+                        return Err(BlasError::SingularMatrix);
+                    }
+
+                    // f <- ([f; 0] - err [0; back]) / denom, and back = reverse(f).
+                    let mut shifted = vec![0.0; k + 1];
+                    Copy::copy(&back[..k], &mut shifted[1..]);
+                    Axpy::axpy(&-err, &shifted, &mut f[..k + 1]);
+                    Scal::scal(&(1.0 / denom), &mut f[..k + 1]);
+                    for i in 0..=k {
+                        back[i] = f[k - i];
+                    }
+
+                    let miss = rhs[k] - Dot::dot(row, &sol[..k]);
+                    Axpy::axpy(&miss, &back[..k + 1], &mut sol[..k + 1]);
+                }
+
+                Copy::copy(&sol, x);
+                Ok(())
+            }
+        }
+    )+
+));
+
+levinson_impl!(f32, f64);
+
+/// Schur complement `D - C * A^-1 * B` of the block `A` in the matrix
+/// `[A, B; C, D]`.
+///
+/// `A` is not inverted: `A^-1 * B` comes from an LU solve with LAPACK
+/// `?gesv`, and the product with `C` is a single `gemm` into a copy of `D`.
+/// Returns `SingularMatrix` if `A` is exactly singular.
+#[cfg(feature = "lapack")]
+pub trait SchurComplement: Sized {
+    fn schur_complement(
+        a: &dyn Matrix<Self>,
+        b: &dyn Matrix<Self>,
+        c: &dyn Matrix<Self>,
+        d: &dyn Matrix<Self>,
+    ) -> Result<Mat<Self>, BlasError>;
+}
+
+#[cfg(feature = "lapack")]
+macro_rules! schur_complement_impl(($($t: ident), +) => (
+    $(
+        impl SchurComplement for $t {
+            fn schur_complement(a: &dyn Matrix<$t>, b: &dyn Matrix<$t>, c: &dyn Matrix<$t>, d: &dyn Matrix<$t>) -> Result<Mat<$t>, BlasError> {
+                let p = a.rows() as usize;
+                let (q, r) = (d.rows() as usize, d.cols() as usize);
+                for &(expected, found) in &[
+                    (p, a.cols() as usize),
+                    (p, b.rows() as usize),
+                    (r, b.cols() as usize),
+                    (q, c.rows() as usize),
+                    (p, c.cols() as usize),
+                ] {
+                    if found != expected {
+                        return Err(BlasError::DimensionMismatch { expected, found });
+                    }
+                }
+
+                let mut lu = owned(a);
+                let mut x = owned(b);
+                let mut ipiv = vec![0; p];
+                let info = unsafe {
+                    lapacke!($t, gesv)(Order::RowMajor, p as u32, r as u32, lu.as_mut_ptr(), p.max(1) as u32, ipiv.as_mut_ptr(), x.as_mut_ptr(), r.max(1) as u32)
+                };
+                match info {
+                    0 => {}
+                    i if i > 0 => return Err(BlasError::SingularMatrix),
+                    i => panic!("gesv failed with info {}", i),
+                }
+
+                let mut s = owned(d);
+                Gemm::gemm(&-1.0, Transpose::NoTrans, c, Transpose::NoTrans, &x, &1.0, &mut s);
+                Ok(s)
+            }
+        }
+    )+
+));
+
+#[cfg(feature = "lapack")]
+schur_complement_impl!(f32, f64);
+
+/// Solves `A x = b` for square `A`, then improves `x` with `iters` rounds of
+/// iterative refinement.
+///
+/// `A` is LU-factored once with `?getrf`. Each round computes the residual
+/// `r = b - A x` with `gemv`, solves `A d = r` with the existing factors
+/// (`?getrs`) and adds the correction to `x`. For `f32` the residual is
+/// formed in `f64`, which is what lets refinement recover digits lost to an
+/// ill-conditioned `A`; for `f64` it is formed in working precision and
+/// mainly cleans up a poorly pivoted solve.
+///
+/// Returns `SingularMatrix` if `A` is exactly singular, in which case `x`
+/// is left untouched.
+#[cfg(feature = "lapack")]
+pub trait SolveRefined: Sized {
+    fn solve_refined<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        a: &dyn Matrix<Self>,
+        b: &V,
+        x: &mut W,
+        iters: usize,
+    ) -> Result<(), BlasError>;
+}
+
+#[cfg(feature = "lapack")]
+macro_rules! solve_refined_impl(($($t: ident => $hi: ident), +) => (
+    $(
+        impl SolveRefined for $t {
+            fn solve_refined<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(a: &dyn Matrix<$t>, b: &V, x: &mut W, iters: usize) -> Result<(), BlasError> {
+                let n = a.rows() as usize;
+                for &found in &[a.cols() as usize, b.len() as usize, x.len() as usize] {
+                    if found != n {
+                        return Err(BlasError::DimensionMismatch { expected: n, found });
+                    }
+                }
+
+                let mut lu = owned(a);
+                let mut ipiv = vec![0; n];
+                let info = unsafe {
+                    lapacke!($t, getrf)(Order::RowMajor, n as u32, n as u32, lu.as_mut_ptr(), n.max(1) as u32, ipiv.as_mut_ptr())
+                };
+                match info {
+                    0 => {}
+                    i if i > 0 => return Err(BlasError::SingularMatrix),
+                    i => panic!("getrf failed with info {}", i),
+                }
+                let lu_solve = |rhs: &mut Vec<$t>| {
+                    let info = unsafe {
+                        lapacke!($t, getrs)(Order::RowMajor, b'N' as _, n as u32, 1, lu.as_ptr(), n.max(1) as u32, ipiv.as_ptr(), rhs.as_mut_ptr(), 1)
+                    };
+                    if info != 0 {
+                        panic!("getrs failed with info {}", info);
+                    }
+                };
+
+                let mut sol = vec![0.0; n];
+                Copy::copy(b, &mut sol);
+                let b_hi: Vec<$hi> = sol.iter().map(|&v| v as $hi).collect();
+                lu_solve(&mut sol);
+
+                let mut a_hi: Mat<$hi> = Mat::fill(0.0, n, n);
+                for i in 0..n {
+                    for j in 0..n {
+                        set(&mut a_hi, i, j, get(a, i, j) as $hi);
+                    }
+                }
+
+                for _ in 0..iters {
+                    let mut r = b_hi.clone();
+                    let x_hi: Vec<$hi> = sol.iter().map(|&v| v as $hi).collect();
+                    Gemv::gemv(Transpose::NoTrans, &-1.0, &a_hi, &x_hi, &1.0, &mut r);
+
+                    let mut d: Vec<$t> = r.iter().map(|&v| v as $t).collect();
+                    lu_solve(&mut d);
+                    Axpy::axpy(&1.0, &d, &mut sol);
+                }
+
+                Copy::copy(&sol, x);
+                Ok(())
+            }
+        }
+    )+
+));
+
+#[cfg(feature = "lapack")]
+solve_refined_impl!(f32 => f64, f64 => f64);
+
+/// The matrix sign function of a square `A`: the matrix with the same
+/// eigenvectors as `A` and eigenvalue `+1` or `-1` in place of each
+/// eigenvalue in the right or left half-plane.
+///
+/// Uses the Newton iteration `X <- (X + X^-1) / 2` from `X = A`, with every
+/// inverse computed by `?gesv` against the identity. It converges
+/// quadratically once close, so `iters` of a few dozen is plenty unless `A`
+/// has eigenvalues very near the imaginary axis. Stops early once a step
+/// changes `X` by a few ulps in the Frobenius norm, and otherwise returns
+/// the iterate after `iters` steps. Returns `SingularMatrix` if an iterate
+/// cannot be inverted, as happens for a zero eigenvalue.
+#[cfg(feature = "lapack")]
+pub trait MatrixSign: Sized {
+    fn matrix_sign(a: &dyn Matrix<Self>, iters: usize) -> Result<Mat<Self>, BlasError>;
+}
+
+#[cfg(feature = "lapack")]
+macro_rules! matrix_sign_impl(($($t: ident), +) => (
+    $(
+        impl MatrixSign for $t {
+            fn matrix_sign(a: &dyn Matrix<$t>, iters: usize) -> Result<Mat<$t>, BlasError> {
+                let n = a.rows() as usize;
+                if a.cols() as usize != n {
+                    return Err(BlasError::DimensionMismatch { expected: n, found: a.cols() as usize });
+                }
+                let frobenius = |m: &Mat<$t>| Nrm2::nrm2(&unsafe { Strided::new(m.as_ptr(), (n * n) as u32, 1) });
+
+                let mut x = owned(a);
+                for _ in 0..iters {
+                    let mut lu = owned(&x);
+                    let mut next = Mat::fill(0.0, n, n);
+                    AddToDiagonal::add_to_diagonal(&mut next, 1.0);
+                    let mut ipiv = vec![0; n];
+                    let info = unsafe {
+                        lapacke!($t, gesv)(Order::RowMajor, n as u32, n as u32, lu.as_mut_ptr(), n.max(1) as u32, ipiv.as_mut_ptr(), next.as_mut_ptr(), n.max(1) as u32)
+                    };
+                    match info {
+                        0 => {}
+                        i if i > 0 => return Err(BlasError::SingularMatrix),
+                        i => panic!("gesv failed with info {}", i),
+                    }
+
+                    Axpy::axpy_mat(&1.0, &x, &mut next);
+                    Scal::scal_mat(&0.5, &mut next);
+                    // x now holds the step just taken, negated.
+                    Axpy::axpy_mat(&-1.0, &next, &mut x);
+                    let change = frobenius(&x);
+                    x = next;
+                    if change <= 4.0 * <$t>::EPSILON * frobenius(&x) {
+                        break;
+                    }
+                }
+
+                Ok(x)
+            }
+        }
+    )+
+));
+
+#[cfg(feature = "lapack")]
+matrix_sign_impl!(f32, f64);
+
+/// The polar decomposition `A = U * P` of a square nonsingular `A`, into an
+/// orthogonal `U` and a symmetric positive definite `P`, returned as
+/// `(U, P)`.
+///
+/// `U` comes from the Newton iteration `X <- (z X + X^-T / z) / 2` from
+/// `X = A`, where the scale `z = sqrt(||X^-1||_F / ||X||_F)` speeds up the
+/// early steps when `A` is badly scaled. Inverses come from `?gesv` and the
+/// stopping rule is that of `MatrixSign`. `P` is then `U^T A`, symmetrized
+/// to remove rounding. Returns `SingularMatrix` if an iterate cannot be
+/// inverted.
+#[cfg(feature = "lapack")]
+pub trait PolarDecomposition: Sized {
+    fn polar_decomposition(
+        a: &dyn Matrix<Self>,
+        iters: usize,
+    ) -> Result<(Mat<Self>, Mat<Self>), BlasError>;
+}
+
+#[cfg(feature = "lapack")]
+macro_rules! polar_decomposition_impl(($($t: ident), +) => (
+    $(
+        impl PolarDecomposition for $t {
+            fn polar_decomposition(a: &dyn Matrix<$t>, iters: usize) -> Result<(Mat<$t>, Mat<$t>), BlasError> {
+                let n = a.rows() as usize;
+                if a.cols() as usize != n {
+                    return Err(BlasError::DimensionMismatch { expected: n, found: a.cols() as usize });
+                }
+                let frobenius = |m: &Mat<$t>| Nrm2::nrm2(&unsafe { Strided::new(m.as_ptr(), (n * n) as u32, 1) });
+
+                let mut x = owned(a);
+                for _ in 0..iters {
+                    let mut lu = owned(&x);
+                    let mut inv = Mat::fill(0.0, n, n);
+                    AddToDiagonal::add_to_diagonal(&mut inv, 1.0);
+                    let mut ipiv = vec![0; n];
+                    let info = unsafe {
+                        lapacke!($t, gesv)(Order::RowMajor, n as u32, n as u32, lu.as_mut_ptr(), n.max(1) as u32, ipiv.as_mut_ptr(), inv.as_mut_ptr(), n.max(1) as u32)
+                    };
+                    match info {
+                        0 => {}
+                        i if i > 0 => return Err(BlasError::SingularMatrix),
+                        i => panic!("gesv failed with info {}", i),
+                    }
+
+                    let norm = frobenius(&x);
+                    let z = (frobenius(&inv) / norm).sqrt();
+                    let mut next = Mat::fill(0.0, n, n);
+                    for i in 0..n {
+                        for j in 0..n {
+                            set(&mut next, i, j, 0.5 * (z * x[i][j] + inv[j][i] / z));
+                        }
+                    }
+
+                    // x now holds the step just taken, negated.
+                    Axpy::axpy_mat(&-1.0, &next, &mut x);
+                    let change = frobenius(&x);
+                    x = next;
+                    if change <= 4.0 * <$t>::EPSILON * frobenius(&x) {
+                        break;
+                    }
+                }
+
+                let mut p = Mat::fill(0.0, n, n);
+                Gemm::gemm(&0.5, Transpose::Trans, &x, Transpose::NoTrans, a, &0.0, &mut p);
+                Gemm::gemm(&0.5, Transpose::Trans, a, Transpose::NoTrans, &x, &1.0, &mut p);
+                Ok((x, p))
+            }
+        }
+    )+
+));
+
+#[cfg(feature = "lapack")]
+polar_decomposition_impl!(f32, f64);
+
+/// Computes `C = alpha * op(A) * op(B) + beta * C` like `Gemm::gemm`, but
+/// single-threaded in plain Rust with a fixed summation order.
+///
+/// Every entry is a sum over `k` in increasing order, so the result is bit
+/// for bit the same on every run, independent of the BLAS library and its
+/// threading. It is much slower than `gemm` and meant for tests and other
+/// places where reproducibility matters more. As in BLAS, `C` is not read
+/// when `beta` is zero.
+#[cfg(feature = "deterministic-gemm")]
+pub trait GemmDeterministic: Sized {
+    fn gemm_deterministic(
+        alpha: &Self,
+        at: Transpose,
+        a: &dyn Matrix<Self>,
+        bt: Transpose,
+        b: &dyn Matrix<Self>,
+        beta: &Self,
+        c: &mut dyn Matrix<Self>,
+    );
+}
+
+#[cfg(feature = "deterministic-gemm")]
+macro_rules! gemm_deterministic_impl(($conj: expr, $($t: ident), +) => (
+    $(
+        impl GemmDeterministic for $t {
+            fn gemm_deterministic(alpha: &$t, at: Transpose, a: &dyn Matrix<$t>, bt: Transpose, b: &dyn Matrix<$t>, beta: &$t, c: &mut dyn Matrix<$t>) {
+                let conj: fn($t) -> $t = $conj;
+                let op = |m: &dyn Matrix<$t>, t: Transpose, i: usize, j: usize| match t {
+                    Transpose::NoTrans => get(m, i, j),
+                    Transpose::Trans => get(m, j, i),
+                    Transpose::ConjTrans => conj(get(m, j, i)),
+                };
+                let dims = |m: &dyn Matrix<$t>, t: Transpose| match t {
+                    Transpose::NoTrans => (m.rows() as usize, m.cols() as usize),
+                    _ => (m.cols() as usize, m.rows() as usize),
+                };
+
+                let ((m, k), (kb, n)) = (dims(a, at), dims(b, bt));
+                if k != kb || c.rows() as usize != m || c.cols() as usize != n {
+                    panic!("Dimension mismatch");
+                }
+
+                let zero: $t = Default::zero();
+                for i in 0..m {
+                    for j in 0..n {
+                        let mut sum = zero;
+                        for l in 0..k {
+                            sum += op(a, at, i, l) * op(b, bt, l, j);
+                        }
+
+                        let v = if *beta == zero {
+                            *alpha * sum
+                        } else {
+                            *alpha * sum + *beta * get(c, i, j)
+                        };
+                        set(c, i, j, v);
+                    }
+                }
+            }
+        }
+    )+
+));
+
+#[cfg(feature = "deterministic-gemm")]
+gemm_deterministic_impl!(|x| x, f32, f64);
+#[cfg(feature = "deterministic-gemm")]
+gemm_deterministic_impl!(|x| x.conj(), Complex32, Complex64);
+
+#[cfg(test)]
+mod gemm_scaled_tests {
+    use crate::attribute::Transpose;
+    use crate::mat;
+    use crate::math::linalg::{GemmScaled, ScalPow2};
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+
+    #[test]
+    fn scal_pow2() {
+        let mut x = vec![3f32, -0.5, 1.0];
+
+        ScalPow2::scal_pow2(-2, &mut x);
+        assert_eq!(x, vec![0.75, -0.125, 0.25]);
+    }
+
+    #[test]
+    fn overflow() {
+        let max = f32::MAX;
+        let a = mat![max, max / 2.0];
+        let b = mat![2f32, 0.5; -3.0, 0.5];
+        let t = Transpose::NoTrans;
+
+        let mut plain = Mat::fill(0f32, 1, 2);
+        Gemm::gemm(&1.0, t, &a, t, &b, &0.0, &mut plain);
+        assert!(!plain[0][0].is_finite());
+
+        let mut c = Mat::fill(0f32, 1, 2);
+        GemmScaled::gemm_scaled(&a, &b, &mut c);
+        assert!((c[0][0] / (max / 2.0) - 1.0).abs() < 1e-6);
+        assert!((c[0][1] / (max * 0.75) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn both_near_max() {
+        let max = f32::MAX;
+        let a = mat![max, 0.0; 0.0, 1e-10];
+        let b = mat![0.5f32, 0.0; max, 3.4e18];
+
+        let mut c = Mat::fill(0f32, 2, 2);
+        GemmScaled::gemm_scaled(&a, &b, &mut c);
+        assert!((c[0][0] / (max / 2.0) - 1.0).abs() < 1e-6);
+        assert_eq!(c[0][1], 0.0);
+        assert!((c[1][0] / (max * 1e-10) - 1.0).abs() < 1e-6);
+        assert!((c[1][1] / 3.4e8 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn no_scaling_needed() {
+        let a = mat![1f64, 2.0; 3.0, 4.0];
+        let b = mat![-1f64, 3.0; 1.0, 1.0];
+
+        let mut c = Mat::fill(0f64, 2, 2);
+        GemmScaled::gemm_scaled(&a, &b, &mut c);
+        assert_eq!(c, mat![1.0, 5.0; 1.0, 13.0]);
+    }
+
+    #[test]
+    fn infinite_input() {
+        let a = mat![f64::INFINITY, 1.0];
+        let b = mat![1f64; 2.0];
+
+        let mut c = Mat::fill(0f64, 1, 1);
+        GemmScaled::gemm_scaled(&a, &b, &mut c);
+        assert_eq!(c[0][0], f64::INFINITY);
+    }
+
+    #[test]
+    fn empty_inner() {
+        let a = Mat::fill(0f32, 2, 0);
+        let b = Mat::fill(0f32, 0, 2);
+
+        let mut c = Mat::fill(1f32, 2, 2);
+        GemmScaled::gemm_scaled(&a, &b, &mut c);
+        assert_eq!(c, Mat::fill(0.0, 2, 2));
+    }
+}
+
+#[cfg(test)]
+mod eliminate_column_tests {
+    use crate::mat;
+    use crate::math::linalg::EliminateColumn;
+    use crate::math::Mat;
+
+    #[test]
+    fn real() {
+        let mut a = mat![1f64, 2.0, 3.0; 4.0, 5.0, 6.0; 7.0, 8.0, 10.0];
+
+        let pivot = EliminateColumn::eliminate_column(&mut a, 0);
+
+        assert_eq!(pivot, 2);
+        assert_eq!(a[0], [7.0, 8.0, 10.0]);
+        assert_eq!(a[1][0], 0.0);
+        assert_eq!(a[2][0], 0.0);
+        assert!((a[1][1] - (5.0 - 8.0 * 4.0 / 7.0)).abs() < 1e-12);
+        assert!((a[2][2] - (3.0 - 10.0 / 7.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn second_column() {
+        let mut a = mat![2f32, 1.0, 1.0; 0.0, 1.0, 2.0; 0.0, -3.0, 1.0];
+
+        let pivot = EliminateColumn::eliminate_column(&mut a, 1);
+
+        assert_eq!(pivot, 2);
+        assert_eq!(a[0], [2.0, 1.0, 1.0]);
+        assert_eq!(a[1], [0.0, -3.0, 1.0]);
+        assert_eq!(a[2][1], 0.0);
+        assert!((a[2][2] - 7.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn zero_column() {
+        let mut a = mat![0f32, 1.0; 0.0, 2.0];
+
+        let pivot = EliminateColumn::eliminate_column(&mut a, 0);
+
+        assert_eq!(pivot, 0);
+        assert_eq!(a, mat![0f32, 1.0; 0.0, 2.0]);
+    }
+}
+
+#[cfg(test)]
+mod add_to_diagonal_tests {
+    use crate::mat;
+    use crate::math::linalg::AddToDiagonal;
+    use crate::math::Mat;
+
+    #[test]
+    fn real() {
+        let mut a = mat![1f32, 2.0, 3.0; 4.0, 5.0, 6.0];
+
+        AddToDiagonal::add_to_diagonal(&mut a, 10.0);
+        assert_eq!(a, mat![11.0, 2.0, 3.0; 4.0, 15.0, 6.0]);
+    }
+}
+
+#[cfg(test)]
+mod cholesky_tests {
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::linalg::Cholesky;
+    use crate::math::Mat;
+
+    #[test]
+    fn real() {
+        let mut a = mat![4f64, 12.0, -16.0; 12.0, 37.0, -43.0; -16.0, -43.0, 98.0];
+
+        Cholesky::cholesky(&mut a).unwrap();
+        assert_eq!(a[0][0], 2.0);
+        assert_eq!(a[1][..2], [6.0, 1.0]);
+        assert_eq!(a[2], [-8.0, 5.0, 3.0]);
+
+        // A * [1, 1, 1] = [0, 6, 39]
+        let mut b = vec![0f64, 6.0, 39.0];
+        Cholesky::cholesky_solve(&a, &mut b);
+        for v in b {
+            assert!((v - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn not_positive_definite() {
+        let mut a = mat![1f32, 2.0; 2.0, 1.0];
+
+        assert_eq!(Cholesky::cholesky(&mut a), Err(BlasError::SingularMatrix));
+    }
+}
+
+#[cfg(test)]
+mod ridge_solve_tests {
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::linalg::RidgeSolve;
+    use crate::math::Mat;
+    use crate::matrix::tests::Cm;
+
+    #[test]
+    fn real() {
+        // y = 1 + 2t exactly
+        let x = mat![1f64, 0.0; 1.0, 1.0; 1.0, 2.0; 1.0, 3.0];
+        let y = vec![1f64, 3.0, 5.0, 7.0];
+
+        let mut exact = vec![0f64; 2];
+        RidgeSolve::ridge_solve(&x, &y, 1e-10, &mut exact).unwrap();
+        assert!((exact[0] - 1.0).abs() < 1e-8);
+        assert!((exact[1] - 2.0).abs() < 1e-8);
+
+        let mut damped = vec![0f64; 2];
+        RidgeSolve::ridge_solve(&x, &y, 1e6, &mut damped).unwrap();
+        assert!(damped[0].abs() < 1e-4);
+        assert!(damped[1].abs() < 1e-4);
+    }
+
+    #[test]
+    fn column_major() {
+        let x = Cm(4, 2, vec![1f64, 1.0, 1.0, 1.0, 0.0, 1.0, 2.0, 3.0]);
+        let y = vec![1f64, 3.0, 5.0, 7.0];
+
+        let mut beta = vec![0f64; 2];
+        RidgeSolve::ridge_solve(&x, &y, 1e-10, &mut beta).unwrap();
+        assert!((beta[0] - 1.0).abs() < 1e-8);
+        assert!((beta[1] - 2.0).abs() < 1e-8);
+    }
+
+    #[test]
+    fn singular_leaves_beta() {
+        let x = mat![1f64, 2.0; 2.0, 4.0; 3.0, 6.0];
+        let y = vec![1f64, 2.0, 3.0];
+
+        let mut beta = vec![-1f64, 5.0];
+        let result = RidgeSolve::ridge_solve(&x, &y, 0.0, &mut beta);
+        assert_eq!(result, Err(BlasError::SingularMatrix));
+        assert_eq!(beta, vec![-1.0, 5.0]);
+    }
+
+    #[test]
+    fn dimension_mismatch() {
+        let x = mat![1f32, 0.0; 0.0, 1.0];
+        let y = vec![1f32; 3];
+        let mut beta = vec![0f32; 2];
+
+        assert_eq!(
+            RidgeSolve::ridge_solve(&x, &y, 1.0, &mut beta),
+            Err(BlasError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+}
+
+#[cfg(test)]
+mod cond_2_estimate_tests {
+    use crate::mat;
+    use crate::math::linalg::Cond2Estimate;
+    use crate::math::Mat;
+    use crate::matrix::tests::Cm;
+
+    #[test]
+    fn ill_conditioned() {
+        // Symmetric positive definite with trace 2 + d and determinant d, so
+        // the condition number is about 4 / d = 16384. Squaring it would
+        // exceed 1 / f32::EPSILON.
+        let d = 2f32.powi(-12);
+        let a = mat![1f32, 1.0; 1.0, 1.0 + d];
+        let (tr, det) = (2.0 + d as f64, d as f64);
+        let large = (tr + (tr * tr - 4.0 * det).sqrt()) / 2.0;
+        let expected = large * large / det;
+
+        let cond = Cond2Estimate::cond_2_estimate(&a, 20) as f64;
+        assert!((cond / expected - 1.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn shear() {
+        // The singular values are (sqrt(5) +- 1) / 2.
+        let a = mat![1f64, 1.0; 0.0, 1.0];
+        let expected = (5f64.sqrt() + 1.0) / (5f64.sqrt() - 1.0);
+
+        let cond = Cond2Estimate::cond_2_estimate(&a, 50);
+        assert!((cond - expected).abs() < 1e-8);
+        assert!(Cond2Estimate::cond_2_estimate(&a, 1) <= expected + 1e-12);
+    }
+
+    #[test]
+    fn column_major() {
+        // The shear above with a row of zeros appended.
+        let a = Cm(3, 2, vec![1f64, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        let expected = (5f64.sqrt() + 1.0) / (5f64.sqrt() - 1.0);
+
+        let cond = Cond2Estimate::cond_2_estimate(&a, 50);
+        assert!((cond - expected).abs() < 1e-8);
+    }
+
+    #[test]
+    fn singular() {
+        // Rounding may leave a tiny positive pivot rather than a zero one.
+        let a = mat![1f32, 2.0; 2.0, 4.0; 3.0, 6.0];
+        assert!(Cond2Estimate::cond_2_estimate(&a, 10) > 1e3);
+
+        let wide = mat![1f32, 0.0, 0.0; 0.0, 1.0, 0.0];
+        assert_eq!(Cond2Estimate::cond_2_estimate(&wide, 10), f32::INFINITY);
+    }
+}
+
+#[cfg(all(test, feature = "lapack", feature = "rand"))]
+mod randomized_svd_tests {
+    use crate::attribute::Transpose;
+    use crate::mat;
+    use crate::math::linalg::RandomizedSvd;
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+
+    #[test]
+    fn low_rank() {
+        // 5 * u1 * v1^T + 2 * u2 * v2^T with orthonormal u1, u2 and v1, v2.
+        let (r6, r2) = (6f64.sqrt(), 2f64.sqrt());
+        let u1 = [1.0 / r6; 6];
+        let u2 = [
+            1.0 / r6,
+            -1.0 / r6,
+            1.0 / r6,
+            -1.0 / r6,
+            1.0 / r6,
+            -1.0 / r6,
+        ];
+        let v1 = [0.5, 0.5, 0.5, 0.5, 0.0];
+        let v2 = [1.0 / r2, -1.0 / r2, 0.0, 0.0, 0.0];
+        let entries = (0..30).map(|k| {
+            let (i, j) = (k / 5, k % 5);
+            5.0 * u1[i] * v1[j] + 2.0 * u2[i] * v2[j]
+        });
+        let a = Mat::new_from_data(6, 5, entries.collect());
+
+        let (u, s, vt) = RandomizedSvd::randomized_svd(&a, 2, 2);
+        assert_eq!((u.rows(), u.cols(), vt.rows(), vt.cols()), (6, 2, 2, 5));
+        assert!((s[0] - 5.0).abs() < 1e-10);
+        assert!((s[1] - 2.0).abs() < 1e-10);
+
+        let us = Mat::new_from_data(6, 2, (0..12).map(|k| u[k / 2][k % 2] * s[k % 2]).collect());
+        let mut b = Mat::fill(0f64, 6, 5);
+        let t = Transpose::NoTrans;
+        Gemm::gemm(&1.0, t, &us, t, &vt, &0.0, &mut b);
+        for i in 0..6 {
+            for j in 0..5 {
+                assert!((a[i][j] - b[i][j]).abs() < 1e-10);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rank_too_large() {
+        let a = mat![1f32, 2.0; 3.0, 4.0];
+
+        let _ = RandomizedSvd::randomized_svd(&a, 3, 0);
+    }
+}
+
+#[cfg(all(test, feature = "lapack"))]
+mod symmetric_eig_tests {
+    use crate::attribute::Symmetry;
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::linalg::SymmetricEig;
+    use crate::math::Mat;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        // Eigenvalues 1 and 3, with eigenvectors (1, -1) and (1, 1).
+        let a = mat![2f64, 1.0; 1.0, 2.0];
+
+        let (w, v) = SymmetricEig::symmetric_eig(&a, Symmetry::Upper).unwrap();
+        assert!((w[0] - 1.0).abs() < 1e-12);
+        assert!((w[1] - 3.0).abs() < 1e-12);
+        assert!((v[0][0] + v[1][0]).abs() < 1e-12);
+        assert!((v[0][1] - v[1][1]).abs() < 1e-12);
+        for k in 0..2 {
+            for i in 0..2 {
+                let av = a[i][0] * v[0][k] + a[i][1] * v[1][k];
+                assert!((av - w[k] * v[i][k]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn lower_triangle_only() {
+        // The strictly upper entry is garbage and must be ignored.
+        let a = mat![2f32, 99.0; 1.0, 2.0];
+
+        let (w, _) = SymmetricEig::symmetric_eig(&a, Symmetry::Lower).unwrap();
+        assert!((w[0] - 1.0).abs() < 1e-5);
+        assert!((w[1] - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn hermitian() {
+        // [[2, i], [-i, 2]] has eigenvalues 1 and 3.
+        let c = |re, im| Complex::new(re, im);
+        let a = Mat::new_from_data(
+            2,
+            2,
+            vec![c(2f64, 0.0), c(0.0, 1.0), c(0.0, -1.0), c(2.0, 0.0)],
+        );
+
+        let (w, v) = SymmetricEig::symmetric_eig(&a, Symmetry::Upper).unwrap();
+        assert!((w[0] - 1.0).abs() < 1e-12);
+        assert!((w[1] - 3.0).abs() < 1e-12);
+        for k in 0..2 {
+            for i in 0..2 {
+                let av = a[i][0] * v[0][k] + a[i][1] * v[1][k];
+                assert!((av - v[i][k] * w[k]).norm() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn not_square() {
+        let a = mat![1f64, 2.0, 3.0];
+
+        let err = SymmetricEig::symmetric_eig(&a, Symmetry::Upper).unwrap_err();
+        assert_eq!(
+            err,
+            BlasError::DimensionMismatch {
+                expected: 1,
+                found: 3
+            }
+        );
+    }
+}
+
+#[cfg(all(test, feature = "lapack"))]
+mod qr_tests {
+    use crate::attribute::Transpose;
+    use crate::mat;
+    use crate::math::linalg::Qr;
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+    use num_complex::Complex;
+
+    #[test]
+    fn tall() {
+        let a = mat![1f64, 2.0; 3.0, 4.0; 5.0, 7.0];
+
+        let (q, r) = Qr::qr(&a).unwrap();
+        assert_eq!((q.rows(), q.cols(), r.rows(), r.cols()), (3, 2, 2, 2));
+        assert_eq!(r[1][0], 0.0);
+
+        let mut qtq = Mat::fill(0f64, 2, 2);
+        Gemm::gemm(
+            &1.0,
+            Transpose::Trans,
+            &q,
+            Transpose::NoTrans,
+            &q,
+            &0.0,
+            &mut qtq,
+        );
+        let mut qr = Mat::fill(0f64, 3, 2);
+        Gemm::gemm(
+            &1.0,
+            Transpose::NoTrans,
+            &q,
+            Transpose::NoTrans,
+            &r,
+            &0.0,
+            &mut qr,
+        );
+        for i in 0..2 {
+            for j in 0..2 {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                assert!((qtq[i][j] - identity).abs() < 1e-12);
+            }
+        }
+        for i in 0..3 {
+            for j in 0..2 {
+                assert!((qr[i][j] - a[i][j]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn wide() {
+        let a = mat![2f32, 0.0, 1.0; 0.0, 3.0, -1.0];
+
+        let (q, r) = Qr::qr(&a).unwrap();
+        assert_eq!((q.rows(), q.cols(), r.rows(), r.cols()), (2, 2, 2, 3));
+
+        let mut qr = Mat::fill(0f32, 2, 3);
+        Gemm::gemm(
+            &1.0,
+            Transpose::NoTrans,
+            &q,
+            Transpose::NoTrans,
+            &r,
+            &0.0,
+            &mut qr,
+        );
+        for i in 0..2 {
+            for j in 0..3 {
+                assert!((qr[i][j] - a[i][j]).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn complex() {
+        let c = |re, im| Complex::new(re, im);
+        let a = Mat::new_from_data(
+            3,
+            2,
+            vec![
+                c(1f64, 1.0),
+                c(0.0, 2.0),
+                c(2.0, 0.0),
+                c(1.0, -1.0),
+                c(0.0, -1.0),
+                c(3.0, 0.0),
+            ],
+        );
+
+        let (q, r) = Qr::qr(&a).unwrap();
+        let (one, zero) = (c(1.0, 0.0), c(0.0, 0.0));
+        let mut qhq = Mat::fill(zero, 2, 2);
+        Gemm::gemm(
+            &one,
+            Transpose::ConjTrans,
+            &q,
+            Transpose::NoTrans,
+            &q,
+            &zero,
+            &mut qhq,
+        );
+        let mut qr = Mat::fill(zero, 3, 2);
+        Gemm::gemm(
+            &one,
+            Transpose::NoTrans,
+            &q,
+            Transpose::NoTrans,
+            &r,
+            &zero,
+            &mut qr,
+        );
+        for i in 0..2 {
+            for j in 0..2 {
+                let identity = if i == j { one } else { zero };
+                assert!((qhq[i][j] - identity).norm() < 1e-12);
+            }
+        }
+        for i in 0..3 {
+            for j in 0..2 {
+                assert!((qr[i][j] - a[i][j]).norm() < 1e-12);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod qr_mgs_tests {
+    use crate::attribute::Transpose;
+    use crate::mat;
+    use crate::math::linalg::QrMgs;
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+
+    #[test]
+    fn tall() {
+        let a = mat![2f64, -1.0; 1.0, 3.0; 0.0, 1.0];
+
+        let (q, r) = QrMgs::qr_mgs(&a);
+        assert_eq!((q.rows(), q.cols(), r.rows(), r.cols()), (3, 2, 2, 2));
+        assert_eq!(r[1][0], 0.0);
+        assert!(r[0][0] > 0.0 && r[1][1] > 0.0);
+
+        let mut qtq = Mat::fill(0f64, 2, 2);
+        Gemm::gemm(
+            &1.0,
+            Transpose::Trans,
+            &q,
+            Transpose::NoTrans,
+            &q,
+            &0.0,
+            &mut qtq,
+        );
+        let mut qr = Mat::fill(0f64, 3, 2);
+        Gemm::gemm(
+            &1.0,
+            Transpose::NoTrans,
+            &q,
+            Transpose::NoTrans,
+            &r,
+            &0.0,
+            &mut qr,
+        );
+        for i in 0..2 {
+            for j in 0..2 {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((qtq[i][j] - expected).abs() < 1e-12);
+            }
+        }
+        for i in 0..3 {
+            for j in 0..2 {
+                assert!((qr[i][j] - a[i][j]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn dependent_column() {
+        let a = mat![1f32, 2.0; 1.0, 2.0];
+
+        let (q, r) = QrMgs::qr_mgs(&a);
+        assert!(r[1][1].abs() < 1e-6);
+        assert!((r[0][1] - 8f32.sqrt()).abs() < 1e-6);
+        assert!((q[0][0] - 0.5f32.sqrt()).abs() < 1e-6);
+    }
+}
+
+#[cfg(all(test, feature = "lapack"))]
+mod schur_complement_tests {
+    use crate::attribute::Order;
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::linalg::SchurComplement;
+    use crate::math::Mat;
+    use crate::Matrix;
+
+    #[test]
+    fn matches_block_of_inverse() {
+        let a = mat![4f64, 1.0; 2.0, 3.0];
+        let b = mat![1f64, 0.0; 2.0, 1.0];
+        let c = mat![0f64, 1.0; 1.0, 1.0];
+        let d = mat![5f64, 2.0; 1.0, 4.0];
+        let s = SchurComplement::schur_complement(&a, &b, &c, &d).unwrap();
+
+        // The lower right block of the inverse of [A, B; C, D] is S^-1.
+        let mut full = mat![
+            4f64, 1.0, 1.0, 0.0;
+            2.0, 3.0, 2.0, 1.0;
+            0.0, 1.0, 5.0, 2.0;
+            1.0, 1.0, 1.0, 4.0
+        ];
+        let mut inv = Mat::fill(0f64, 4, 4);
+        for i in 0..4 {
+            unsafe {
+                *inv.as_mut_ptr().add(i * 4 + i) = 1.0;
+            }
+        }
+        let mut ipiv = vec![0; 4];
+        let info = unsafe {
+            lapacke!(f64, gesv)(
+                Order::RowMajor,
+                4,
+                4,
+                full.as_mut_ptr(),
+                4,
+                ipiv.as_mut_ptr(),
+                inv.as_mut_ptr(),
+                4,
+            )
+        };
+        assert_eq!(info, 0);
+
+        let det = inv[2][2] * inv[3][3] - inv[2][3] * inv[3][2];
+        let expected = [
+            [inv[3][3] / det, -inv[2][3] / det],
+            [-inv[3][2] / det, inv[2][2] / det],
+        ];
+        for i in 0..2 {
+            for j in 0..2 {
+                assert!((s[i][j] - expected[i][j]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn singular_block() {
+        let a = mat![1f32, 2.0; 2.0, 4.0];
+        let b = mat![1f32; 1.0];
+        let c = mat![1f32, 1.0];
+        let d = mat![1f32];
+
+        assert_eq!(
+            SchurComplement::schur_complement(&a, &b, &c, &d),
+            Err(BlasError::SingularMatrix)
+        );
+    }
+
+    #[test]
+    fn mismatched_blocks() {
+        let a = mat![1f64, 0.0; 0.0, 1.0];
+        let b = mat![1f64; 1.0];
+        let c = mat![1f64, 1.0];
+        let d = mat![1f64, 0.0];
+
+        assert_eq!(
+            SchurComplement::schur_complement(&a, &b, &c, &d),
+            Err(BlasError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+}
+
+#[cfg(all(test, feature = "deterministic-gemm"))]
+mod gemm_deterministic_tests {
+    use crate::attribute::Transpose;
+    use crate::mat;
+    use crate::math::linalg::GemmDeterministic;
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+    use num_complex::Complex;
+
+    #[test]
+    fn reproducible() {
+        let (m, k, n) = (7, 33, 5);
+        let a = Mat::new_from_data(m, k, (0..m * k).map(|x| (x as f64 * 0.37).sin()).collect());
+        let b = Mat::new_from_data(
+            k,
+            n,
+            (0..k * n).map(|x| (x as f64 * 1.3).cos() / 3.0).collect(),
+        );
+        let t = Transpose::NoTrans;
+
+        let run = || {
+            let mut c = Mat::fill(1f64, m, n);
+            GemmDeterministic::gemm_deterministic(&0.5, t, &a, t, &b, &2.0, &mut c);
+            c
+        };
+        let (first, second) = (run(), run());
+        for i in 0..m {
+            for j in 0..n {
+                assert_eq!(first[i][j].to_bits(), second[i][j].to_bits());
+            }
+        }
+
+        let mut blas = Mat::fill(1f64, m, n);
+        Gemm::gemm(&0.5, t, &a, t, &b, &2.0, &mut blas);
+        for i in 0..m {
+            for j in 0..n {
+                assert!((first[i][j] - blas[i][j]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn transposed() {
+        let a = mat![1f32, 2.0; 3.0, 4.0; 5.0, 6.0];
+        let b = mat![-1f32, 3.0; 1.0, 1.0; 1.0, 1.0];
+        let t = Transpose::Trans;
+
+        let mut c = Mat::fill(f32::NAN, 2, 2);
+        GemmDeterministic::gemm_deterministic(&1.0, t, &a, Transpose::NoTrans, &b, &0.0, &mut c);
+        assert_eq!(c, mat![7.0, 11.0; 8.0, 16.0]);
+    }
+
+    #[test]
+    fn conjugate_transpose() {
+        let i = Complex::new(0f64, 1.0);
+        let a = Mat::new_from_data(1, 2, vec![i, Complex::new(2.0, 0.0)]);
+        let b = Mat::new_from_data(1, 1, vec![Complex::new(1.0, 0.0)]);
+
+        let mut c = Mat::fill(Complex::new(0.0, 0.0), 2, 1);
+        let one = Complex::new(1.0, 0.0);
+        let zero = Complex::new(0.0, 0.0);
+        GemmDeterministic::gemm_deterministic(
+            &one,
+            Transpose::ConjTrans,
+            &a,
+            Transpose::NoTrans,
+            &b,
+            &zero,
+            &mut c,
+        );
+        assert_eq!(c[0][0], -i);
+        assert_eq!(c[1][0], Complex::new(2.0, 0.0));
+    }
+}
+
+#[cfg(all(test, feature = "lapack"))]
+mod solve_refined_tests {
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::linalg::SolveRefined;
+    use crate::math::Mat;
+    use crate::matrix::{get, set};
+
+    fn hilbert(n: usize) -> Mat<f32> {
+        let mut h = Mat::fill(0f32, n, n);
+        for i in 0..n {
+            for j in 0..n {
+                set(&mut h, i, j, 1.0 / (i + j + 1) as f32);
+            }
+        }
+        h
+    }
+
+    fn residual(a: &Mat<f32>, b: &[f32], x: &[f32]) -> f64 {
+        (0..b.len())
+            .map(|i| {
+                let ax: f64 = (0..x.len())
+                    .map(|j| get(a, i, j) as f64 * x[j] as f64)
+                    .sum();
+                (b[i] as f64 - ax).powi(2)
+            })
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    #[test]
+    fn refinement_shrinks_residual() {
+        let a = hilbert(6);
+        let b = vec![1f32, -1.0, 2.0, 0.5, -3.0, 1.5];
+
+        let mut once = vec![0f32; 6];
+        SolveRefined::solve_refined(&a, &b, &mut once, 0).unwrap();
+        let mut refined = vec![0f32; 6];
+        SolveRefined::solve_refined(&a, &b, &mut refined, 3).unwrap();
+
+        assert!(residual(&a, &b, &refined) < residual(&a, &b, &once));
+    }
+
+    #[test]
+    fn well_conditioned() {
+        let a = mat![4f64, 1.0; 2.0, 3.0];
+        let b = vec![6f64, 8.0];
+        let mut x = vec![0f64; 2];
+
+        SolveRefined::solve_refined(&a, &b, &mut x, 2).unwrap();
+        assert!((x[0] - 1.0).abs() < 1e-12 && (x[1] - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn singular() {
+        let a = mat![1f64, 2.0; 2.0, 4.0];
+        let mut x = vec![7f64, 7.0];
+
+        let result = SolveRefined::solve_refined(&a, &vec![1.0, 1.0], &mut x, 1);
+        assert_eq!(result, Err(BlasError::SingularMatrix));
+        assert_eq!(x, vec![7.0, 7.0]);
+    }
+
+    #[test]
+    fn mismatched_rhs() {
+        let a = mat![1f32, 0.0; 0.0, 1.0];
+        let mut x = vec![0f32; 2];
+
+        let result = SolveRefined::solve_refined(&a, &vec![1.0; 3], &mut x, 1);
+        assert_eq!(
+            result,
+            Err(BlasError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+}
+
+#[cfg(test)]
+mod canonicalize_signs_tests {
+    use crate::mat;
+    use crate::math::linalg::CanonicalizeSigns;
+    use crate::math::Mat;
+    use crate::matrix::get;
+    use num_complex::Complex;
+
+    #[test]
+    fn flipped_columns_agree() {
+        let mut a = mat![0.6f64, -0.8; -0.8, -0.6; 0.0, 0.0];
+        let mut b = mat![-0.6f64, -0.8; 0.8, -0.6; 0.0, 0.0];
+        let mut c = mat![0.6f64, 0.8; -0.8, 0.6; 0.0, 0.0];
+
+        CanonicalizeSigns::canonicalize_signs(&mut a);
+        CanonicalizeSigns::canonicalize_signs(&mut b);
+        CanonicalizeSigns::canonicalize_signs(&mut c);
+
+        let expected = mat![-0.6f64, 0.8; 0.8, 0.6; 0.0, 0.0];
+        for m in &[a, b, c] {
+            for i in 0..3 {
+                assert_eq!(m[i], expected[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_column() {
+        let mut a = mat![0f32, -1.0; 0.0, 2.0];
+
+        CanonicalizeSigns::canonicalize_signs(&mut a);
+        assert_eq!(a[0], [0.0, -1.0]);
+        assert_eq!(a[1], [0.0, 2.0]);
+    }
+
+    #[test]
+    fn complex_phase() {
+        let v = [Complex::new(0.6f64, 0.0), Complex::new(0.0, 0.8)];
+        let i = Complex::new(0.0, 1.0);
+        let mut a: Mat<Complex<f64>> = Mat::new_from_data(2, 1, v.to_vec());
+        let mut b: Mat<Complex<f64>> = Mat::new_from_data(2, 1, v.iter().map(|x| x * i).collect());
+
+        CanonicalizeSigns::canonicalize_signs(&mut a);
+        CanonicalizeSigns::canonicalize_signs(&mut b);
+
+        assert_eq!(get(&a, 1, 0), Complex::new(0.8, 0.0));
+        for r in 0..2 {
+            assert!((get(&a, r, 0) - get(&b, r, 0)).norm() < 1e-12);
+        }
+    }
+}
+
+#[cfg(test)]
+mod gemv_batched_tests {
+    use crate::attribute::Transpose;
+    use crate::mat;
+    use crate::math::linalg::GemvBatched;
+    use crate::math::Mat;
+    use crate::matrix::tests::Cm;
+    use crate::matrix::{col, col_mut};
+    use crate::matrix_vector::ops::Gemv;
+    use num_complex::Complex;
+
+    #[test]
+    fn matches_gemv_loop() {
+        let a = mat![1f64, 2.0, 0.0; -1.0, 0.5, 3.0];
+        let xs = Cm(3, 4, (0..12).map(|v| v as f64 - 5.0).collect());
+        let mut ys = Mat::fill(0f64, 2, 4);
+
+        GemvBatched::gemv_batched(&a, &xs, &mut ys);
+
+        let mut expected = Mat::fill(0f64, 2, 4);
+        for j in 0..4 {
+            Gemv::gemv(
+                Transpose::NoTrans,
+                &1.0,
+                &a,
+                &col(&xs, j),
+                &0.0,
+                &mut col_mut(&mut expected, j),
+            );
+        }
+        for i in 0..2 {
+            assert_eq!(ys[i], expected[i]);
+        }
+    }
+
+    #[test]
+    fn overwrites_ys() {
+        let a = mat![Complex::new(0f32, 1.0)];
+        let xs = mat![Complex::new(2f32, 0.0), Complex::new(0.0, 1.0)];
+        let mut ys = Mat::fill(Complex::new(9f32, 9.0), 1, 2);
+
+        GemvBatched::gemv_batched(&a, &xs, &mut ys);
+        assert_eq!(ys[0], [Complex::new(0.0, 2.0), Complex::new(-1.0, 0.0)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn batch_size_mismatch() {
+        let a = mat![1f32, 0.0; 0.0, 1.0];
+        let xs = Mat::fill(1f32, 2, 3);
+        let mut ys = Mat::fill(0f32, 2, 2);
+
+        GemvBatched::gemv_batched(&a, &xs, &mut ys);
+    }
+}
+
+#[cfg(test)]
+mod levinson_tests {
+    use crate::attribute::Transpose;
+    use crate::error::BlasError;
+    use crate::math::linalg::Levinson;
+    use crate::math::structure::SlidingWindow;
+    use crate::matrix_vector::ops::Gemv;
+
+    #[test]
+    fn matches_dense_product() {
+        let c = vec![4f64, 1.0, -0.5, 0.25, 0.1];
+        let b = vec![1f64, -2.0, 0.5, 3.0, 1.0];
+        let mut x = vec![0f64; 5];
+
+        Levinson::levinson(&c, &b, &mut x).unwrap();
+
+        let t = SlidingWindow::toeplitz(&c, &c);
+        let mut tx = vec![0f64; 5];
+        Gemv::gemv(Transpose::NoTrans, &1.0, &t, &x, &0.0, &mut tx);
+        for (u, v) in tx.iter().zip(&b) {
+            assert!((u - v).abs() < 1e-12);
+        }
+    }
+
+    #[cfg(feature = "lapack")]
+    #[test]
+    fn matches_dense_solve() {
+        use crate::math::linalg::SolveRefined;
+
+        let c = vec![2f32, -1.0, 0.0, 0.0];
+        let b = vec![1f32, 0.0, 0.0, 1.0];
+        let mut x = vec![0f32; 4];
+        Levinson::levinson(&c, &b, &mut x).unwrap();
+
+        let t = SlidingWindow::toeplitz(&c, &c);
+        let mut dense = vec![0f32; 4];
+        SolveRefined::solve_refined(&t, &b, &mut dense, 0).unwrap();
+        for (u, v) in x.iter().zip(&dense) {
+            assert!((u - v).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn zero_leading_element() {
+        let mut x = vec![9f64; 2];
+
+        let result = Levinson::levinson(&vec![0.0, 1.0], &vec![1.0, 1.0], &mut x);
+        assert_eq!(result, Err(BlasError::SingularMatrix));
+        assert_eq!(x, vec![9.0, 9.0]);
+    }
+
+    #[test]
+    fn singular_leading_block() {
+        let mut x = vec![0f32; 3];
+
+        // The leading 2x2 block [1 1; 1 1] is singular.
+        let result = Levinson::levinson(&vec![1.0, 1.0, 0.0], &vec![1.0; 3], &mut x);
+        assert_eq!(result, Err(BlasError::SingularMatrix));
+    }
+}
+
+#[cfg(all(test, feature = "lapack"))]
+mod matrix_sign_tests {
+    use crate::attribute::Transpose;
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::linalg::MatrixSign;
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+
+    #[test]
+    fn triangular() {
+        // Eigenvalues 2 and -3; the sign commutes with A, which fixes the
+        // off-diagonal entry at 0.4.
+        let a = mat![2f64, 1.0; 0.0, -3.0];
+
+        let s = MatrixSign::matrix_sign(&a, 50).unwrap();
+        let expected = mat![1f64, 0.4; 0.0, -1.0];
+        for i in 0..2 {
+            for j in 0..2 {
+                assert!((s[i][j] - expected[i][j]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn squares_to_identity() {
+        let a = mat![
+            1f64, 2.0, 0.0;
+            -1.0, -4.0, 1.0;
+            0.5, 0.0, 3.0
+        ];
+        let s = MatrixSign::matrix_sign(&a, 100).unwrap();
+
+        let t = Transpose::NoTrans;
+        let mut sq = Mat::fill(0f64, 3, 3);
+        Gemm::gemm(&1.0, t, &s, t, &s, &0.0, &mut sq);
+        for i in 0..3 {
+            for j in 0..3 {
+                let id = if i == j { 1.0 } else { 0.0 };
+                assert!((sq[i][j] - id).abs() < 1e-10, "{}", sq);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_eigenvalue() {
+        let a = mat![1f32, 2.0; 0.0, 0.0];
+
+        let result = MatrixSign::matrix_sign(&a, 10);
+        assert_eq!(result, Err(BlasError::SingularMatrix));
+    }
+}
+
+#[cfg(all(test, feature = "lapack"))]
+mod polar_decomposition_tests {
+    use crate::attribute::Transpose;
+    use crate::error::BlasError;
+    use crate::mat;
+    use crate::math::linalg::PolarDecomposition;
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+
+    #[test]
+    fn factors() {
+        let a = mat![
+            4f64, 1.0, -2.0;
+            0.5, 3.0, 1.0;
+            -1.0, 2.0, 5.0
+        ];
+        let (u, p) = PolarDecomposition::polar_decomposition(&a, 100).unwrap();
+
+        let mut utu = Mat::fill(0f64, 3, 3);
+        Gemm::gemm(
+            &1.0,
+            Transpose::Trans,
+            &u,
+            Transpose::NoTrans,
+            &u,
+            &0.0,
+            &mut utu,
+        );
+        let mut up = Mat::fill(0f64, 3, 3);
+        Gemm::gemm(
+            &1.0,
+            Transpose::NoTrans,
+            &u,
+            Transpose::NoTrans,
+            &p,
+            &0.0,
+            &mut up,
+        );
+        for i in 0..3 {
+            let id = |j| if i == j { 1.0 } else { 0.0 };
+            for j in 0..3 {
+                assert!((utu[i][j] - id(j)).abs() < 1e-12, "{}", utu);
+                assert!((up[i][j] - a[i][j]).abs() < 1e-12, "{}", up);
+                assert_eq!(p[i][j], p[j][i]);
+            }
+            assert!(p[i][i] > 0.0);
+        }
+    }
+
+    #[test]
+    fn scaled_rotation() {
+        // 100 times a rotation by 90 degrees.
+        let a = mat![0f32, -100.0; 100.0, 0.0];
+
+        let (u, p) = PolarDecomposition::polar_decomposition(&a, 20).unwrap();
+        let (eu, ep) = (mat![0f32, -1.0; 1.0, 0.0], mat![100f32, 0.0; 0.0, 100.0]);
+        for i in 0..2 {
+            for j in 0..2 {
+                assert!((u[i][j] - eu[i][j]).abs() < 1e-6);
+                assert!((p[i][j] - ep[i][j]).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn singular() {
+        let a = mat![1f64, 2.0; 2.0, 4.0];
+
+        let result = PolarDecomposition::polar_decomposition(&a, 10);
+        assert_eq!(result.map(|_| ()), Err(BlasError::SingularMatrix));
+    }
+}
@@ -89,6 +89,18 @@ where
     }
 }
 
+impl<'a, T> Mul<&'a Mat<T>> for &'a Mat<T>
+where
+    T: Default + Gemm,
+{
+    type Output = Mat<T>;
+
+    /// Same as the product of the two as `&dyn Matrix<T>`, without the casts.
+    fn mul(self, b: &Mat<T>) -> Mat<T> {
+        (self as &dyn Matrix<T>) * (b as &dyn Matrix<T>)
+    }
+}
+
 impl<'a, T> Mul<&'a dyn Matrix<T>> for Trans<&'a dyn Matrix<T>>
 where
     T: Default + Gemm,
@@ -181,8 +193,10 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::attribute::Transpose;
     use crate::math::Marker::T;
     use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
     use crate::Matrix;
 
     #[test]
@@ -224,6 +238,32 @@ mod tests {
         assert_eq!(c, mat![1.0, 5.0; 1.0, 13.0]);
     }
 
+    #[test]
+    fn mul_owned() {
+        let a = mat![1f64, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let b = mat![7f64, 8.0; 9.0, 10.0; 11.0, 12.0];
+
+        let mut expected = Mat::fill(0f64, 2, 2);
+        let t = Transpose::NoTrans;
+        Gemm::gemm(&1.0, t, &a, t, &b, &0.0, &mut expected);
+
+        assert_eq!(&a * &b, expected);
+        assert_eq!((&a * &b).cols(), 2);
+        assert_eq!(
+            &b * &a,
+            mat![39.0, 54.0, 69.0; 49.0, 68.0, 87.0; 59.0, 82.0, 105.0]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn mul_mismatched() {
+        let a = mat![1f32, 2.0, 3.0];
+        let b = mat![1f32, 2.0];
+
+        let _ = &a * &b;
+    }
+
     #[test]
     fn left_mul_trans() {
         let a = mat![1.0, 3.0; 2.0, 4.0];
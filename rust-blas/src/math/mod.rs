@@ -9,9 +9,20 @@ use std::ops::{BitXor, Deref};
 pub use self::mat::Mat;
 
 pub mod bandmat;
+pub mod iterative;
+pub mod kernel;
+pub mod linalg;
 pub mod mat;
 pub mod matrix;
 pub mod matrix_vector;
+pub mod nn;
+#[cfg(feature = "npy")]
+pub mod npy;
+pub mod optim;
+pub mod signal;
+pub mod sparse;
+pub mod stats;
+pub mod structure;
 pub mod vector;
 
 pub enum Trans<A> {
@@ -0,0 +1,385 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Building blocks for neural network layers.
+
+use crate::attribute::Transpose;
+use crate::default::Default;
+use crate::math::structure::ApplyElementwise;
+use crate::math::Mat;
+use crate::matrix::ops::Gemm;
+use crate::matrix::{row, row_mut, Matrix};
+use crate::vector::ops::{Axpy, Copy, Scal};
+use crate::vector::Vector;
+use num_complex::{Complex32, Complex64};
+
+/// Computes the attention logits `out = scale * Q * K^T`.
+///
+/// `q` holds one query per row and `k` one key per row, so `out` has a row
+/// per query and a column per key. The usual choice of `scale` is one over
+/// the square root of the key width. This is a single `gemm` with the keys
+/// transposed in place.
+pub trait AttentionScores: Sized {
+    fn attention_scores(
+        q: &dyn Matrix<Self>,
+        k: &dyn Matrix<Self>,
+        scale: Self,
+        out: &mut dyn Matrix<Self>,
+    );
+}
+
+macro_rules! attention_scores_impl(($($t: ident), +) => (
+    $(
+        impl AttentionScores for $t {
+            fn attention_scores(q: &dyn Matrix<$t>, k: &dyn Matrix<$t>, scale: $t, out: &mut dyn Matrix<$t>) {
+                if q.cols() != k.cols() || out.rows() != q.rows() || out.cols() != k.rows() {
+                    panic!("Dimension mismatch");
+                }
+
+                Gemm::gemm(&scale, Transpose::NoTrans, q, Transpose::Trans, k, &0.0, out);
+            }
+        }
+    )+
+));
+
+attention_scores_impl!(f32, f64);
+
+/// Replaces every row of `a` in place by its softmax.
+///
+/// The row maximum is subtracted before exponentiating, so large logits do
+/// not overflow.
+pub trait SoftmaxRows: Sized {
+    fn softmax_rows(a: &mut dyn Matrix<Self>);
+}
+
+macro_rules! softmax_rows_impl(($($t: ident), +) => (
+    $(
+        impl SoftmaxRows for $t {
+            fn softmax_rows(a: &mut dyn Matrix<$t>) {
+                for i in 0..a.rows() as usize {
+                    let mut r = row_mut(a, i);
+                    let max = (0..r.len()).map(|j| r.get(j)).fold(<$t>::NEG_INFINITY, <$t>::max);
+                    let mut sum = 0.0;
+                    for j in 0..r.len() {
+                        let e = (r.get(j) - max).exp();
+                        r.set(j, e);
+                        sum += e;
+                    }
+                    Scal::scal(&sum.recip(), &mut r);
+                }
+            }
+        }
+    )+
+));
+
+softmax_rows_impl!(f32, f64);
+
+/// Scaled dot-product attention, `out = softmax(scale * Q * K^T) * V`.
+///
+/// The scores come from `attention_scores`, are turned into weights by
+/// `softmax_rows`, and a second `gemm` takes the weighted sums of the rows
+/// of `v`. `k` and `v` need a row per key; `out` gets a row per query and a
+/// column per column of `v`.
+pub trait Attention: Sized {
+    fn attention(
+        q: &dyn Matrix<Self>,
+        k: &dyn Matrix<Self>,
+        v: &dyn Matrix<Self>,
+        scale: Self,
+        out: &mut dyn Matrix<Self>,
+    );
+}
+
+macro_rules! attention_impl(($($t: ident), +) => (
+    $(
+        impl Attention for $t {
+            fn attention(q: &dyn Matrix<$t>, k: &dyn Matrix<$t>, v: &dyn Matrix<$t>, scale: $t, out: &mut dyn Matrix<$t>) {
+                if v.rows() != k.rows() || out.rows() != q.rows() || out.cols() != v.cols() {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut weights = Mat::fill(0.0, q.rows() as usize, k.rows() as usize);
+                AttentionScores::attention_scores(q, k, scale, &mut weights);
+                SoftmaxRows::softmax_rows(&mut weights);
+                Gemm::gemm(&1.0, Transpose::NoTrans, &weights, Transpose::NoTrans, v, &0.0, out);
+            }
+        }
+    )+
+));
+
+attention_impl!(f32, f64);
+
+/// Encodes class labels as the rows of a matrix.
+///
+/// Row `i` of the `labels.len() x num_classes` result is all zeros except
+/// for a one in column `labels[i]`. Panics if a label is not below
+/// `num_classes`.
+pub trait OneHot: Sized {
+    fn one_hot(labels: &[usize], num_classes: usize) -> Mat<Self>;
+}
+
+macro_rules! one_hot_impl(($($t: ident), +) => (
+    $(
+        impl OneHot for $t {
+            fn one_hot(labels: &[usize], num_classes: usize) -> Mat<$t> {
+                let mut data = vec![Default::zero(); labels.len() * num_classes];
+                for (i, &label) in labels.iter().enumerate() {
+                    if label >= num_classes {
+                        panic!("Label {} out of range for {} classes", label, num_classes);
+                    }
+                    data[i * num_classes + label] = Default::one();
+                }
+
+                Mat::new_from_data(labels.len(), num_classes, data)
+            }
+        }
+    )+
+));
+
+one_hot_impl!(f32, f64, Complex32, Complex64);
+
+/// Elementwise nonlinearity applied after each layer of `mlp_forward`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Activation {
+    Relu,
+    Tanh,
+    Sigmoid,
+}
+
+/// Forward pass of a fully connected network, one sample per row of
+/// `input`.
+///
+/// Layer `l` maps its input `X` to `f(X * W_l + b_l)`, where `W_l` is
+/// `weights[l]` with a row per input feature and a column per output, `b_l`
+/// is `biases[l]` added to every row, and `f` is `activation`, applied after
+/// every layer including the last. Each layer is one `gemm`, an `axpy` per
+/// row for the bias and an elementwise pass. Panics if the layer shapes do
+/// not chain.
+pub trait MlpForward: Sized {
+    fn mlp_forward(
+        input: &dyn Matrix<Self>,
+        weights: &[&dyn Matrix<Self>],
+        biases: &[&dyn Vector<Self>],
+        activation: Activation,
+    ) -> Mat<Self>;
+}
+
+macro_rules! mlp_forward_impl(($($t: ident), +) => (
+    $(
+        impl MlpForward for $t {
+            fn mlp_forward(input: &dyn Matrix<$t>, weights: &[&dyn Matrix<$t>], biases: &[&dyn Vector<$t>], activation: Activation) -> Mat<$t> {
+                if weights.len() != biases.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let f: fn($t) -> $t = match activation {
+                    Activation::Relu => |v| v.max(0.0),
+                    Activation::Tanh => |v| v.tanh(),
+                    Activation::Sigmoid => |v| 1.0 / (1.0 + (-v).exp()),
+                };
+
+                let n = input.rows() as usize;
+                let mut x = Mat::fill(0.0, n, input.cols() as usize);
+                for i in 0..n {
+                    Copy::copy(&row(input, i), &mut row_mut(&mut x, i));
+                }
+
+                for (w, b) in weights.iter().zip(biases) {
+                    if w.rows() as usize != x.cols() || b.len() != w.cols() {
+                        panic!("Dimension mismatch");
+                    }
+
+                    let mut y = Mat::fill(0.0, n, w.cols() as usize);
+                    Gemm::gemm(&1.0, Transpose::NoTrans, &x, Transpose::NoTrans, *w, &0.0, &mut y);
+                    for i in 0..n {
+                        Axpy::axpy(&1.0, *b, &mut row_mut(&mut y, i));
+                    }
+                    ApplyElementwise::apply_elementwise(&mut y, f);
+                    x = y;
+                }
+
+                x
+            }
+        }
+    )+
+));
+
+mlp_forward_impl!(f32, f64);
+
+#[cfg(test)]
+mod attention_scores_tests {
+    use crate::attribute::Transpose;
+    use crate::math::nn::AttentionScores;
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+
+    #[test]
+    fn real() {
+        let q = mat![1f32, 0.0; 0.0, 2.0; 1.0, 1.0];
+        let k = mat![1f32, 2.0; -1.0, 1.0];
+        let kt = mat![1f32, -1.0; 2.0, 1.0];
+        let scale = 0.5;
+
+        let mut expected = Mat::fill(0f32, 3, 2);
+        Gemm::gemm(
+            &scale,
+            Transpose::NoTrans,
+            &q,
+            Transpose::NoTrans,
+            &kt,
+            &0.0,
+            &mut expected,
+        );
+
+        let mut out = Mat::fill(0f32, 3, 2);
+        AttentionScores::attention_scores(&q, &k, scale, &mut out);
+        assert_eq!(out, expected);
+        assert_eq!(out, mat![0.5, -0.5; 2.0, 1.0; 1.5, 0.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_widths() {
+        let q = mat![1f64, 0.0];
+        let k = mat![1f64, 2.0, 3.0];
+        let mut out = Mat::fill(0f64, 1, 1);
+
+        AttentionScores::attention_scores(&q, &k, 1.0, &mut out);
+    }
+}
+
+#[cfg(test)]
+mod attention_tests {
+    use crate::math::nn::{Attention, SoftmaxRows};
+    use crate::math::Mat;
+
+    #[test]
+    fn softmax_rows() {
+        let mut a = mat![0f64, 0.0; 1000.0, 1000.0 + 2f64.ln()];
+
+        SoftmaxRows::softmax_rows(&mut a);
+        assert_eq!(a[0], [0.5, 0.5]);
+        assert!((a[1][0] - 1.0 / 3.0).abs() < 1e-12);
+        assert!((a[1][1] - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reference() {
+        let q = mat![1f64, 0.0; 0.0, 1.0; 1.0, 1.0];
+        let k = mat![1f64, 2.0; -1.0, 0.5];
+        let v = mat![1f64, 0.0, 2.0; -1.0, 3.0, 0.0];
+        let scale = 0.5;
+
+        let mut out = Mat::fill(0f64, 3, 3);
+        Attention::attention(&q, &k, &v, scale, &mut out);
+
+        for i in 0..3 {
+            let scores: Vec<f64> = (0..2)
+                .map(|j| scale * (q[i][0] * k[j][0] + q[i][1] * k[j][1]))
+                .collect();
+            let total: f64 = scores.iter().map(|s| s.exp()).sum();
+            for c in 0..3 {
+                let expected: f64 = (0..2).map(|j| scores[j].exp() / total * v[j][c]).sum();
+                assert!((out[i][c] - expected).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_values() {
+        let q = mat![1f32, 0.0];
+        let k = mat![1f32, 2.0; 3.0, 4.0];
+        let v = mat![1f32, 2.0];
+        let mut out = Mat::fill(0f32, 1, 2);
+
+        Attention::attention(&q, &k, &v, 1.0, &mut out);
+    }
+}
+
+#[cfg(test)]
+mod one_hot_tests {
+    use crate::math::nn::OneHot;
+    use crate::math::Mat;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let x: Mat<f32> = OneHot::one_hot(&[0, 2, 1], 3);
+        assert_eq!(x, mat![1.0, 0.0, 0.0; 0.0, 0.0, 1.0; 0.0, 1.0, 0.0]);
+
+        let y: Mat<f64> = OneHot::one_hot(&[1, 1], 2);
+        assert_eq!(y, mat![0.0, 1.0; 0.0, 1.0]);
+    }
+
+    #[test]
+    fn complex() {
+        let x: Mat<Complex<f64>> = OneHot::one_hot(&[1], 2);
+        assert_eq!(x[0][0], Complex::new(0.0, 0.0));
+        assert_eq!(x[0][1], Complex::new(1.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn label_out_of_range() {
+        let _: Mat<f32> = OneHot::one_hot(&[0, 3], 3);
+    }
+}
+
+#[cfg(test)]
+mod mlp_forward_tests {
+    use crate::math::nn::{Activation, MlpForward};
+    use crate::math::Mat;
+    use crate::matrix::Matrix;
+    use crate::vector::Vector;
+
+    #[test]
+    fn two_layers() {
+        let input = mat![1f64, -1.0; 0.5, 2.0];
+        let w1 = mat![1.0, -2.0, 0.5; 0.0, 1.0, -1.0];
+        let b1 = vec![0.1, 0.0, -0.2];
+        let w2 = mat![1.0; -1.0; 2.0];
+        let b2 = vec![0.5];
+
+        let weights: [&dyn Matrix<f64>; 2] = [&w1, &w2];
+        let biases: [&dyn Vector<f64>; 2] = [&b1, &b2];
+        let out = MlpForward::mlp_forward(&input, &weights, &biases, Activation::Tanh);
+        assert_eq!((out.rows(), out.cols()), (2, 1));
+
+        for r in 0..2 {
+            let hidden: Vec<f64> = (0..3)
+                .map(|j| (input[r][0] * w1[0][j] + input[r][1] * w1[1][j] + b1[j]).tanh())
+                .collect();
+            let expected = ((0..3).map(|j| hidden[j] * w2[j][0]).sum::<f64>() + b2[0]).tanh();
+            assert!((out[r][0] - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn relu_and_sigmoid() {
+        let input = mat![1f32, 2.0];
+        let w = mat![1.0, -1.0; 1.0, -1.0];
+        let b = vec![0.0, 0.0];
+        let weights: [&dyn Matrix<f32>; 1] = [&w];
+        let biases: [&dyn Vector<f32>; 1] = [&b];
+
+        let relu = MlpForward::mlp_forward(&input, &weights, &biases, Activation::Relu);
+        assert_eq!(relu, mat![3.0, 0.0]);
+
+        let sigmoid = MlpForward::mlp_forward(&input, &weights, &biases, Activation::Sigmoid);
+        assert!((sigmoid[0][0] - 1.0 / (1.0 + (-3f32).exp())).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn shapes_do_not_chain() {
+        let input = mat![1f64, 2.0];
+        let w = mat![1.0, 2.0, 3.0];
+        let b = vec![0.0, 0.0, 0.0];
+        let weights: [&dyn Matrix<f64>; 1] = [&w];
+        let biases: [&dyn Vector<f64>; 1] = [&b];
+
+        let _ = MlpForward::mlp_forward(&input, &weights, &biases, Activation::Relu);
+    }
+}
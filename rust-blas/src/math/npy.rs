@@ -0,0 +1,376 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Reading and writing matrices in NumPy's `.npy` format.
+//!
+//! Files are written in format version 1.0 with little-endian data, in the
+//! order of the source matrix: row-major matrices are stored C-contiguous
+//! and column-major ones with `fortran_order` set. Versions 1.0 to 3.0 can
+//! be read, as long as the array is two-dimensional, little-endian and of
+//! the element type being loaded.
+
+use crate::attribute::Order;
+use crate::math::Mat;
+use crate::matrix::{get, Matrix};
+use num_complex::{Complex32, Complex64};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Element types that have a fixed `.npy` encoding.
+trait Element: Sized {
+    const DESCR: &'static str;
+    const SIZE: usize;
+
+    fn write_le(&self, out: &mut Vec<u8>);
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! real_element_impl(($($t: ident, $descr: expr), +) => (
+    $(
+        impl Element for $t {
+            const DESCR: &'static str = $descr;
+            const SIZE: usize = std::mem::size_of::<$t>();
+
+            fn write_le(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> $t {
+                let mut b = [0; std::mem::size_of::<$t>()];
+                b.copy_from_slice(&bytes[..Self::SIZE]);
+                $t::from_le_bytes(b)
+            }
+        }
+    )+
+));
+
+macro_rules! complex_element_impl(($($t: ident, $real: ident, $descr: expr), +) => (
+    $(
+        impl Element for $t {
+            const DESCR: &'static str = $descr;
+            const SIZE: usize = 2 * <$real as Element>::SIZE;
+
+            fn write_le(&self, out: &mut Vec<u8>) {
+                self.re.write_le(out);
+                self.im.write_le(out);
+            }
+
+            fn read_le(bytes: &[u8]) -> $t {
+                let half = <$real as Element>::SIZE;
+                $t::new($real::read_le(bytes), $real::read_le(&bytes[half..]))
+            }
+        }
+    )+
+));
+
+real_element_impl!(f32, "<f4", f64, "<f8");
+complex_element_impl!(Complex32, f32, "<c8", Complex64, f64, "<c16");
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Builds the magic string, version and padded header dictionary.
+fn header(descr: &str, fortran_order: bool, rows: usize, cols: usize) -> Vec<u8> {
+    let dict = format!(
+        "{{'descr': '{}', 'fortran_order': {}, 'shape': ({}, {}), }}",
+        descr,
+        if fortran_order { "True" } else { "False" },
+        rows,
+        cols
+    );
+
+    // The data has to start on a 64 byte boundary, and the header ends in a
+    // newline.
+    let unpadded = MAGIC.len() + 4 + dict.len() + 1;
+    let padding = (64 - unpadded % 64) % 64;
+    let len = dict.len() + padding + 1;
+
+    let mut out = Vec::with_capacity(unpadded + padding);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&[1, 0]);
+    out.extend_from_slice(&(len as u16).to_le_bytes());
+    out.extend_from_slice(dict.as_bytes());
+    out.resize(out.len() + padding, b' ');
+    out.push(b'\n');
+    out
+}
+
+/// Returns the text following `'key':` in the header dictionary.
+fn field<'a>(dict: &'a str, key: &str) -> io::Result<&'a str> {
+    let pattern = format!("'{}':", key);
+    dict.find(&pattern)
+        .map(|i| dict[i + pattern.len()..].trim_start())
+        .ok_or_else(|| invalid(format!("Missing '{}' in .npy header", key)))
+}
+
+/// Parses the header at the start of `bytes`, returning the element type,
+/// whether the data is column-major, the shape and where the data starts.
+fn parse_header(bytes: &[u8]) -> io::Result<(String, bool, usize, usize, usize)> {
+    if bytes.len() < MAGIC.len() + 4 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid("Not a .npy file".to_string()));
+    }
+
+    let (len, start) = match bytes[MAGIC.len()] {
+        1 => {
+            let b = &bytes[MAGIC.len() + 2..];
+            (u16::from_le_bytes([b[0], b[1]]) as usize, MAGIC.len() + 4)
+        }
+        2 | 3 if bytes.len() >= MAGIC.len() + 6 => {
+            let b = &bytes[MAGIC.len() + 2..];
+            (
+                u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize,
+                MAGIC.len() + 6,
+            )
+        }
+        v => return Err(invalid(format!("Unsupported .npy version {}", v))),
+    };
+    let dict = bytes
+        .get(start..start + len)
+        .and_then(|d| std::str::from_utf8(d).ok())
+        .ok_or_else(|| invalid("Truncated .npy header".to_string()))?;
+
+    let descr = field(dict, "descr")?;
+    let descr = descr
+        .strip_prefix('\'')
+        .and_then(|d| d.split('\'').next())
+        .ok_or_else(|| invalid("Malformed 'descr' in .npy header".to_string()))?;
+
+    let fortran_order = field(dict, "fortran_order")?.starts_with("True");
+
+    let shape = field(dict, "shape")?;
+    let shape = shape
+        .strip_prefix('(')
+        .and_then(|s| s.split(')').next())
+        .ok_or_else(|| invalid("Malformed 'shape' in .npy header".to_string()))?;
+    let dims = shape
+        .split(',')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid(format!("Malformed 'shape' in .npy header: {}", e)))?;
+    if dims.len() != 2 {
+        return Err(invalid(format!(
+            "Expected a 2-d array, found {} dimensions",
+            dims.len()
+        )));
+    }
+
+    Ok((
+        descr.to_string(),
+        fortran_order,
+        dims[0],
+        dims[1],
+        start + len,
+    ))
+}
+
+fn save<T: Element + std::marker::Copy>(a: &dyn Matrix<T>, path: &Path) -> io::Result<()> {
+    let (rows, cols) = (a.rows() as usize, a.cols() as usize);
+    let fortran_order = match a.order() {
+        Order::RowMajor => false,
+        Order::ColMajor => true,
+    };
+
+    let mut out = header(T::DESCR, fortran_order, rows, cols);
+    out.reserve(rows * cols * T::SIZE);
+    if fortran_order {
+        for j in 0..cols {
+            for i in 0..rows {
+                get(a, i, j).write_le(&mut out);
+            }
+        }
+    } else {
+        for i in 0..rows {
+            for j in 0..cols {
+                get(a, i, j).write_le(&mut out);
+            }
+        }
+    }
+
+    fs::File::create(path)?.write_all(&out)
+}
+
+fn load<T: Element>(path: &Path) -> io::Result<Mat<T>> {
+    let mut bytes = Vec::new();
+    fs::File::open(path)?.read_to_end(&mut bytes)?;
+
+    let (descr, fortran_order, rows, cols, start) = parse_header(&bytes)?;
+    if descr != T::DESCR {
+        return Err(invalid(format!(
+            "Expected dtype '{}', found '{}'",
+            T::DESCR,
+            descr
+        )));
+    }
+
+    // The shape comes straight from the file, so the size can overflow.
+    let size = rows
+        .checked_mul(cols)
+        .and_then(|n| n.checked_mul(T::SIZE))
+        .ok_or_else(|| invalid(format!("Shape ({}, {}) is too large", rows, cols)))?;
+    let data = &bytes[start..];
+    if data.len() != size {
+        return Err(invalid(format!(
+            "Expected {} bytes of data, found {}",
+            size,
+            data.len()
+        )));
+    }
+
+    let at = |k: usize| T::read_le(&data[k * T::SIZE..]);
+    let mut values = Vec::with_capacity(rows * cols);
+    for i in 0..rows {
+        for j in 0..cols {
+            values.push(if fortran_order {
+                at(j * rows + i)
+            } else {
+                at(i * cols + j)
+            });
+        }
+    }
+
+    Ok(Mat::new_from_data(rows, cols, values))
+}
+
+/// Saves matrices to and loads them from `.npy` files.
+pub trait Npy: Sized {
+    /// Writes `a` to `path`, replacing any existing file.
+    fn save_npy(a: &dyn Matrix<Self>, path: &Path) -> io::Result<()>;
+    /// Reads a two-dimensional array of this element type from `path`.
+    ///
+    /// Column-major files are transposed into the row-major result.
+    fn load_npy(path: &Path) -> io::Result<Mat<Self>>;
+}
+
+macro_rules! npy_impl(($($t: ident), +) => (
+    $(
+        impl Npy for $t {
+            fn save_npy(a: &dyn Matrix<$t>, path: &Path) -> io::Result<()> {
+                save(a, path)
+            }
+
+            fn load_npy(path: &Path) -> io::Result<Mat<$t>> {
+                load(path)
+            }
+        }
+    )+
+));
+
+npy_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod npy_tests {
+    use crate::attribute::Order;
+    use crate::math::npy::Npy;
+    use crate::math::Mat;
+    use crate::matrix::Matrix;
+    use num_complex::Complex;
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rust-blas-{}-{}.npy", std::process::id(), name))
+    }
+
+    struct ColMajor(u32, u32, Vec<f64>);
+
+    impl Matrix<f64> for ColMajor {
+        fn order(&self) -> Order {
+            Order::ColMajor
+        }
+
+        fn rows(&self) -> u32 {
+            self.0
+        }
+
+        fn cols(&self) -> u32 {
+            self.1
+        }
+
+        fn as_ptr(&self) -> *const f64 {
+            self.2.as_ptr()
+        }
+
+        fn as_mut_ptr(&mut self) -> *mut f64 {
+            self.2.as_mut_ptr()
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round_trip");
+        let a = mat![1f64, -2.5, 3.0; 4.0, 1e-300, f64::MAX];
+
+        Npy::save_npy(&a, &path).unwrap();
+        let b: Mat<f64> = Npy::load_npy(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn header() {
+        let path = temp_path("header");
+        // [[1, 2, 3], [4, 5, 6]] stored column by column.
+        let a = ColMajor(2, 3, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+
+        Npy::save_npy(&a, &path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let b: Mat<f64> = Npy::load_npy(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + len) % 64, 0);
+        let dict = std::str::from_utf8(&bytes[10..10 + len]).unwrap();
+        assert!(dict.starts_with("{'descr': '<f8', 'fortran_order': True, 'shape': (2, 3), }"));
+        assert!(dict.ends_with('\n'));
+        assert_eq!(bytes.len(), 10 + len + 6 * 8);
+
+        assert_eq!(b, mat![1.0, 2.0, 3.0; 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn complex() {
+        let path = temp_path("complex");
+        let a = Mat::new_from_data(1, 2, vec![Complex::new(1f32, -1.0), Complex::new(0.5, 2.0)]);
+
+        Npy::save_npy(&a, &path).unwrap();
+        let b: Mat<Complex<f32>> = Npy::load_npy(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn wrong_dtype() {
+        let path = temp_path("wrong_dtype");
+        let a = mat![1f32, 2.0];
+
+        Npy::save_npy(&a, &path).unwrap();
+        let err = <f64 as Npy>::load_npy(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn overflowing_shape() {
+        let path = temp_path("overflowing_shape");
+        let mut bytes = super::header("<f8", false, usize::MAX / 4, 8);
+        bytes.extend_from_slice(&[0; 8]);
+
+        fs::write(&path, &bytes).unwrap();
+        let err = <f64 as Npy>::load_npy(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,1013 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Update steps and proximal operators for gradient-based optimization.
+
+use crate::attribute::Transpose;
+use crate::matrix::{col, Matrix};
+use crate::matrix_vector::ops::{Gemv, Ger};
+use crate::vector::ops::{Axpy, Copy, Dot, Nrm2, Scal};
+use crate::vector::{Strided, Vector};
+use num::traits::Float;
+
+/// Applies the soft-thresholding operator `sign(x) * max(|x| - lambda, 0)`
+/// to every element of `x`.
+///
+/// This is the proximal operator of `lambda * |x|_1`, the shrinkage step of
+/// ISTA and other Lasso solvers.
+pub trait SoftThreshold: Sized {
+    fn soft_threshold<V: ?Sized + Vector<Self>>(x: &mut V, lambda: Self);
+}
+
+macro_rules! soft_threshold_impl(($($t: ident), +) => (
+    $(
+        impl SoftThreshold for $t {
+            fn soft_threshold<V: ?Sized + Vector<Self>>(x: &mut V, lambda: $t) {
+                let mut x = unsafe { Strided::new(x.as_mut_ptr(), x.len(), x.inc()) };
+                for i in 0..x.len() {
+                    let v = x.get(i);
+                    x.set(i, v.signum() * (v.abs() - lambda).max(0.0));
+                }
+            }
+        }
+    )+
+));
+
+soft_threshold_impl!(f32, f64);
+
+/// Fits the Lasso `min_b |y - X b|^2 / 2 + lambda * |b|_1` by cyclic
+/// coordinate descent, starting from the coefficients already in `beta`.
+///
+/// Each coordinate update takes the correlation of column `j` with the
+/// current residual from `dot`, soft-thresholds it and folds the change
+/// back into the residual with `axpy`. Columns of `X` are not normalized,
+/// and `lambda` is the non-negative real weight of the penalty. Stops after
+/// `max_iter` sweeps over the columns, or earlier once no coefficient moves
+/// by more than a few ulps of the largest one in a sweep, and returns the
+/// number of sweeps.
+pub trait LassoCd: Sized {
+    fn lasso_cd<V, W>(
+        x: &dyn Matrix<Self>,
+        y: &V,
+        lambda: Self,
+        max_iter: usize,
+        beta: &mut W,
+    ) -> usize
+    where
+        V: ?Sized + Vector<Self>,
+        W: ?Sized + Vector<Self>;
+}
+
+macro_rules! lasso_cd_impl(($($t: ident), +) => (
+    $(
+        impl LassoCd for $t {
+            fn lasso_cd<V, W>(x: &dyn Matrix<$t>, y: &V, lambda: $t, max_iter: usize, beta: &mut W) -> usize
+            where
+                V: ?Sized + Vector<Self>,
+                W: ?Sized + Vector<Self>,
+            {
+                if y.len() != x.rows() || beta.len() != x.cols() {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut b = vec![0.0; x.cols() as usize];
+                Copy::copy(beta, &mut b);
+                let norms: Vec<$t> = (0..b.len()).map(|j| Dot::dot(&col(x, j), &col(x, j))).collect();
+
+                // r = y - X b
+                let mut r = vec![0.0; x.rows() as usize];
+                Copy::copy(y, &mut r);
+                Gemv::gemv(Transpose::NoTrans, &-1.0, x, &b, &1.0, &mut r);
+
+                let mut sweeps = 0;
+                while sweeps < max_iter {
+                    sweeps += 1;
+                    let mut max_change: $t = 0.0;
+                    for j in 0..b.len() {
+                        let old = b[j];
+                        let new = if norms[j] == 0.0 {
+                            0.0
+                        } else {
+                            let mut z = [Dot::dot(&col(x, j), &r) + norms[j] * old];
+                            SoftThreshold::soft_threshold(&mut z[..], lambda);
+                            z[0] / norms[j]
+                        };
+                        if new != old {
+                            Axpy::axpy(&(old - new), &col(x, j), &mut r);
+                            b[j] = new;
+                            max_change = max_change.max((new - old).abs());
+                        }
+                    }
+                    // Exact equality can fail forever on last-ulp oscillation.
+                    let max_abs = b.iter().fold(0.0, |m: $t, v| m.max(v.abs()));
+                    if max_change <= 4.0 * <$t>::EPSILON * max_abs {
+                        break;
+                    }
+                }
+
+                Copy::copy(&b, beta);
+                sweeps
+            }
+        }
+    )+
+));
+
+lasso_cd_impl!(f32, f64);
+
+/// Writes the gradient of the Huber loss at every residual to `out`.
+///
+/// The loss is quadratic, `r^2 / 2`, for `|r| <= delta` and linear beyond,
+/// so the gradient is `r` clipped to `[-delta, delta]`.
+pub trait HuberGrad: Sized {
+    fn huber_grad<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        residual: &V,
+        delta: Self,
+        out: &mut W,
+    );
+}
+
+macro_rules! huber_grad_impl(($($t: ident), +) => (
+    $(
+        impl HuberGrad for $t {
+            fn huber_grad<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(residual: &V, delta: $t, out: &mut W) {
+                if residual.len() != out.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                Copy::copy(residual, out);
+                let mut out = unsafe { Strided::new(out.as_mut_ptr(), out.len(), out.inc()) };
+                for i in 0..out.len() {
+                    let r = out.get(i);
+                    if r.abs() > delta {
+                        out.set(i, delta.copysign(r));
+                    }
+                }
+            }
+        }
+    )+
+));
+
+huber_grad_impl!(f32, f64);
+
+/// Scales a gradient by a cosine-annealed learning rate.
+///
+/// The rate falls from `base_lr` at step 0 to `min_lr` at step `total`
+/// along half a cosine period, and stays at `min_lr` after that:
+/// `lr = min_lr + (base_lr - min_lr) * (1 + cos(pi * step / total)) / 2`.
+pub trait ApplyLrSchedule: Sized {
+    fn apply_lr_schedule<V: ?Sized + Vector<Self>>(
+        grad: &mut V,
+        step: usize,
+        total: usize,
+        base_lr: Self,
+        min_lr: Self,
+    );
+}
+
+macro_rules! apply_lr_schedule_impl(($($t: ident), +) => (
+    $(
+        impl ApplyLrSchedule for $t {
+            fn apply_lr_schedule<V: ?Sized + Vector<Self>>(grad: &mut V, step: usize, total: usize, base_lr: $t, min_lr: $t) {
+                let lr = if step >= total {
+                    min_lr
+                } else {
+                    let progress = step as $t / total as $t;
+                    let cos = (std::$t::consts::PI * progress).cos();
+                    min_lr + (base_lr - min_lr) * 0.5 * (1.0 + cos)
+                };
+
+                Scal::scal(&lr, grad);
+            }
+        }
+    )+
+));
+
+apply_lr_schedule_impl!(f32, f64);
+
+/// BFGS update of an inverse Hessian approximation `H` from a step `s` and
+/// the change in gradient `y` it caused.
+///
+/// With `rho = 1 / (s^T y)` this computes
+/// `H = (I - rho s y^T) H (I - rho y s^T) + rho s s^T`, expanded into a
+/// `gemv` for `H y` and three `ger` rank-1 updates. `H` is assumed to be
+/// symmetric and is updated in full.
+///
+/// If the curvature `s^T y` is not positive the update would lose positive
+/// definiteness, so `H` is left alone and `false` is returned.
+pub trait BfgsUpdate: Sized {
+    fn bfgs_update<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        h: &mut dyn Matrix<Self>,
+        s: &V,
+        y: &W,
+    ) -> bool;
+}
+
+macro_rules! bfgs_update_impl(($($t: ident), +) => (
+    $(
+        impl BfgsUpdate for $t {
+            fn bfgs_update<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(h: &mut dyn Matrix<$t>, s: &V, y: &W) -> bool {
+                let n = s.len();
+                if h.rows() != n || h.cols() != n || y.len() != n {
+                    panic!("Dimension mismatch");
+                }
+
+                let sy: $t = Dot::dot(s, y);
+                if sy.is_nan() || sy <= 0.0 {
+                    return false;
+                }
+                let rho = 1.0 / sy;
+
+                let mut hy = vec![0.0; n as usize];
+                Gemv::gemv(Transpose::NoTrans, &1.0, h, y, &0.0, &mut hy);
+                let yhy: $t = Dot::dot(y, &hy);
+
+                Ger::ger(&-rho, s, &hy, h);
+                Ger::ger(&-rho, &hy, s, h);
+                Ger::ger(&(rho * rho * yhy + rho), s, s, h);
+
+                true
+            }
+        }
+    )+
+));
+
+bfgs_update_impl!(f32, f64);
+
+/// Rescales a set of gradient vectors together so that their combined L2
+/// norm is at most `max_norm`.
+///
+/// The global norm is the norm of all vectors stacked end to end, computed
+/// from each vector's `nrm2` with `combine_nrm2`. If it exceeds `max_norm`,
+/// every vector is scaled by `max_norm / total`, which keeps the direction
+/// of the overall step. Returns the norm from before clipping.
+pub trait ClipGradNorm: Sized {
+    /// The norm of the concatenation of vectors whose norms are `norms`.
+    ///
+    /// This is `nrm2` of the norms, so it avoids overflow the same way.
+    fn combine_nrm2(norms: &[Self]) -> Self;
+    fn clip_grad_norm(grads: &mut [&mut dyn Vector<Self>], max_norm: Self) -> Self;
+}
+
+macro_rules! clip_grad_norm_impl(($($t: ident), +) => (
+    $(
+        impl ClipGradNorm for $t {
+            fn combine_nrm2(norms: &[$t]) -> $t {
+                Nrm2::nrm2(norms)
+            }
+
+            fn clip_grad_norm(grads: &mut [&mut dyn Vector<$t>], max_norm: $t) -> $t {
+                let norms: Vec<$t> = grads.iter().map(|g| Nrm2::nrm2(&**g)).collect();
+                let total = <$t as ClipGradNorm>::combine_nrm2(&norms);
+
+                if total > max_norm {
+                    let factor = max_norm / total;
+                    for g in grads.iter_mut() {
+                        Scal::scal(&factor, &mut **g);
+                    }
+                }
+
+                total
+            }
+        }
+    )+
+));
+
+clip_grad_norm_impl!(f32, f64);
+
+/// Multiplies every gradient vector by the loss scale `scale`, as in
+/// mixed-precision training.
+///
+/// Returns `false` if any scaled element is infinite or NaN. The scaled
+/// loss overflowed in that case, and the caller should skip the update and
+/// retry with a smaller scale. All vectors are scaled either way.
+pub trait LossScale: Sized {
+    fn loss_scale(grads: &mut [&mut dyn Vector<Self>], scale: Self) -> bool;
+}
+
+macro_rules! loss_scale_impl(($($t: ident), +) => (
+    $(
+        impl LossScale for $t {
+            fn loss_scale(grads: &mut [&mut dyn Vector<$t>], scale: $t) -> bool {
+                let mut finite = true;
+                for g in grads.iter_mut() {
+                    Scal::scal(&scale, &mut **g);
+                    let g = unsafe { Strided::new(g.as_ptr(), g.len(), g.inc()) };
+                    finite &= (0..g.len()).all(|i| g.get(i).is_finite());
+                }
+
+                finite
+            }
+        }
+    )+
+));
+
+loss_scale_impl!(f32, f64);
+
+/// One step of the Adam optimizer.
+///
+/// The moment estimates are decayed with `scal` and take in the gradient
+/// with `axpy`: `m = beta1 * m + (1 - beta1) * g` and
+/// `v = beta2 * v + (1 - beta2) * g^2`. After the bias corrections for step
+/// `t` (counted from 1) the parameters move by
+/// `-lr * m_hat / (sqrt(v_hat) + eps)`, elementwise.
+pub trait AdamStep: Sized {
+    #[allow(clippy::too_many_arguments)]
+    fn adam_step<U, V, W, X>(
+        param: &mut U,
+        grad: &V,
+        m: &mut W,
+        v: &mut X,
+        t: usize,
+        lr: Self,
+        beta1: Self,
+        beta2: Self,
+        eps: Self,
+    ) where
+        U: ?Sized + Vector<Self>,
+        V: ?Sized + Vector<Self>,
+        W: ?Sized + Vector<Self>,
+        X: ?Sized + Vector<Self>;
+}
+
+macro_rules! adam_step_impl(($($t: ident), +) => (
+    $(
+        impl AdamStep for $t {
+            fn adam_step<U, V, W, X>(param: &mut U, grad: &V, m: &mut W, v: &mut X, t: usize, lr: $t, beta1: $t, beta2: $t, eps: $t)
+            where
+                U: ?Sized + Vector<Self>,
+                V: ?Sized + Vector<Self>,
+                W: ?Sized + Vector<Self>,
+                X: ?Sized + Vector<Self>,
+            {
+                let n = param.len();
+                if grad.len() != n || m.len() != n || v.len() != n {
+                    panic!("Dimension mismatch");
+                }
+                if t == 0 {
+                    panic!("Adam steps are counted from 1");
+                }
+
+                let g = unsafe { Strided::new(grad.as_ptr(), n, grad.inc()) };
+                let g2: Vec<$t> = (0..n).map(|i| g.get(i) * g.get(i)).collect();
+                Scal::scal(&beta1, m);
+                Axpy::axpy(&(1.0 - beta1), grad, m);
+                Scal::scal(&beta2, v);
+                Axpy::axpy(&(1.0 - beta2), &g2, v);
+
+                let m = unsafe { Strided::new(m.as_ptr(), n, m.inc()) };
+                let v = unsafe { Strided::new(v.as_ptr(), n, v.inc()) };
+                let (c1, c2) = (1.0 - beta1.powi(t as i32), 1.0 - beta2.powi(t as i32));
+                let step: Vec<$t> = (0..n)
+                    .map(|i| (m.get(i) / c1) / ((v.get(i) / c2).sqrt() + eps))
+                    .collect();
+                Axpy::axpy(&-lr, &step, param);
+            }
+        }
+    )+
+));
+
+adam_step_impl!(f32, f64);
+
+/// One step of gradient descent with Nesterov momentum.
+///
+/// With the gradient `g` taken at the current parameters, the velocity is
+/// updated as `v = momentum * v + g` by a `scal` and an `axpy`, and the
+/// parameters move by `-lr * (g + momentum * v)`, two more `axpy`s. This is
+/// the usual reformulation of Nesterov's method that avoids evaluating the
+/// gradient at a look-ahead point.
+pub trait NesterovStep: Sized {
+    fn nesterov_step<U, V, W>(param: &mut U, grad: &V, velocity: &mut W, lr: Self, momentum: Self)
+    where
+        U: ?Sized + Vector<Self>,
+        V: ?Sized + Vector<Self>,
+        W: ?Sized + Vector<Self>;
+}
+
+macro_rules! nesterov_step_impl(($($t: ident), +) => (
+    $(
+        impl NesterovStep for $t {
+            fn nesterov_step<U, V, W>(param: &mut U, grad: &V, velocity: &mut W, lr: $t, momentum: $t)
+            where
+                U: ?Sized + Vector<Self>,
+                V: ?Sized + Vector<Self>,
+                W: ?Sized + Vector<Self>,
+            {
+                let n = param.len();
+                if grad.len() != n || velocity.len() != n {
+                    panic!("Dimension mismatch");
+                }
+
+                Scal::scal(&momentum, velocity);
+                Axpy::axpy(&1.0, grad, velocity);
+                Axpy::axpy(&-lr, grad, param);
+                Axpy::axpy(&(-lr * momentum), velocity, param);
+            }
+        }
+    )+
+));
+
+nesterov_step_impl!(f32, f64);
+
+/// One step of RMSProp.
+///
+/// The cache of squared gradients is decayed with `scal` and takes in the
+/// new one with `axpy`: `cache = decay * cache + (1 - decay) * g^2`. The
+/// parameters then move by `-lr * g / (sqrt(cache) + eps)`, elementwise, so
+/// every coordinate takes steps of roughly `lr` however steep it is.
+pub trait RmspropStep: Sized {
+    fn rmsprop_step<U, V, W>(
+        param: &mut U,
+        grad: &V,
+        cache: &mut W,
+        lr: Self,
+        decay: Self,
+        eps: Self,
+    ) where
+        U: ?Sized + Vector<Self>,
+        V: ?Sized + Vector<Self>,
+        W: ?Sized + Vector<Self>;
+}
+
+macro_rules! rmsprop_step_impl(($($t: ident), +) => (
+    $(
+        impl RmspropStep for $t {
+            fn rmsprop_step<U, V, W>(param: &mut U, grad: &V, cache: &mut W, lr: $t, decay: $t, eps: $t)
+            where
+                U: ?Sized + Vector<Self>,
+                V: ?Sized + Vector<Self>,
+                W: ?Sized + Vector<Self>,
+            {
+                let n = param.len();
+                if grad.len() != n || cache.len() != n {
+                    panic!("Dimension mismatch");
+                }
+
+                let g = unsafe { Strided::new(grad.as_ptr(), n, grad.inc()) };
+                let g2: Vec<$t> = (0..n).map(|i| g.get(i) * g.get(i)).collect();
+                Scal::scal(&decay, cache);
+                Axpy::axpy(&(1.0 - decay), &g2, cache);
+
+                let c = unsafe { Strided::new(cache.as_ptr(), n, cache.inc()) };
+                let step: Vec<$t> = (0..n).map(|i| g.get(i) / (c.get(i).sqrt() + eps)).collect();
+                Axpy::axpy(&-lr, &step, param);
+            }
+        }
+    )+
+));
+
+rmsprop_step_impl!(f32, f64);
+
+/// Compares an analytic gradient against central finite differences.
+///
+/// Each coordinate of `x` in turn is moved by `+eps` and `-eps` and the
+/// estimate is `(f(x + eps e_i) - f(x - eps e_i)) / (2 eps)`. Returns
+/// `|g - g_fd| / max(|g|, |g_fd|)` in the `nrm2` norm, or zero if both
+/// gradients vanish. For smooth `f` a correct gradient gives an error of
+/// order `eps^2`, while a wrong one gives an error near one. `x` is copied,
+/// so `f` and `grad` always see a contiguous vector.
+pub trait CheckGradient: Sized {
+    fn check_gradient<F, G, V>(f: F, grad: G, x: &V, eps: Self) -> Self
+    where
+        F: Fn(&dyn Vector<Self>) -> Self,
+        G: Fn(&dyn Vector<Self>) -> Vec<Self>,
+        V: ?Sized + Vector<Self>;
+}
+
+macro_rules! check_gradient_impl(($($t: ident), +) => (
+    $(
+        impl CheckGradient for $t {
+            fn check_gradient<F, G, V>(f: F, grad: G, x: &V, eps: $t) -> $t
+            where
+                F: Fn(&dyn Vector<$t>) -> $t,
+                G: Fn(&dyn Vector<$t>) -> Vec<$t>,
+                V: ?Sized + Vector<$t>,
+            {
+                let mut point = vec![0.0; x.len() as usize];
+                Copy::copy(x, &mut point);
+                let analytic = grad(&point);
+                if analytic.len() != point.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut diff = analytic.clone();
+                for i in 0..point.len() {
+                    let xi = point[i];
+                    point[i] = xi + eps;
+                    let up = f(&point);
+                    point[i] = xi - eps;
+                    let down = f(&point);
+                    point[i] = xi;
+                    diff[i] -= (up - down) / (2.0 * eps);
+                }
+
+                let mut numeric = analytic.clone();
+                Axpy::axpy(&-1.0, &diff, &mut numeric);
+                let scale = <$t>::max(Nrm2::nrm2(&analytic), Nrm2::nrm2(&numeric));
+                if scale == 0.0 {
+                    0.0
+                } else {
+                    Nrm2::nrm2(&diff) / scale
+                }
+            }
+        }
+    )+
+));
+
+check_gradient_impl!(f32, f64);
+
+/// Sums gradients over several micro-batches before a single update.
+pub struct GradAccumulator<T> {
+    sum: Vec<T>,
+}
+
+impl<T: Float + Axpy + Scal> GradAccumulator<T> {
+    /// Starts an all-zero accumulator for gradients of length `len`.
+    pub fn new(len: usize) -> GradAccumulator<T> {
+        GradAccumulator {
+            sum: vec![T::zero(); len],
+        }
+    }
+
+    /// Adds `grad` to the running sum.
+    pub fn add<V: ?Sized + Vector<T>>(&mut self, grad: &V) {
+        if grad.len() as usize != self.sum.len() {
+            panic!("Dimension mismatch");
+        }
+
+        Axpy::axpy(&T::one(), grad, &mut self.sum);
+    }
+
+    /// The running sum divided by `batches`, usually the number of gradients
+    /// added since the last reset.
+    pub fn scaled_mean(&self, batches: usize) -> Vec<T> {
+        if batches == 0 {
+            panic!("Batch count must be positive");
+        }
+
+        let mut mean = self.sum.clone();
+        Scal::scal(&T::from(batches).unwrap().recip(), &mut mean);
+        mean
+    }
+
+    /// Zeros the running sum for the next round of micro-batches.
+    pub fn reset(&mut self) {
+        for x in self.sum.iter_mut() {
+            *x = T::zero();
+        }
+    }
+}
+
+#[cfg(test)]
+mod soft_threshold_tests {
+    use crate::math::optim::SoftThreshold;
+    use crate::vector::Strided;
+
+    #[test]
+    fn real() {
+        let mut x = vec![-3f32, 0.5, 2.0];
+
+        SoftThreshold::soft_threshold(&mut x, 1.0);
+        assert_eq!(x, vec![-2.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn strided() {
+        let mut x = vec![4f64, 4.0, -0.25, 4.0];
+
+        {
+            let mut evens = unsafe { Strided::new(x.as_mut_ptr(), 2, 2) };
+            SoftThreshold::soft_threshold(&mut evens, 0.5);
+        }
+        assert_eq!(x, vec![3.5, 4.0, 0.0, 4.0]);
+    }
+}
+
+#[cfg(test)]
+mod lasso_cd_tests {
+    use crate::math::optim::LassoCd;
+    use crate::math::Mat;
+
+    fn data() -> (Mat<f64>, Vec<f64>) {
+        // y = 2 x_0 - x_1; x_2 is a weakly related nuisance feature.
+        let x = mat![
+            1.0, 0.0, 0.1;
+            0.0, 1.0, -0.1;
+            1.0, 1.0, 0.2;
+            2.0, -1.0, -0.2;
+            -1.0, 2.0, 0.1
+        ];
+        let y = vec![2.0, -1.0, 1.0, 5.0, -4.0];
+        (x, y)
+    }
+
+    #[test]
+    fn zeroes_nuisance_feature() {
+        let (x, y) = data();
+        let mut beta = vec![0f64; 3];
+
+        let sweeps = LassoCd::lasso_cd(&x, &y, 1.0, 1000, &mut beta);
+        assert!(sweeps < 1000);
+        assert_eq!(beta[2], 0.0);
+        assert!(beta[0] > 1.5 && beta[1] < -0.5, "{:?}", beta);
+    }
+
+    #[test]
+    fn no_penalty_is_least_squares() {
+        let (x, y) = data();
+        let mut beta = vec![0f64; 3];
+
+        LassoCd::lasso_cd(&x, &y, 0.0, 10_000, &mut beta);
+        assert!((beta[0] - 2.0).abs() < 1e-8 && (beta[1] + 1.0).abs() < 1e-8);
+        assert!(beta[2].abs() < 1e-8);
+    }
+
+    #[test]
+    fn large_penalty_zeroes_everything() {
+        let (x, y) = data();
+        let mut beta = vec![1f64, 1.0, 1.0];
+
+        LassoCd::lasso_cd(&x, &y, 100.0, 10, &mut beta);
+        assert_eq!(beta, vec![0.0; 3]);
+    }
+}
+
+#[cfg(test)]
+mod huber_grad_tests {
+    use crate::math::optim::HuberGrad;
+    use crate::vector::Strided;
+
+    #[test]
+    fn piecewise() {
+        let r = vec![-3f64, -1.0, -0.25, 0.0, 0.5, 1.0, 2.5];
+        let mut g = vec![0f64; 7];
+
+        HuberGrad::huber_grad(&r, 1.0, &mut g);
+        assert_eq!(g, vec![-1.0, -1.0, -0.25, 0.0, 0.5, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn strided() {
+        let r = [4f32, 0.0, -0.5, 0.0];
+        let mut g = [9f32; 4];
+        let evens = unsafe { Strided::new(r.as_ptr(), 2, 2) };
+        let mut odds = unsafe { Strided::new(g.as_mut_ptr().add(1), 2, 2) };
+
+        HuberGrad::huber_grad(&evens, 2.0, &mut odds);
+        assert_eq!(g, [9.0, 2.0, 9.0, -0.5]);
+    }
+}
+
+#[cfg(test)]
+mod apply_lr_schedule_tests {
+    use crate::math::optim::ApplyLrSchedule;
+
+    fn rate(step: usize) -> f64 {
+        let mut g = vec![1f64];
+        ApplyLrSchedule::apply_lr_schedule(&mut g, step, 100, 0.1, 0.001);
+        g[0]
+    }
+
+    #[test]
+    fn endpoints() {
+        assert_eq!(rate(0), 0.1);
+        assert!((rate(50) - 0.0505).abs() < 1e-12);
+        assert_eq!(rate(100), 0.001);
+        assert_eq!(rate(150), 0.001);
+    }
+
+    #[test]
+    fn decreasing() {
+        let rates: Vec<f64> = (0..=100).map(rate).collect();
+        assert!(rates.windows(2).all(|w| w[1] < w[0]));
+    }
+
+    #[test]
+    fn scales_gradient() {
+        let mut g = vec![2f32, -4.0];
+
+        ApplyLrSchedule::apply_lr_schedule(&mut g, 0, 10, 0.5, 0.0);
+        assert_eq!(g, vec![1.0, -2.0]);
+    }
+}
+
+#[cfg(test)]
+mod bfgs_update_tests {
+    use crate::math::optim::BfgsUpdate;
+    use crate::math::Mat;
+
+    fn distance(a: &Mat<f64>, b: &Mat<f64>) -> f64 {
+        let mut d = 0.0;
+        for i in 0..a.rows() {
+            for j in 0..a.cols() {
+                d += (a[i][j] - b[i][j]).powi(2);
+            }
+        }
+        d.sqrt()
+    }
+
+    #[test]
+    fn quadratic() {
+        // f(x) = x^T A x / 2 with A = diag(2, 4), so y = A s.
+        let inverse = mat![0.5, 0.0; 0.0, 0.25];
+        let mut h = mat![1f64, 0.0; 0.0, 1.0];
+        let s = vec![1f64, 1.0];
+        let y = vec![2f64, 4.0];
+
+        let before = distance(&h, &inverse);
+        assert!(BfgsUpdate::bfgs_update(&mut h, &s, &y));
+        assert!(distance(&h, &inverse) < before);
+
+        // The secant condition H y = s holds after the update.
+        for i in 0..2 {
+            assert!((h[i][0] * y[0] + h[i][1] * y[1] - s[i]).abs() < 1e-12);
+        }
+        assert_eq!(h[0][1], h[1][0]);
+    }
+
+    #[test]
+    fn negative_curvature() {
+        let mut h = mat![1f32, 0.0; 0.0, 1.0];
+        let s = vec![1f32, 0.0];
+        let y = vec![-1f32, 3.0];
+
+        assert!(!BfgsUpdate::bfgs_update(&mut h, &s, &y));
+        assert_eq!(h, mat![1.0, 0.0; 0.0, 1.0]);
+    }
+}
+
+#[cfg(test)]
+mod clip_grad_norm_tests {
+    use crate::math::optim::ClipGradNorm;
+    use crate::vector::Vector;
+
+    #[test]
+    fn combine() {
+        assert_eq!(ClipGradNorm::combine_nrm2(&[3f64, 4.0]), 5.0);
+        assert_eq!(ClipGradNorm::combine_nrm2(&[0f32; 0]), 0.0);
+    }
+
+    #[test]
+    fn below_threshold() {
+        let mut a = vec![3f64];
+        let mut b = vec![0f64, 4.0];
+
+        let total = {
+            let mut grads: [&mut dyn Vector<f64>; 2] = [&mut a, &mut b];
+            ClipGradNorm::clip_grad_norm(&mut grads, 5.0)
+        };
+        assert_eq!(total, 5.0);
+        assert_eq!((a, b), (vec![3.0], vec![0.0, 4.0]));
+    }
+
+    #[test]
+    fn above_threshold() {
+        let mut a = vec![6f32, 0.0];
+        let mut b = vec![8f32];
+
+        let total = {
+            let mut grads: [&mut dyn Vector<f32>; 2] = [&mut a, &mut b];
+            ClipGradNorm::clip_grad_norm(&mut grads, 2.0)
+        };
+        assert_eq!(total, 10.0);
+        assert!((a[0] - 1.2).abs() < 1e-6 && a[1] == 0.0);
+        assert!((b[0] - 1.6).abs() < 1e-6);
+    }
+}
+
+#[cfg(test)]
+mod loss_scale_tests {
+    use crate::math::optim::LossScale;
+    use crate::vector::Vector;
+
+    #[test]
+    fn finite() {
+        let mut a = vec![0.5f32, -1.0];
+        let mut b = vec![2f32];
+
+        let ok = {
+            let mut grads: [&mut dyn Vector<f32>; 2] = [&mut a, &mut b];
+            LossScale::loss_scale(&mut grads, 1024.0)
+        };
+        assert!(ok);
+        assert_eq!((a, b), (vec![512.0, -1024.0], vec![2048.0]));
+    }
+
+    #[test]
+    fn overflow() {
+        let mut a = vec![1f32, 2.0];
+        let mut b = vec![1e35f32];
+
+        let ok = {
+            let mut grads: [&mut dyn Vector<f32>; 2] = [&mut a, &mut b];
+            LossScale::loss_scale(&mut grads, 65536.0)
+        };
+        assert!(!ok);
+        assert_eq!(a, vec![65536.0, 131072.0]);
+        assert!(b[0].is_infinite());
+    }
+
+    #[test]
+    fn nan() {
+        let mut a = vec![1f64, f64::NAN];
+
+        let mut grads: [&mut dyn Vector<f64>; 1] = [&mut a];
+        assert!(!LossScale::loss_scale(&mut grads, 2.0));
+    }
+}
+
+#[cfg(test)]
+mod adam_step_tests {
+    use crate::math::optim::AdamStep;
+
+    #[test]
+    fn first_step() {
+        // The bias-corrected first step is lr * sign(g), up to eps.
+        let mut x = vec![1f64, -2.0];
+        let (mut m, mut v) = (vec![0f64; 2], vec![0f64; 2]);
+
+        AdamStep::adam_step(
+            &mut x,
+            &vec![4.0, -0.5],
+            &mut m,
+            &mut v,
+            1,
+            0.1,
+            0.9,
+            0.999,
+            1e-8,
+        );
+        assert!((x[0] - 0.9).abs() < 1e-6);
+        assert!((x[1] + 1.9).abs() < 1e-6);
+    }
+
+    #[test]
+    fn quadratic() {
+        // f(x) = |x - c|^2 has its minimum at c.
+        let c = [3f64, -1.0, 0.5];
+        let mut x = vec![0f64; 3];
+        let (mut m, mut v) = (vec![0f64; 3], vec![0f64; 3]);
+
+        for t in 1..=2000 {
+            let grad: Vec<f64> = x.iter().zip(&c).map(|(xi, ci)| 2.0 * (xi - ci)).collect();
+            AdamStep::adam_step(&mut x, &grad, &mut m, &mut v, t, 0.05, 0.9, 0.999, 1e-8);
+        }
+
+        for (xi, ci) in x.iter().zip(&c) {
+            assert!((xi - ci).abs() < 1e-3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod nesterov_step_tests {
+    use crate::math::optim::NesterovStep;
+    use crate::vector::ops::Axpy;
+
+    #[test]
+    fn first_step() {
+        let mut x = vec![1f64, -1.0];
+        let mut v = vec![0f64; 2];
+
+        // v = g, x -= lr * (1 + momentum) * g
+        NesterovStep::nesterov_step(&mut x, &vec![2.0, 4.0], &mut v, 0.1, 0.5);
+        assert_eq!(v, vec![2.0, 4.0]);
+        assert!((x[0] - 0.7).abs() < 1e-12 && (x[1] + 1.6).abs() < 1e-12);
+    }
+
+    /// Iterations until `|x| < 1e-6` on `f(x) = (x_0^2 + 100 x_1^2) / 2`.
+    fn iterations(mut step: impl FnMut(&mut Vec<f64>, &Vec<f64>)) -> usize {
+        let mut x = vec![1f64, 1.0];
+        for iter in 0..10_000 {
+            if x[0].hypot(x[1]) < 1e-6 {
+                return iter;
+            }
+            let grad = vec![x[0], 100.0 * x[1]];
+            step(&mut x, &grad);
+        }
+        panic!("Did not converge");
+    }
+
+    #[test]
+    fn faster_than_gradient_descent() {
+        let lr = 0.01;
+        let plain = iterations(|x, g| Axpy::axpy(&-lr, g, x));
+        let mut v = vec![0f64; 2];
+        let nesterov = iterations(|x, g| NesterovStep::nesterov_step(x, g, &mut v, lr, 0.9));
+
+        assert!(nesterov * 4 < plain);
+    }
+}
+
+#[cfg(test)]
+mod rmsprop_step_tests {
+    use crate::math::optim::RmspropStep;
+
+    #[test]
+    fn first_step() {
+        let mut x = vec![1f64, -1.0];
+        let mut cache = vec![0f64; 2];
+
+        // cache = g^2 / 10, so every coordinate moves by lr * sqrt(10).
+        RmspropStep::rmsprop_step(&mut x, &vec![2.0, -40.0], &mut cache, 0.01, 0.9, 0.0);
+        assert!((cache[0] - 0.4).abs() < 1e-12 && (cache[1] - 160.0).abs() < 1e-9);
+        let moved = 0.01 * 10f64.sqrt();
+        assert!((x[0] - (1.0 - moved)).abs() < 1e-12);
+        assert!((x[1] - (-1.0 + moved)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn converges_on_quadratic() {
+        // f(x) = (x_0^2 + 100 x_1^2) / 2, badly scaled for plain descent.
+        let mut x = vec![1f32, 1.0];
+        let mut cache = vec![0f32; 2];
+        for _ in 0..1000 {
+            let grad = vec![x[0], 100.0 * x[1]];
+            RmspropStep::rmsprop_step(&mut x, &grad, &mut cache, 0.005, 0.9, 1e-8);
+        }
+
+        // A fixed rate leaves an oscillation of about `lr` around the minimum.
+        assert!(x[0].abs() < 0.02 && x[1].abs() < 0.02, "{:?}", x);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn short_cache() {
+        let mut x = vec![1f32, 2.0];
+        RmspropStep::rmsprop_step(&mut x, &vec![1.0, 1.0], &mut vec![0.0], 0.1, 0.9, 1e-8);
+    }
+}
+
+#[cfg(test)]
+mod grad_accumulator_tests {
+    use crate::math::optim::GradAccumulator;
+
+    #[test]
+    fn mean() {
+        let grads = [[1f64, -2.0], [2.0, 0.5], [6.0, -1.0]];
+
+        let mut acc = GradAccumulator::new(2);
+        for g in &grads {
+            acc.add(&g[..]);
+        }
+        let mean = acc.scaled_mean(3);
+        for (j, m) in mean.iter().enumerate() {
+            let expected = grads.iter().map(|g| g[j]).sum::<f64>() / 3.0;
+            assert!((m - expected).abs() < 1e-12);
+        }
+
+        acc.reset();
+        acc.add(&vec![4.0, 4.0]);
+        assert_eq!(acc.scaled_mean(2), vec![2.0, 2.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_batches() {
+        let acc = GradAccumulator::<f32>::new(1);
+        let _ = acc.scaled_mean(0);
+    }
+}
+
+#[cfg(test)]
+mod check_gradient_tests {
+    use crate::math::optim::CheckGradient;
+    use crate::vector::ops::{Copy, Dot};
+    use crate::vector::Vector;
+
+    fn half_norm_sq(x: &dyn Vector<f64>) -> f64 {
+        0.5 * Dot::dot(x, x)
+    }
+
+    fn identity(x: &dyn Vector<f64>) -> Vec<f64> {
+        let mut g = vec![0.0; x.len() as usize];
+        Copy::copy(x, &mut g);
+        g
+    }
+
+    #[test]
+    fn correct_gradient() {
+        let x = vec![1.5f64, -2.0, 0.25, 3.0];
+
+        let err = CheckGradient::check_gradient(half_norm_sq, identity, &x, 1e-5);
+        assert!(err < 1e-8, "relative error {}", err);
+    }
+
+    #[test]
+    fn wrong_gradient() {
+        let x = vec![1.5f64, -2.0, 0.25, 3.0];
+        let doubled = |x: &dyn Vector<f64>| identity(x).iter().map(|v| 2.0 * v).collect();
+
+        let err = CheckGradient::check_gradient(half_norm_sq, doubled, &x, 1e-5);
+        assert!(err > 0.4, "relative error {}", err);
+    }
+
+    #[test]
+    fn zero_gradient() {
+        let x = vec![0f32; 3];
+        let f = |x: &dyn Vector<f32>| Dot::dot(x, x);
+        let g = |x: &dyn Vector<f32>| vec![0.0; x.len() as usize];
+
+        assert_eq!(CheckGradient::check_gradient(f, g, &x, 1e-2), 0.0);
+    }
+}
@@ -0,0 +1,471 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Transforms, resampling and reshuffling of vectors built on BLAS calls.
+
+use crate::attribute::Transpose;
+use crate::default::Default;
+use crate::math::Mat;
+use crate::matrix_vector::ops::Gemv;
+use crate::vector::ops::{Axpy, Copy, Scal};
+use crate::vector::{Strided, Vector};
+use num_complex::{Complex32, Complex64};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+/// Interleaves two vectors of equal length into `[a0, b0, a1, b1, ...]`.
+///
+/// Each input is placed with a single strided `copy` into the combined
+/// buffer.
+pub trait InterleavePair: Sized {
+    fn interleave_pair<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        a: &V,
+        b: &W,
+    ) -> Vec<Self>;
+}
+
+macro_rules! interleave_pair_impl(($($t: ident), +) => (
+    $(
+        impl InterleavePair for $t {
+            fn interleave_pair<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(a: &V, b: &W) -> Vec<$t> {
+                if a.len() != b.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let n = a.len();
+                let mut result = vec![Default::zero(); 2 * n as usize];
+                unsafe {
+                    Copy::copy(a, &mut Strided::new(result.as_mut_ptr(), n, 2));
+                    Copy::copy(b, &mut Strided::new(result.as_mut_ptr().add(1), n, 2));
+                }
+
+                result
+            }
+        }
+    )+
+));
+
+interleave_pair_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod interleave_pair_tests {
+    use crate::math::signal::InterleavePair;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let a = vec![1f32, 3.0];
+        let b = vec![2f32, 4.0];
+
+        let x = InterleavePair::interleave_pair(&a, &b);
+        assert_eq!(x, vec![1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn complex() {
+        let a = vec![Complex::new(1f64, 1.0)];
+        let b = vec![Complex::new(2f64, -2.0)];
+
+        let x = InterleavePair::interleave_pair(&a, &b);
+        assert_eq!(x, vec![Complex::new(1.0, 1.0), Complex::new(2.0, -2.0)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_lengths() {
+        let a = vec![1f32, 3.0];
+        let b = vec![2f32];
+
+        let _ = InterleavePair::interleave_pair(&a, &b);
+    }
+}
+
+/// Evaluates a polynomial at every point of `x`, storing the values in `out`.
+///
+/// Coefficients are in ascending order, so `[1, 2, 3]` is `1 + 2x + 3x^2`.
+/// Horner's scheme is run on all points at once: the leading coefficient is
+/// broadcast with `scal`, and each step multiplies the running values by `x`
+/// elementwise and adds the next coefficient with an `axpy`.
+pub trait Polyval: Sized {
+    fn polyval<U: ?Sized + Vector<Self>, V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        coeffs: &U,
+        x: &V,
+        out: &mut W,
+    );
+}
+
+macro_rules! polyval_impl(($($t: ident), +) => (
+    $(
+        impl Polyval for $t {
+            fn polyval<U: ?Sized + Vector<Self>, V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(coeffs: &U, x: &V, out: &mut W) {
+                if x.len() != out.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let n = x.len() as usize;
+                let ones: Vec<$t> = vec![Default::one(); n];
+                let mut points: Vec<$t> = vec![Default::zero(); n];
+                Copy::copy(x, &mut points);
+                let mut c: Vec<$t> = vec![Default::zero(); coeffs.len() as usize];
+                Copy::copy(coeffs, &mut c);
+
+                let mut acc = ones.clone();
+                match c.pop() {
+                    Some(lead) => Scal::scal(&lead, &mut acc),
+                    None => Scal::scal(&Default::zero(), &mut acc),
+                }
+
+                for k in c.iter().rev() {
+                    for (a, p) in acc.iter_mut().zip(&points) {
+                        *a *= *p;
+                    }
+                    Axpy::axpy(k, &ones, &mut acc);
+                }
+
+                Copy::copy(&acc, out);
+            }
+        }
+    )+
+));
+
+polyval_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod polyval_tests {
+    use crate::math::signal::Polyval;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let coeffs = vec![1f64, 2.0, 3.0];
+        let x = vec![0f64, 1.0, 2.0];
+        let mut out = vec![0f64; 3];
+
+        Polyval::polyval(&coeffs, &x, &mut out);
+        assert_eq!(out, vec![1.0, 6.0, 17.0]);
+    }
+
+    #[test]
+    fn constant() {
+        let coeffs = vec![4f32];
+        let x = vec![-1f32, 7.0];
+        let mut out = vec![0f32; 2];
+
+        Polyval::polyval(&coeffs, &x, &mut out);
+        assert_eq!(out, vec![4.0, 4.0]);
+    }
+
+    #[test]
+    fn complex() {
+        // 1 + x^2 vanishes at i
+        let coeffs = vec![
+            Complex::new(1f32, 0.0),
+            Complex::new(0.0, 0.0),
+            Complex::new(1.0, 0.0),
+        ];
+        let x = vec![Complex::new(0f32, 1.0)];
+        let mut out = vec![Complex::new(5f32, 5.0)];
+
+        Polyval::polyval(&coeffs, &x, &mut out);
+        assert_eq!(out, vec![Complex::new(0.0, 0.0)]);
+    }
+}
+
+/// Changes the sampling rate of a vector by an integer factor.
+pub trait Resample: Sized {
+    /// Keeps every `factor`-th element of `x`, starting with the first.
+    fn downsample<V: ?Sized + Vector<Self>>(x: &V, factor: usize) -> Vec<Self>;
+    /// Inserts `factor - 1` zeros after every element of `x`.
+    fn upsample<V: ?Sized + Vector<Self>>(x: &V, factor: usize) -> Vec<Self>;
+}
+
+macro_rules! resample_impl(($($t: ident), +) => (
+    $(
+        impl Resample for $t {
+            fn downsample<V: ?Sized + Vector<Self>>(x: &V, factor: usize) -> Vec<$t> {
+                if factor == 0 {
+                    panic!("Resampling factor must be positive");
+                }
+
+                let inc = u32::try_from(factor)
+                    .ok()
+                    .and_then(|f| x.inc().checked_mul(f))
+                    .unwrap_or_else(|| panic!("Resampling factor {} is too large", factor));
+                let n = x.len().div_ceil(factor as u32);
+                let mut result = vec![Default::zero(); n as usize];
+                let src = unsafe { Strided::new(x.as_ptr(), n, inc) };
+                Copy::copy(&src, &mut result);
+
+                result
+            }
+
+            fn upsample<V: ?Sized + Vector<Self>>(x: &V, factor: usize) -> Vec<$t> {
+                if factor == 0 {
+                    panic!("Resampling factor must be positive");
+                }
+
+                let inc = u32::try_from(factor)
+                    .unwrap_or_else(|_| panic!("Resampling factor {} is too large", factor));
+                let n = x.len();
+                let len = (n as usize)
+                    .checked_mul(factor)
+                    .unwrap_or_else(|| panic!("Resampling factor {} is too large", factor));
+                let mut result = vec![Default::zero(); len];
+                unsafe {
+                    Copy::copy(x, &mut Strided::new(result.as_mut_ptr(), n, inc));
+                }
+
+                result
+            }
+        }
+    )+
+));
+
+resample_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod resample_tests {
+    use crate::math::signal::Resample;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let x = vec![1f32, 2.0, 3.0, 4.0];
+
+        let down = Resample::downsample(&x, 2);
+        assert_eq!(down, vec![1.0, 3.0]);
+
+        let up = Resample::upsample(&down, 2);
+        assert_eq!(up, vec![1.0, 0.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn uneven_length() {
+        let x = vec![1f64, 2.0, 3.0, 4.0, 5.0];
+
+        assert_eq!(Resample::downsample(&x, 3), vec![1.0, 4.0]);
+        assert_eq!(Resample::downsample(&x, 1), x);
+    }
+
+    #[test]
+    fn complex() {
+        let x = vec![Complex::new(1f64, -1.0)];
+
+        let up = Resample::upsample(&x, 3);
+        assert_eq!(
+            up,
+            vec![
+                Complex::new(1.0, -1.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(0.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_factor() {
+        let x = vec![1f32, 2.0];
+
+        let _ = Resample::downsample(&x, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn downsample_factor_overflow() {
+        let x = vec![1f32, 2.0];
+
+        let _ = Resample::downsample(&x, u32::MAX as usize + 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn upsample_factor_overflow() {
+        let x = vec![1f32, 2.0];
+
+        let _ = Resample::upsample(&x, u32::MAX as usize + 2);
+    }
+}
+
+/// Running sums of a vector and their inverse.
+pub trait PrefixSum: Sized {
+    /// Writes the inclusive prefix sum of `x` to `out`, so
+    /// `out[i] = x[0] + ... + x[i]`.
+    fn prefix_sum<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, out: &mut W);
+    /// Replaces `x` with its inclusive prefix sum.
+    fn prefix_sum_in_place<V: ?Sized + Vector<Self>>(x: &mut V);
+    /// Writes the first differences of `x` to `out`, keeping `x[0]` as the
+    /// first element, which undoes `prefix_sum`.
+    fn diff<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, out: &mut W);
+}
+
+macro_rules! prefix_sum_impl(($($t: ident), +) => (
+    $(
+        impl PrefixSum for $t {
+            fn prefix_sum<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, out: &mut W) {
+                if x.len() != out.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                Copy::copy(x, out);
+                PrefixSum::prefix_sum_in_place(out);
+            }
+
+            fn prefix_sum_in_place<V: ?Sized + Vector<Self>>(x: &mut V) {
+                let mut view = unsafe { Strided::new(x.as_mut_ptr(), x.len(), x.inc()) };
+                for i in 1..view.len() {
+                    let sum = view.get(i - 1) + view.get(i);
+                    view.set(i, sum);
+                }
+            }
+
+            fn diff<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, out: &mut W) {
+                if x.len() != out.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                Copy::copy(x, out);
+                let n = x.len();
+                if n > 1 {
+                    let prev = unsafe { Strided::new(x.as_ptr(), n - 1, x.inc()) };
+                    let mut rest = unsafe { Strided::new(out.as_mut_ptr(), n, out.inc()) }.skip(1);
+                    Axpy::axpy(&-<$t as Default>::one(), &prev, &mut rest);
+                }
+            }
+        }
+    )+
+));
+
+prefix_sum_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod prefix_sum_tests {
+    use crate::math::signal::PrefixSum;
+    use crate::vector::Strided;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let x = vec![1f64, 2.0, 3.0, 4.0];
+        let mut sums = vec![0f64; 4];
+
+        PrefixSum::prefix_sum(&x, &mut sums);
+        assert_eq!(sums, vec![1.0, 3.0, 6.0, 10.0]);
+
+        let mut back = vec![0f64; 4];
+        PrefixSum::diff(&sums, &mut back);
+        assert_eq!(back, x);
+    }
+
+    #[test]
+    fn in_place_strided() {
+        let mut x = [1f32, 9.0, 2.0, 9.0, 3.0, 9.0];
+        let mut odds = unsafe { Strided::new(x.as_mut_ptr(), 3, 2) };
+
+        PrefixSum::prefix_sum_in_place(&mut odds);
+        assert_eq!(x, [1.0, 9.0, 3.0, 9.0, 6.0, 9.0]);
+    }
+
+    #[test]
+    fn complex() {
+        let x = vec![Complex::new(1f32, 1.0), Complex::new(2.0, -3.0)];
+        let mut sums = vec![Complex::new(0f32, 0.0); 2];
+
+        PrefixSum::prefix_sum(&x, &mut sums);
+        assert_eq!(sums[1], Complex::new(3.0, -2.0));
+    }
+
+    #[test]
+    fn empty() {
+        let x: Vec<f64> = vec![];
+        let mut out: Vec<f64> = vec![];
+
+        PrefixSum::prefix_sum(&x, &mut out);
+        PrefixSum::diff(&x, &mut out);
+        assert!(out.is_empty());
+    }
+}
+
+/// The unnormalized DCT-II, `out[k] = sum_n x[n] * cos(pi / N * (n + 1/2) * k)`.
+///
+/// The transform is a single `gemv` with the `N x N` cosine basis. Building
+/// the basis is the expensive part, so it is kept in a per-thread cache
+/// keyed by length and reused by later calls. Meant for the short vectors
+/// of feature transforms, where this beats pulling in an FFT.
+pub trait DctII: Sized {
+    fn dct_ii<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, out: &mut W);
+}
+
+macro_rules! dct_ii_impl(($($t: ident), +) => (
+    $(
+        impl DctII for $t {
+            fn dct_ii<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, out: &mut W) {
+                thread_local! {
+                    static BASES: RefCell<HashMap<usize, Rc<Mat<$t>>>> = RefCell::new(HashMap::new());
+                }
+
+                if x.len() != out.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let n = x.len() as usize;
+                let basis = BASES.with(|bases| {
+                    bases
+                        .borrow_mut()
+                        .entry(n)
+                        .or_insert_with(|| {
+                            let step = std::$t::consts::PI / n as $t;
+                            let entries = (0..n * n).map(|e| {
+                                let (k, j) = ((e / n) as $t, (e % n) as $t);
+                                (step * (j + 0.5) * k).cos()
+                            });
+                            Rc::new(Mat::new_from_data(n, n, entries.collect()))
+                        })
+                        .clone()
+                });
+
+                Gemv::gemv(Transpose::NoTrans, &1.0, &*basis, x, &0.0, out);
+            }
+        }
+    )+
+));
+
+dct_ii_impl!(f32, f64);
+
+#[cfg(test)]
+mod dct_ii_tests {
+    use crate::math::signal::DctII;
+    use crate::vector::Strided;
+
+    #[test]
+    fn reference() {
+        let x = vec![1f64, 2.0, 3.0, 4.0];
+        let mut out = vec![0f64; 4];
+
+        DctII::dct_ii(&x, &mut out);
+        let expected = [10.0, -3.154322, 0.0, -0.224171];
+        for (o, e) in out.iter().zip(&expected) {
+            assert!((o - e).abs() < 1e-6);
+        }
+
+        // The second call reuses the cached basis.
+        let mut again = vec![0f64; 4];
+        DctII::dct_ii(&x, &mut again);
+        assert_eq!(again, out);
+    }
+
+    #[test]
+    fn strided() {
+        let x = [2f32, 9.0, 2.0, 9.0, 2.0];
+        let evens = unsafe { Strided::new(x.as_ptr(), 3, 2) };
+        let mut out = vec![0f32; 3];
+
+        // A constant input only has a DC component.
+        DctII::dct_ii(&evens, &mut out);
+        assert!((out[0] - 6.0).abs() < 1e-5);
+        assert!(out[1].abs() < 1e-5 && out[2].abs() < 1e-5);
+    }
+}
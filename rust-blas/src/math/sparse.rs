@@ -0,0 +1,245 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Sparse matrix storage and kernels that skip the zeros dense BLAS would
+//! multiply through.
+
+use crate::default::Default;
+use crate::vector::{Strided, Vector};
+use num_complex::{Complex32, Complex64};
+
+/// A matrix in compressed sparse row (CSR) format.
+///
+/// The nonzeros of row `i` are `values[row_ptr[i]..row_ptr[i + 1]]`, and
+/// `col_indices` holds the column of each of them.
+#[derive(Debug, PartialEq)]
+pub struct CsrMatrix<T> {
+    rows: usize,
+    cols: usize,
+    row_ptr: Vec<usize>,
+    col_indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T> CsrMatrix<T> {
+    /// Assembles a CSR matrix from its three arrays.
+    ///
+    /// Panics unless `row_ptr` has `rows + 1` nondecreasing entries starting
+    /// at zero and ending at `values.len()`, and every column index is below
+    /// `cols`.
+    pub fn new(
+        rows: usize,
+        cols: usize,
+        row_ptr: Vec<usize>,
+        col_indices: Vec<usize>,
+        values: Vec<T>,
+    ) -> CsrMatrix<T> {
+        assert_eq!(row_ptr.len(), rows + 1, "Row pointer length mismatch");
+        assert_eq!(
+            col_indices.len(),
+            values.len(),
+            "Column index length mismatch"
+        );
+        assert!(
+            row_ptr[0] == 0 && row_ptr[rows] == values.len(),
+            "Row pointers must span the values"
+        );
+        assert!(
+            row_ptr.windows(2).all(|w| w[0] <= w[1]),
+            "Row pointers must be nondecreasing"
+        );
+        assert!(
+            col_indices.iter().all(|&j| j < cols),
+            "Column index out of range"
+        );
+
+        CsrMatrix {
+            rows,
+            cols,
+            row_ptr,
+            col_indices,
+            values,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The number of stored entries.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn row_ptr(&self) -> &[usize] {
+        &self.row_ptr
+    }
+
+    pub fn col_indices(&self) -> &[usize] {
+        &self.col_indices
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+}
+
+/// Computes `y = A * x` for a sparse `A`.
+///
+/// Each entry of `y` is the dot product of the stored values of a row with
+/// the matching entries of `x`; entries of `x` in empty columns are never
+/// read.
+pub trait CsrGemv: Sized {
+    fn csr_gemv<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        a: &CsrMatrix<Self>,
+        x: &V,
+        y: &mut W,
+    );
+}
+
+macro_rules! csr_gemv_impl(($($t: ident), +) => (
+    $(
+        impl CsrGemv for $t {
+            fn csr_gemv<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(a: &CsrMatrix<$t>, x: &V, y: &mut W) {
+                if x.len() as usize != a.cols || y.len() as usize != a.rows {
+                    panic!("Dimension mismatch");
+                }
+
+                let x = unsafe { Strided::new(x.as_ptr(), x.len(), x.inc()) };
+                let mut y = unsafe { Strided::new(y.as_mut_ptr(), y.len(), y.inc()) };
+                for i in 0..a.rows {
+                    let (start, end) = (a.row_ptr[i], a.row_ptr[i + 1]);
+                    let sum = a.values[start..end]
+                        .iter()
+                        .zip(&a.col_indices[start..end])
+                        .fold(Default::zero(), |acc: $t, (&v, &j)| acc + v * x.get(j as u32));
+                    y.set(i as u32, sum);
+                }
+            }
+        }
+    )+
+));
+
+csr_gemv_impl!(f32, f64, Complex32, Complex64);
+
+/// Computes the dot product of `dense` with a sparse vector given as
+/// parallel `indices` and `values`.
+///
+/// Only the listed entries of `dense` are read. Repeated indices each
+/// contribute, and complex values are not conjugated.
+pub trait DotSparse: Sized {
+    fn dot_sparse<V: ?Sized + Vector<Self>>(dense: &V, indices: &[usize], values: &[Self]) -> Self;
+}
+
+macro_rules! dot_sparse_impl(($($t: ident), +) => (
+    $(
+        impl DotSparse for $t {
+            fn dot_sparse<V: ?Sized + Vector<Self>>(dense: &V, indices: &[usize], values: &[$t]) -> $t {
+                if indices.len() != values.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                let n = dense.len() as usize;
+                if let Some(&i) = indices.iter().find(|&&i| i >= n) {
+                    panic!("Index {} out of range for length {}", i, n);
+                }
+
+                let dense = unsafe { Strided::new(dense.as_ptr(), dense.len(), dense.inc()) };
+                indices
+                    .iter()
+                    .zip(values)
+                    .fold(Default::zero(), |acc: $t, (&i, &v)| acc + dense.get(i as u32) * v)
+            }
+        }
+    )+
+));
+
+dot_sparse_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod csr_gemv_tests {
+    use crate::attribute::Transpose;
+    use crate::math::sparse::{CsrGemv, CsrMatrix};
+    use crate::math::Mat;
+    use crate::matrix_vector::ops::Gemv;
+    use num_complex::Complex;
+
+    #[test]
+    fn tridiagonal() {
+        let a = CsrMatrix::new(
+            4,
+            4,
+            vec![0, 2, 5, 8, 10],
+            vec![0, 1, 0, 1, 2, 1, 2, 3, 2, 3],
+            vec![2f64, -1.0, -1.0, 2.0, -1.0, -1.0, 2.0, -1.0, -1.0, 2.0],
+        );
+        let dense = mat![2f64, -1.0, 0.0, 0.0;
+                         -1.0, 2.0, -1.0, 0.0;
+                         0.0, -1.0, 2.0, -1.0;
+                         0.0, 0.0, -1.0, 2.0];
+        let x = vec![1f64, 2.0, 4.0, 8.0];
+
+        let mut expected = vec![0f64; 4];
+        Gemv::gemv(Transpose::NoTrans, &1.0, &dense, &x, &0.0, &mut expected);
+
+        let mut y = vec![5f64; 4];
+        CsrGemv::csr_gemv(&a, &x, &mut y);
+        assert_eq!(a.nnz(), 10);
+        assert_eq!(y, expected);
+    }
+
+    #[test]
+    fn empty_row() {
+        let a = CsrMatrix::new(2, 3, vec![0, 0, 1], vec![2], vec![Complex::new(0f32, 1.0)]);
+        let x = vec![Complex::new(1f32, 0.0); 3];
+
+        let mut y = vec![Complex::new(7f32, 7.0); 2];
+        CsrGemv::csr_gemv(&a, &x, &mut y);
+        assert_eq!(y, vec![Complex::new(0.0, 0.0), Complex::new(0.0, 1.0)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bad_row_ptr() {
+        let _ = CsrMatrix::new(2, 2, vec![0, 2, 1], vec![0, 1], vec![1f32, 1.0]);
+    }
+}
+
+#[cfg(test)]
+mod dot_sparse_tests {
+    use crate::math::sparse::DotSparse;
+    use crate::vector::ops::Dot;
+
+    #[test]
+    fn real() {
+        let dense = vec![1f32, -2.0, 3.0, 4.0, 5.0];
+        let sparse = vec![0f32, 0.5, 0.0, 0.0, 2.0];
+
+        let expected = Dot::dot(&dense, &sparse);
+        assert_eq!(
+            DotSparse::dot_sparse(&dense, &[1, 4], &[0.5, 2.0]),
+            expected
+        );
+        assert_eq!(expected, 9.0);
+    }
+
+    #[test]
+    fn empty() {
+        let dense = vec![1f64, 2.0];
+
+        assert_eq!(DotSparse::dot_sparse(&dense, &[], &[]), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_range() {
+        let dense = vec![1f64, 2.0];
+
+        let _ = DotSparse::dot_sparse(&dense, &[2], &[1.0]);
+    }
+}
@@ -0,0 +1,783 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Summary statistics and orderings of vectors.
+
+use crate::matrix::{col, col_mut, row_mut, Matrix};
+use crate::vector::ops::{Axpy, Copy, Dot, Nrm2, Scal};
+use crate::vector::{Strided, Vector};
+use num::traits::Float;
+use num_complex::{Complex32, Complex64};
+use std::cmp::Ordering;
+
+/// Returns the permutation that sorts `x` by magnitude.
+///
+/// Element `k` of the result is the index of the `k`th smallest (or, with
+/// `descending`, largest) element of `x`. Equal magnitudes keep their
+/// original order. Complex elements are compared by their modulus.
+pub trait ArgsortByMagnitude: Sized {
+    fn argsort_by_magnitude<V: ?Sized + Vector<Self>>(x: &V, descending: bool) -> Vec<usize>;
+}
+
+macro_rules! argsort_by_magnitude_impl(($magnitude: ident, $($t: ident), +) => (
+    $(
+        impl ArgsortByMagnitude for $t {
+            fn argsort_by_magnitude<V: ?Sized + Vector<Self>>(x: &V, descending: bool) -> Vec<usize> {
+                let x = unsafe { Strided::new(x.as_ptr(), x.len(), x.inc()) };
+                let magnitudes: Vec<_> = (0..x.len()).map(|i| x.get(i).$magnitude()).collect();
+
+                let mut perm: Vec<usize> = (0..magnitudes.len()).collect();
+                perm.sort_by(|&i, &j| {
+                    let ord = magnitudes[i].partial_cmp(&magnitudes[j]).unwrap_or(Ordering::Equal);
+                    if descending { ord.reverse() } else { ord }
+                });
+
+                perm
+            }
+        }
+    )+
+));
+
+argsort_by_magnitude_impl!(abs, f32, f64);
+argsort_by_magnitude_impl!(norm, Complex32, Complex64);
+
+/// Moments of the elements of a vector.
+///
+/// The variance is the population variance, dividing by `n` rather than
+/// `n - 1`. All of these are `NaN` for an empty vector.
+pub trait Stats: Sized {
+    /// The arithmetic mean.
+    fn mean<V: ?Sized + Vector<Self>>(x: &V) -> Self;
+    /// The mean squared deviation from the mean.
+    fn variance<V: ?Sized + Vector<Self>>(x: &V) -> Self;
+    /// The square root of the variance.
+    fn std_dev<V: ?Sized + Vector<Self>>(x: &V) -> Self;
+}
+
+macro_rules! stats_impl(($($t: ident), +) => (
+    $(
+        impl Stats for $t {
+            fn mean<V: ?Sized + Vector<Self>>(x: &V) -> $t {
+                let ones = vec![1.0; x.len() as usize];
+                Dot::dot(x, &ones) / x.len() as $t
+            }
+
+            fn variance<V: ?Sized + Vector<Self>>(x: &V) -> $t {
+                let n = x.len() as usize;
+                let ones = vec![1.0; n];
+                let mut centered = vec![0.0; n];
+                Copy::copy(x, &mut centered);
+                Axpy::axpy(&-Stats::mean(x), &ones, &mut centered);
+
+                let norm: $t = Nrm2::nrm2(&centered);
+                norm * norm / n as $t
+            }
+
+            fn std_dev<V: ?Sized + Vector<Self>>(x: &V) -> $t {
+                <$t as Stats>::variance(x).sqrt()
+            }
+        }
+    )+
+));
+
+stats_impl!(f32, f64);
+
+/// Shifts and scales `x` in place to zero mean and unit variance.
+///
+/// A constant vector has no spread to scale by and comes out all zeros. That
+/// includes a spread of no more than rounding error relative to the mean.
+pub trait Standardize: Sized {
+    fn standardize<V: ?Sized + Vector<Self>>(x: &mut V);
+}
+
+macro_rules! standardize_impl(($($t: ident), +) => (
+    $(
+        impl Standardize for $t {
+            fn standardize<V: ?Sized + Vector<Self>>(x: &mut V) {
+                if x.is_empty() {
+                    return;
+                }
+
+                let mean: $t = Stats::mean(x);
+                let std_dev: $t = Stats::std_dev(x);
+
+                // A constant vector whose value is not exactly representable
+                // still has a rounding-level spread, which must not be
+                // blown up to unit variance.
+                let v = unsafe { Strided::new(x.as_ptr(), x.len(), x.inc()) };
+                let constant = std_dev <= <$t>::EPSILON * mean.abs() || (1..v.len()).all(|i| v.get(i) == v.get(0));
+
+                let ones = vec![1.0; x.len() as usize];
+                Axpy::axpy(&-mean, &ones, x);
+                let scale = if constant { 0.0 } else { std_dev.recip() };
+                Scal::scal(&scale, x);
+            }
+        }
+    )+
+));
+
+standardize_impl!(f32, f64);
+
+/// Subtracts from every column of `a` its mean, in place, and returns the
+/// means that were removed.
+///
+/// Each mean comes from `Stats::mean` of the column and is taken off with a
+/// strided `axpy`. Adding the returned means back undoes the centering.
+pub trait CenterColumns: Sized {
+    fn center_columns(a: &mut dyn Matrix<Self>) -> Vec<Self>;
+}
+
+macro_rules! center_columns_impl(($($t: ident), +) => (
+    $(
+        impl CenterColumns for $t {
+            fn center_columns(a: &mut dyn Matrix<$t>) -> Vec<$t> {
+                let ones = vec![1.0; a.rows() as usize];
+                (0..a.cols() as usize)
+                    .map(|j| {
+                        let mean: $t = Stats::mean(&col(a, j));
+                        Axpy::axpy(&-mean, &ones, &mut col_mut(a, j));
+                        mean
+                    })
+                    .collect()
+            }
+        }
+    )+
+));
+
+center_columns_impl!(f32, f64);
+
+/// Rescales every column of `a`, in place, to span `feature_range`.
+///
+/// With `(lo, hi) = feature_range`, column `j` becomes
+/// `lo + scale_j * (a_j - min_j)` where `scale_j = (hi - lo) / (max_j - min_j)`,
+/// applied as an `axpy` with a vector of ones, a `scal` and another `axpy`.
+/// Returns the `min_j` and `scale_j` of every column, so that
+/// `min_j + (y - lo) / scale_j` undoes the transform. A constant column has
+/// no range to stretch; it is mapped to `lo` and gets a scale of one, so the
+/// inverse still recovers it.
+pub trait MinMaxScale: Sized {
+    fn min_max_scale(
+        a: &mut dyn Matrix<Self>,
+        feature_range: (Self, Self),
+    ) -> (Vec<Self>, Vec<Self>);
+}
+
+macro_rules! min_max_scale_impl(($($t: ident), +) => (
+    $(
+        impl MinMaxScale for $t {
+            fn min_max_scale(a: &mut dyn Matrix<$t>, feature_range: ($t, $t)) -> (Vec<$t>, Vec<$t>) {
+                let (lo, hi) = feature_range;
+                if a.rows() == 0 {
+                    return (vec![0.0; a.cols() as usize], vec![1.0; a.cols() as usize]);
+                }
+
+                let ones = vec![1.0; a.rows() as usize];
+                let mut mins = Vec::with_capacity(a.cols() as usize);
+                let mut scales = Vec::with_capacity(a.cols() as usize);
+
+                for j in 0..a.cols() as usize {
+                    let c = col(a, j);
+                    let (min, max) = (0..c.len())
+                        .map(|i| c.get(i))
+                        .fold((<$t>::INFINITY, <$t>::NEG_INFINITY), |(mn, mx), v| (mn.min(v), mx.max(v)));
+                    let scale = if max > min { (hi - lo) / (max - min) } else { 1.0 };
+
+                    let mut c = col_mut(a, j);
+                    Axpy::axpy(&-min, &ones, &mut c);
+                    Scal::scal(&scale, &mut c);
+                    Axpy::axpy(&lo, &ones, &mut c);
+                    mins.push(min);
+                    scales.push(scale);
+                }
+
+                (mins, scales)
+            }
+        }
+    )+
+));
+
+min_max_scale_impl!(f32, f64);
+
+/// Medians and other quantiles of the elements of a vector.
+///
+/// The elements are copied out, so `x` is left as it is, and the needed
+/// order statistics are found by selection in expected linear time rather
+/// than by sorting. Quantiles between two elements are interpolated
+/// linearly, so the median of an even number of elements is the mean of the
+/// middle two. An empty vector gives NaN. Elements are ordered by
+/// `total_cmp`, so NaNs sort above infinity (or below negative infinity when
+/// negative) instead of making the selection arbitrary.
+pub trait Quantile: Sized {
+    fn median<V: ?Sized + Vector<Self>>(x: &V) -> Self;
+    /// Panics unless `0 <= q <= 1`.
+    fn quantile<V: ?Sized + Vector<Self>>(x: &V, q: f64) -> Self;
+}
+
+macro_rules! quantile_impl(($($t: ident), +) => (
+    $(
+        impl Quantile for $t {
+            fn median<V: ?Sized + Vector<Self>>(x: &V) -> $t {
+                Quantile::quantile(x, 0.5)
+            }
+
+            fn quantile<V: ?Sized + Vector<Self>>(x: &V, q: f64) -> $t {
+                if !(0.0..=1.0).contains(&q) {
+                    panic!("Quantile {} out of range [0, 1]", q);
+                }
+
+                let n = x.len() as usize;
+                if n == 0 {
+                    return <$t>::NAN;
+                }
+                let mut values = vec![0.0; n];
+                Copy::copy(x, &mut values);
+
+                let cmp = |a: &$t, b: &$t| a.total_cmp(b);
+                let pos = q * (n - 1) as f64;
+                let lo = pos.floor() as usize;
+                let (_, &mut below, above) = values.select_nth_unstable_by(lo, cmp);
+                let frac = (pos - lo as f64) as $t;
+                if frac == 0.0 {
+                    return below;
+                }
+
+                // The next order statistic is the smallest of the upper part.
+                let next = above.iter().copied().min_by(cmp).unwrap_or(below);
+                below + frac * (next - below)
+            }
+        }
+    )+
+));
+
+quantile_impl!(f32, f64);
+
+/// Reweights a regression problem so that recent observations count more.
+///
+/// Row `i` of `rows` and entry `i` of `target` are both scaled by `sqrt(w_i)`,
+/// where `w_i = 0.5^((n - 1 - i) / half_life)`: the last row keeps weight one
+/// and every `half_life` rows further back halve it. Ordinary least squares
+/// on the result then solves the weighted problem. Each row is scaled with a
+/// strided `scal`, so any order or leading dimension works.
+///
+/// Panics if `target` does not have one entry per row.
+pub trait ExponentialWeights: Sized {
+    fn apply_exponential_weights<V: ?Sized + Vector<Self>>(
+        rows: &mut dyn Matrix<Self>,
+        target: &mut V,
+        half_life: Self,
+    );
+}
+
+macro_rules! exponential_weights_impl(($($t: ident), +) => (
+    $(
+        impl ExponentialWeights for $t {
+            fn apply_exponential_weights<V: ?Sized + Vector<Self>>(
+                rows: &mut dyn Matrix<$t>,
+                target: &mut V,
+                half_life: $t,
+            ) {
+                let n = rows.rows();
+                if target.len() != n {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut y = unsafe { Strided::new(target.as_mut_ptr(), n, target.inc()) };
+                for i in 0..n {
+                    let age = (n - 1 - i) as $t;
+                    let scale = (0.5 as $t).powf(age / half_life).sqrt();
+                    Scal::scal(&scale, &mut row_mut(rows, i as usize));
+                    y.set(i, y.get(i) * scale);
+                }
+            }
+        }
+    )+
+));
+
+exponential_weights_impl!(f32, f64);
+
+/// The forward pass of batch normalization over the rows of `a`, in place.
+///
+/// Every feature column is shifted and scaled to zero mean and unit
+/// variance using its `Stats::mean` and `Stats::variance`, with `eps` added
+/// to the variance before the square root, and then mapped to
+/// `gamma_j * a_j + beta_j`. The mean is taken off before scaling, so
+/// features with a large offset keep their precision. Returns the batch
+/// means and variances, which the backward pass and running averages need.
+///
+/// Panics unless `gamma` and `beta` have one entry per column.
+pub trait BatchNorm: Sized {
+    fn batch_norm<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        a: &mut dyn Matrix<Self>,
+        gamma: &V,
+        beta: &W,
+        eps: Self,
+    ) -> (Vec<Self>, Vec<Self>);
+}
+
+macro_rules! batch_norm_impl(($($t: ident), +) => (
+    $(
+        impl BatchNorm for $t {
+            fn batch_norm<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+                a: &mut dyn Matrix<$t>,
+                gamma: &V,
+                beta: &W,
+                eps: $t,
+            ) -> (Vec<$t>, Vec<$t>) {
+                let n = a.cols();
+                if gamma.len() != n || beta.len() != n {
+                    panic!("Dimension mismatch");
+                }
+
+                let gamma = unsafe { Strided::new(gamma.as_ptr(), n, gamma.inc()) };
+                let beta = unsafe { Strided::new(beta.as_ptr(), n, beta.inc()) };
+                let ones = vec![1.0; a.rows() as usize];
+                let mut means = Vec::with_capacity(n as usize);
+                let mut variances = Vec::with_capacity(n as usize);
+                for j in 0..n {
+                    let mean: $t = Stats::mean(&col(a, j as usize));
+                    let variance: $t = Stats::variance(&col(a, j as usize));
+
+                    let mut x = col_mut(a, j as usize);
+                    Axpy::axpy(&-mean, &ones, &mut x);
+                    Scal::scal(&(gamma.get(j) / (variance + eps).sqrt()), &mut x);
+                    Axpy::axpy(&beta.get(j), &ones, &mut x);
+
+                    means.push(mean);
+                    variances.push(variance);
+                }
+                (means, variances)
+            }
+        }
+    )+
+));
+
+batch_norm_impl!(f32, f64);
+
+/// Per-element mean and variance of a stream of equally long vectors.
+///
+/// Uses Welford's update, so the variance stays accurate when the spread is
+/// small next to the mean. The shifts are done with `axpy`; only the final
+/// product of the two deviations is elementwise.
+pub struct RunningStats<T> {
+    count: usize,
+    mean: Vec<T>,
+    m2: Vec<T>,
+}
+
+impl<T: Float + Axpy + Copy + Scal> RunningStats<T> {
+    /// Starts an empty accumulator for vectors of length `len`.
+    pub fn new(len: usize) -> RunningStats<T> {
+        RunningStats {
+            count: 0,
+            mean: vec![T::zero(); len],
+            m2: vec![T::zero(); len],
+        }
+    }
+
+    /// Folds another sample into the statistics.
+    pub fn update<V: ?Sized + Vector<T>>(&mut self, x: &V) {
+        if x.len() as usize != self.mean.len() {
+            panic!("Dimension mismatch");
+        }
+
+        self.count += 1;
+        let mut before = vec![T::zero(); self.mean.len()];
+        Copy::copy(x, &mut before);
+        Axpy::axpy(&-T::one(), &self.mean, &mut before);
+        Axpy::axpy(
+            &T::from(self.count).unwrap().recip(),
+            &before,
+            &mut self.mean,
+        );
+
+        let mut after = vec![T::zero(); self.mean.len()];
+        Copy::copy(x, &mut after);
+        Axpy::axpy(&-T::one(), &self.mean, &mut after);
+        for ((m2, b), a) in self.m2.iter_mut().zip(&before).zip(&after) {
+            *m2 = *m2 + *b * *a;
+        }
+    }
+
+    /// The number of samples seen so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The mean of every element; zeros before the first update.
+    pub fn mean(&self) -> Vec<T> {
+        self.mean.clone()
+    }
+
+    /// The population variance of every element; NaN before the first
+    /// update.
+    pub fn variance(&self) -> Vec<T> {
+        let mut var = self.m2.clone();
+        Scal::scal(&T::from(self.count).unwrap().recip(), &mut var);
+        var
+    }
+}
+
+#[cfg(test)]
+mod argsort_by_magnitude_tests {
+    use crate::math::stats::ArgsortByMagnitude;
+    use crate::vector::Strided;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let x = vec![3f32, -1.0, 2.0];
+
+        assert_eq!(
+            ArgsortByMagnitude::argsort_by_magnitude(&x, true),
+            vec![0, 2, 1]
+        );
+        assert_eq!(
+            ArgsortByMagnitude::argsort_by_magnitude(&x, false),
+            vec![1, 2, 0]
+        );
+    }
+
+    #[test]
+    fn strided() {
+        let x = [-4f64, 9.0, 1.0, 9.0, -2.0];
+        let every_other = unsafe { Strided::new(x.as_ptr(), 3, 2) };
+
+        let perm = ArgsortByMagnitude::argsort_by_magnitude(&every_other, false);
+        assert_eq!(perm, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn complex() {
+        let x = vec![
+            Complex::new(3f64, 4.0),
+            Complex::new(0.0, -1.0),
+            Complex::new(-5.0, 0.0),
+        ];
+
+        let perm = ArgsortByMagnitude::argsort_by_magnitude(&x, true);
+        assert_eq!(perm, vec![0, 2, 1]);
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use crate::math::stats::Stats;
+
+    #[test]
+    fn real() {
+        let x = vec![2f64, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+
+        assert_eq!(Stats::mean(&x), 5.0);
+        assert!((<f64 as Stats>::variance(&x) - 4.0).abs() < 1e-12);
+        assert!((<f64 as Stats>::std_dev(&x) - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn empty() {
+        let x: Vec<f32> = vec![];
+
+        assert!(<f32 as Stats>::mean(&x).is_nan());
+    }
+}
+
+#[cfg(test)]
+mod standardize_tests {
+    use crate::math::stats::{Standardize, Stats};
+
+    #[test]
+    fn real() {
+        let mut x = vec![1f64, 2.0, 3.0, 4.0, 5.0];
+
+        Standardize::standardize(&mut x);
+        assert!(<f64 as Stats>::mean(&x).abs() < 1e-12);
+        assert!((<f64 as Stats>::variance(&x) - 1.0).abs() < 1e-12);
+        assert!((x[4] - 2f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn constant() {
+        let mut x = vec![3f32; 4];
+
+        Standardize::standardize(&mut x);
+        assert_eq!(x, vec![0.0; 4]);
+    }
+
+    #[test]
+    fn inexact_constant() {
+        let mut x = vec![0.1f64; 3];
+
+        Standardize::standardize(&mut x);
+        assert_eq!(x, vec![0.0; 3]);
+    }
+}
+
+#[cfg(test)]
+mod center_columns_tests {
+    use crate::math::stats::{CenterColumns, Stats};
+    use crate::math::Mat;
+    use crate::matrix::col;
+
+    #[test]
+    fn real() {
+        let mut a = mat![1f64, 10.0; 2.0, -4.0; 6.0, 3.0];
+
+        let means = CenterColumns::center_columns(&mut a);
+        assert_eq!(means, vec![3.0, 3.0]);
+        assert_eq!(a, mat![-2.0, 7.0; -1.0, -7.0; 3.0, 0.0]);
+        for j in 0..2 {
+            assert!(<f64 as Stats>::mean(&col(&a, j)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn no_rows() {
+        let mut a = Mat::fill(0f32, 0, 2);
+
+        let means = CenterColumns::center_columns(&mut a);
+        assert_eq!(means.len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod min_max_scale_tests {
+    use crate::math::stats::MinMaxScale;
+    use crate::math::Mat;
+    use crate::matrix::tests::Cm;
+
+    #[test]
+    fn unit_range() {
+        let mut a = Mat::new_from_data(3, 2, vec![2f64, -1.0, 6.0, 3.0, 4.0, 1.0]);
+
+        let (mins, scales) = MinMaxScale::min_max_scale(&mut a, (0.0, 1.0));
+        assert_eq!(mins, vec![2.0, -1.0]);
+        assert_eq!(scales, vec![0.25, 0.25]);
+        assert_eq!(a[0], [0.0, 0.0]);
+        assert_eq!(a[1], [1.0, 1.0]);
+        assert_eq!(a[2], [0.5, 0.5]);
+    }
+
+    #[test]
+    fn inverse() {
+        let original = vec![3f32, -2.0, 0.5, 7.0, 7.0, 7.0];
+        let mut a = Cm(3, 2, original.clone());
+
+        let (mins, scales) = MinMaxScale::min_max_scale(&mut a, (-1.0, 1.0));
+        // The second column is constant, so it lands on the lower bound.
+        assert_eq!(&a.2[3..], [-1.0, -1.0, -1.0]);
+        assert_eq!(scales[1], 1.0);
+
+        for (i, (scaled, x)) in a.2.iter().zip(&original).enumerate() {
+            let back = mins[i / 3] + (scaled + 1.0) / scales[i / 3];
+            assert!((back - x).abs() < 1e-6);
+        }
+    }
+}
+
+#[cfg(test)]
+mod quantile_tests {
+    use crate::math::stats::Quantile;
+    use crate::vector::Strided;
+
+    #[test]
+    fn median() {
+        let x = vec![3f64, 1.0, 2.0];
+
+        assert_eq!(Quantile::median(&x), 2.0);
+        assert_eq!(x, vec![3.0, 1.0, 2.0]);
+        assert_eq!(Quantile::median(&vec![4f32, 1.0, 3.0, 2.0]), 2.5);
+    }
+
+    #[test]
+    fn quantile() {
+        let x = vec![40f64, 10.0, 30.0, 20.0];
+
+        assert_eq!(Quantile::quantile(&x, 0.0), 10.0);
+        assert_eq!(Quantile::quantile(&x, 0.25), 17.5);
+        assert_eq!(Quantile::quantile(&x, 1.0), 40.0);
+    }
+
+    #[test]
+    fn strided() {
+        let x = [5f32, 0.0, 1.0, 0.0, 9.0, 0.0, 3.0];
+        let evens = unsafe { Strided::new(x.as_ptr(), 4, 2) };
+
+        assert_eq!(Quantile::median(&evens), 4.0);
+    }
+
+    #[test]
+    fn empty() {
+        let x: Vec<f64> = vec![];
+
+        assert!(<f64 as Quantile>::median(&x).is_nan());
+    }
+
+    #[test]
+    fn nan_sorts_last() {
+        let x = vec![3f64, f64::NAN, 1.0, 2.0];
+
+        assert_eq!(Quantile::median(&x), 2.5);
+        assert_eq!(Quantile::quantile(&x, 0.0), 1.0);
+        assert!(<f64 as Quantile>::quantile(&x, 1.0).is_nan());
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_range() {
+        let _ = <f32 as Quantile>::quantile(&vec![1f32], 1.5);
+    }
+}
+
+#[cfg(test)]
+mod running_stats_tests {
+    use crate::math::stats::{RunningStats, Stats};
+    use crate::vector::Strided;
+
+    #[test]
+    fn matches_batch() {
+        let samples = [
+            [1f64, 10.0, -3.0],
+            [2.0, 10.5, 4.0],
+            [4.0, 9.5, 0.0],
+            [7.0, 10.0, 1.5],
+        ];
+
+        let mut stats = RunningStats::new(3);
+        for s in &samples {
+            stats.update(&s[..]);
+        }
+        assert_eq!(stats.count(), 4);
+
+        let (mean, var) = (stats.mean(), stats.variance());
+        for j in 0..3 {
+            let column = unsafe { Strided::new(samples[0].as_ptr().add(j), 4, 3) };
+            assert!((mean[j] - <f64 as Stats>::mean(&column)).abs() < 1e-12);
+            assert!((var[j] - <f64 as Stats>::variance(&column)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn large_offset() {
+        let mut stats = RunningStats::new(1);
+        for x in &[1e9f64 + 1.0, 1e9 - 1.0, 1e9 + 1.0, 1e9 - 1.0] {
+            stats.update(&vec![*x]);
+        }
+
+        assert!((stats.variance()[0] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_length() {
+        let mut stats = RunningStats::new(2);
+        stats.update(&vec![1f64]);
+    }
+}
+
+#[cfg(test)]
+mod exponential_weights_tests {
+    use crate::math::stats::ExponentialWeights;
+    use crate::math::Mat;
+    use crate::matrix::tests::Cm;
+
+    #[test]
+    fn recent_rows_weigh_more() {
+        let mut a: Mat<f64> = Mat::fill(1.0, 5, 2);
+        let mut y = vec![1.0; 5];
+
+        ExponentialWeights::apply_exponential_weights(&mut a, &mut y, 2.0);
+
+        assert_eq!(a[4], [1.0, 1.0]);
+        assert!((a[2][0] - 0.5f64.sqrt()).abs() < 1e-12);
+        assert!((a[0][1] - 0.5).abs() < 1e-12);
+        for i in 1..5 {
+            assert!(a[i][0] > a[i - 1][0]);
+        }
+    }
+
+    #[test]
+    fn rows_and_target_match() {
+        let mut a = Cm(3, 2, vec![1f32, 2.0, 3.0, -1.0, -2.0, -3.0]);
+        let mut y = vec![4f32, 5.0, 6.0];
+
+        ExponentialWeights::apply_exponential_weights(&mut a, &mut y, 1.5);
+
+        for (i, &yi) in y.iter().enumerate() {
+            let scale = yi / (4.0 + i as f32);
+            assert!((a.2[i] - (1.0 + i as f32) * scale).abs() < 1e-6);
+            assert!((a.2[i + 3] + (1.0 + i as f32) * scale).abs() < 1e-6);
+        }
+        assert_eq!(y[2], 6.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn target_too_short() {
+        let mut a: Mat<f32> = Mat::fill(1.0, 3, 2);
+        let mut y = vec![1f32; 2];
+
+        ExponentialWeights::apply_exponential_weights(&mut a, &mut y, 1.0);
+    }
+}
+
+#[cfg(test)]
+mod batch_norm_tests {
+    use crate::math::stats::{BatchNorm, Stats};
+    use crate::math::Mat;
+    use crate::matrix::col;
+
+    #[test]
+    fn normalized() {
+        let mut a = mat![1f64, 10.0; 2.0, -4.0; 6.0, 3.0; 3.0, 7.0];
+
+        let (means, variances) = BatchNorm::batch_norm(&mut a, &vec![1.0; 2], &vec![0.0; 2], 1e-12);
+        assert_eq!(means, vec![3.0, 4.0]);
+        assert!((variances[0] - 3.5).abs() < 1e-12);
+        assert!((variances[1] - 27.5).abs() < 1e-12);
+        for j in 0..2 {
+            assert!(<f64 as Stats>::mean(&col(&a, j)).abs() < 1e-12);
+            assert!((<f64 as Stats>::variance(&col(&a, j)) - 1.0).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn scale_and_shift() {
+        let mut a = mat![0f32, 5.0; 2.0, 5.0; 4.0, 5.0];
+
+        let (means, variances) =
+            BatchNorm::batch_norm(&mut a, &vec![3.0, 2.0], &vec![-1.0, 0.5], 1e-5);
+        assert_eq!(means, vec![2.0, 5.0]);
+        assert_eq!(variances[1], 0.0);
+        assert!((<f32 as Stats>::mean(&col(&a, 0)) + 1.0).abs() < 1e-6);
+        assert!((<f32 as Stats>::std_dev(&col(&a, 0)) - 3.0).abs() < 1e-4);
+        // A constant feature only gets the shift.
+        for i in 0..3 {
+            assert_eq!(a[i][1], 0.5);
+        }
+    }
+
+    #[test]
+    fn large_offset() {
+        let values = [9998.5f64, 9999.5, 10000.0, 10001.0, 10002.25];
+        let mean = values.iter().sum::<f64>() / 5.0;
+        let std_dev = (values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / 5.0).sqrt();
+
+        let mut a = Mat::new_from_data(5, 1, values.iter().map(|&v| v as f32).collect());
+        let _ = BatchNorm::batch_norm(&mut a, &vec![1.0], &vec![0.0], 0.0);
+        for (i, v) in values.iter().enumerate() {
+            assert!(
+                (a[i][0] as f64 - (v - mean) / std_dev).abs() < 1e-5,
+                "{}",
+                a
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn gamma_too_short() {
+        let mut a: Mat<f64> = Mat::fill(1.0, 3, 2);
+
+        let _ = BatchNorm::batch_norm(&mut a, &vec![1.0], &vec![0.0; 2], 1e-5);
+    }
+}
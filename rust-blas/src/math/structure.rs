@@ -0,0 +1,845 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Constructing, reshaping and rearranging matrices.
+
+use crate::attribute::Order;
+use crate::default::Default;
+use crate::math::Mat;
+use crate::matrix::{col, col_mut, row, row_mut, swap_cols, Matrix};
+use crate::vector::ops::{Copy, Dot};
+use crate::vector::{Strided, Vector};
+use num_complex::{Complex32, Complex64};
+
+/// Replaces every entry `a_ij` of the matrix with `f(a_ij)`.
+///
+/// Only the `rows x cols` entries of `a` are visited, so padding between
+/// rows of a view with a larger leading dimension is left alone. Entries
+/// are visited one row at a time, left to right.
+pub trait ApplyElementwise: Sized {
+    fn apply_elementwise<F: FnMut(Self) -> Self>(a: &mut dyn Matrix<Self>, f: F);
+}
+
+macro_rules! apply_elementwise_impl(($($t: ident), +) => (
+    $(
+        impl ApplyElementwise for $t {
+            fn apply_elementwise<F: FnMut($t) -> $t>(a: &mut dyn Matrix<$t>, mut f: F) {
+                for i in 0..a.rows() as usize {
+                    let mut r = row_mut(a, i);
+                    for j in 0..a.cols() {
+                        let v = r.get(j);
+                        r.set(j, f(v));
+                    }
+                }
+            }
+        }
+    )+
+));
+
+apply_elementwise_impl!(f32, f64, Complex32, Complex64);
+
+/// Returns the dot product of every row of `a` with the same row of `b`.
+///
+/// Complex rows are multiplied without conjugation, as in `Dot::dot`.
+pub trait RowDots: Sized {
+    fn row_dots(a: &dyn Matrix<Self>, b: &dyn Matrix<Self>) -> Vec<Self>;
+}
+
+macro_rules! row_dots_impl(($($t: ident), +) => (
+    $(
+        impl RowDots for $t {
+            fn row_dots(a: &dyn Matrix<$t>, b: &dyn Matrix<$t>) -> Vec<$t> {
+                if a.rows() != b.rows() || a.cols() != b.cols() {
+                    panic!("Dimension mismatch");
+                }
+
+                (0..a.rows() as usize)
+                    .map(|i| Dot::dot(&row(a, i), &row(b, i)))
+                    .collect()
+            }
+        }
+    )+
+));
+
+row_dots_impl!(f32, f64, Complex32, Complex64);
+
+/// Writes the elementwise product of `a` and `b` to `out`.
+///
+/// Entries are matched by their logical position, so the three matrices may
+/// each have their own order and leading dimension.
+pub trait HadamardMat: Sized {
+    fn hadamard_mat(a: &dyn Matrix<Self>, b: &dyn Matrix<Self>, out: &mut dyn Matrix<Self>);
+}
+
+macro_rules! hadamard_mat_impl(($($t: ident), +) => (
+    $(
+        impl HadamardMat for $t {
+            fn hadamard_mat(a: &dyn Matrix<$t>, b: &dyn Matrix<$t>, out: &mut dyn Matrix<$t>) {
+                if a.rows() != b.rows() || a.cols() != b.cols() || out.rows() != a.rows() || out.cols() != a.cols() {
+                    panic!("Dimension mismatch");
+                }
+
+                for i in 0..a.rows() as usize {
+                    let (x, y) = (row(a, i), row(b, i));
+                    let mut z = row_mut(out, i);
+                    for j in 0..a.cols() {
+                        z.set(j, x.get(j) * y.get(j));
+                    }
+                }
+            }
+        }
+    )+
+));
+
+hadamard_mat_impl!(f32, f64, Complex32, Complex64);
+
+/// Assembles a block-diagonal matrix from `blocks`.
+///
+/// The blocks are placed corner to corner down the diagonal in the order
+/// given, each copied in row by row, and every entry outside them is zero.
+/// Blocks need not be square.
+pub trait BlockDiagonal: Sized {
+    fn block_diagonal(blocks: &[&dyn Matrix<Self>]) -> Mat<Self>;
+}
+
+macro_rules! block_diagonal_impl(($($t: ident), +) => (
+    $(
+        impl BlockDiagonal for $t {
+            fn block_diagonal(blocks: &[&dyn Matrix<$t>]) -> Mat<$t> {
+                let rows = blocks.iter().map(|b| b.rows() as usize).sum();
+                let cols = blocks.iter().map(|b| b.cols() as usize).sum();
+                let mut result = Mat::fill(Default::zero(), rows, cols);
+
+                let (mut r0, mut c0) = (0, 0);
+                for b in blocks {
+                    for i in 0..b.rows() as usize {
+                        let mut dst = row_mut(&mut result, r0 + i).skip(c0).take(b.cols());
+                        Copy::copy(&row(*b, i), &mut dst);
+                    }
+                    r0 += b.rows() as usize;
+                    c0 += b.cols();
+                }
+
+                result
+            }
+        }
+    )+
+));
+
+block_diagonal_impl!(f32, f64, Complex32, Complex64);
+
+/// Transposes every matrix of `src` into the matching entry of `dst`.
+///
+/// Each transpose copies rows of the source into columns of the destination
+/// in bands of a few columns at a time, so the destination rows being
+/// written stay in cache while the source is walked row by row.
+pub trait TransposeBatched: Sized {
+    fn transpose_batched(src: &[&dyn Matrix<Self>], dst: &mut [&mut Mat<Self>]);
+}
+
+/// Width of the column bands copied at once by `transpose_batched`.
+const TRANSPOSE_BLOCK: u32 = 32;
+
+macro_rules! transpose_batched_impl(($($t: ident), +) => (
+    $(
+        impl TransposeBatched for $t {
+            fn transpose_batched(src: &[&dyn Matrix<$t>], dst: &mut [&mut Mat<$t>]) {
+                if src.len() != dst.len() {
+                    panic!("Dimension mismatch");
+                }
+
+                for (a, b) in src.iter().zip(dst.iter_mut()) {
+                    if b.rows() != a.cols() as usize || b.cols() != a.rows() as usize {
+                        panic!("Dimension mismatch");
+                    }
+
+                    for j0 in (0..a.cols()).step_by(TRANSPOSE_BLOCK as usize) {
+                        let width = TRANSPOSE_BLOCK.min(a.cols() - j0);
+                        for i in 0..a.rows() as usize {
+                            let band = row(*a, i).skip(j0).take(width);
+                            Copy::copy(&band, &mut col_mut(&mut **b, i).skip(j0).take(width));
+                        }
+                    }
+                }
+            }
+        }
+    )+
+));
+
+transpose_batched_impl!(f32, f64, Complex32, Complex64);
+
+/// Reorders the columns of `a` in place so that column `j` afterwards is
+/// the column `perm[j]` was before.
+///
+/// Each cycle of the permutation is applied with one column `swap` per
+/// element, so no extra storage is needed. Panics unless `perm` holds every
+/// index in `0..cols` exactly once.
+pub trait PermuteCols: Sized {
+    fn permute_cols(a: &mut dyn Matrix<Self>, perm: &[usize]);
+}
+
+macro_rules! permute_cols_impl(($($t: ident), +) => (
+    $(
+        impl PermuteCols for $t {
+            fn permute_cols(a: &mut dyn Matrix<$t>, perm: &[usize]) {
+                let n = a.cols() as usize;
+                let mut seen = vec![false; n];
+                if perm.len() != n || !perm.iter().all(|&p| p < n && !std::mem::replace(&mut seen[p], true)) {
+                    panic!("Invalid permutation of {} columns", n);
+                }
+
+                let mut done = vec![false; n];
+                for start in 0..n {
+                    let mut j = start;
+                    while !done[j] {
+                        done[j] = true;
+                        if perm[j] == start {
+                            break;
+                        }
+                        swap_cols(a, j, perm[j]);
+                        j = perm[j];
+                    }
+                }
+            }
+        }
+    )+
+));
+
+permute_cols_impl!(f32, f64, Complex32, Complex64);
+
+/// Copies of a matrix with one row or one column left out.
+///
+/// Every remaining row (or column) is moved over with a single strided
+/// `copy`. Panics if the index is out of range.
+pub trait DeleteRowCol: Sized {
+    fn delete_row(a: &dyn Matrix<Self>, row: usize) -> Mat<Self>;
+    fn delete_col(a: &dyn Matrix<Self>, col: usize) -> Mat<Self>;
+}
+
+macro_rules! delete_row_col_impl(($($t: ident), +) => (
+    $(
+        impl DeleteRowCol for $t {
+            fn delete_row(a: &dyn Matrix<$t>, r: usize) -> Mat<$t> {
+                let (rows, cols) = (a.rows() as usize, a.cols() as usize);
+                if r >= rows {
+                    panic!("Row {} out of range for {}x{} matrix", r, rows, cols);
+                }
+
+                let mut result = Mat::fill(Default::zero(), rows - 1, cols);
+                for (dst, src) in (0..rows).filter(|&i| i != r).enumerate() {
+                    Copy::copy(&row(a, src), &mut row_mut(&mut result, dst));
+                }
+
+                result
+            }
+
+            fn delete_col(a: &dyn Matrix<$t>, c: usize) -> Mat<$t> {
+                let (rows, cols) = (a.rows() as usize, a.cols() as usize);
+                if c >= cols {
+                    panic!("Column {} out of range for {}x{} matrix", c, rows, cols);
+                }
+
+                let mut result = Mat::fill(Default::zero(), rows, cols - 1);
+                for (dst, src) in (0..cols).filter(|&j| j != c).enumerate() {
+                    Copy::copy(&col(a, src), &mut col_mut(&mut result, dst));
+                }
+
+                result
+            }
+        }
+    )+
+));
+
+delete_row_col_impl!(f32, f64, Complex32, Complex64);
+
+/// Matrices that are constant along their anti-diagonals (Hankel) or
+/// diagonals (Toeplitz), built from vectors with strided `copy`.
+pub trait SlidingWindow: Sized {
+    /// The `(len - window + 1) x window` matrix whose row `i` is
+    /// `x[i..i + window]`, as used for time-delay embeddings.
+    ///
+    /// Panics unless `1 <= window <= x.len()`.
+    fn hankel_matrix<V: ?Sized + Vector<Self>>(x: &V, window: usize) -> Mat<Self>;
+    /// The matrix with first column `first_col` and first row `first_row`,
+    /// where entry `(i, j)` only depends on `i - j`.
+    ///
+    /// The diagonal comes from `first_col`; `first_row[0]` is ignored.
+    fn toeplitz<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        first_col: &V,
+        first_row: &W,
+    ) -> Mat<Self>;
+}
+
+macro_rules! sliding_window_impl(($($t: ident), +) => (
+    $(
+        impl SlidingWindow for $t {
+            fn hankel_matrix<V: ?Sized + Vector<Self>>(x: &V, window: usize) -> Mat<$t> {
+                let len = x.len() as usize;
+                if window == 0 || window > len {
+                    panic!("Window {} out of range for length {}", window, len);
+                }
+
+                let rows = len - window + 1;
+                let mut result = Mat::fill(Default::zero(), rows, window);
+                for i in 0..rows {
+                    let w = unsafe { Strided::new(x.as_ptr(), x.len(), x.inc()) }.skip(i as u32).take(window as u32);
+                    Copy::copy(&w, &mut row_mut(&mut result, i));
+                }
+
+                result
+            }
+
+            fn toeplitz<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(first_col: &V, first_row: &W) -> Mat<$t> {
+                let (rows, cols) = (first_col.len() as usize, first_row.len() as usize);
+                let c = || unsafe { Strided::new(first_col.as_ptr(), first_col.len(), first_col.inc()) };
+                let r = || unsafe { Strided::new(first_row.as_ptr(), first_row.len(), first_row.inc()) };
+
+                let mut result = Mat::fill(Default::zero(), rows, cols);
+                // On and below the diagonal, column j is first_col shifted
+                // down by j; above it, row i is first_row shifted right by i.
+                for j in 0..cols.min(rows) {
+                    Copy::copy(&c().take((rows - j) as u32), &mut col_mut(&mut result, j).skip(j as u32));
+                }
+                for i in 0..rows.min(cols) {
+                    Copy::copy(&r().skip(1).take((cols - i - 1) as u32), &mut row_mut(&mut result, i).skip(i as u32 + 1));
+                }
+
+                result
+            }
+        }
+    )+
+));
+
+sliding_window_impl!(f32, f64, Complex32, Complex64);
+
+/// Conversions between matrices and flat buffers in a chosen order.
+///
+/// Each row (for `RowMajor`) or column (for `ColMajor`) moves with one
+/// strided `copy`, whatever the order and leading dimension of the matrix.
+pub trait Flatten: Sized {
+    /// The elements of `a`, row after row or column after column.
+    fn to_flat(a: &dyn Matrix<Self>, layout: Order) -> Vec<Self>;
+    /// The `rows x cols` matrix whose elements `data` lists in `layout`
+    /// order. Panics unless `data` has exactly `rows * cols` elements.
+    fn from_flat<V: ?Sized + Vector<Self>>(
+        rows: usize,
+        cols: usize,
+        data: &V,
+        layout: Order,
+    ) -> Mat<Self>;
+}
+
+macro_rules! flatten_impl(($($t: ident), +) => (
+    $(
+        impl Flatten for $t {
+            fn to_flat(a: &dyn Matrix<$t>, layout: Order) -> Vec<$t> {
+                let (rows, cols) = (a.rows() as usize, a.cols() as usize);
+                let mut flat: Vec<$t> = vec![Default::zero(); rows * cols];
+                let ptr = flat.as_mut_ptr();
+                match layout {
+                    Order::RowMajor => for i in 0..rows {
+                        let mut dst = unsafe { Strided::new(ptr.add(i * cols), cols as u32, 1) };
+                        Copy::copy(&row(a, i), &mut dst);
+                    },
+                    Order::ColMajor => for j in 0..cols {
+                        let mut dst = unsafe { Strided::new(ptr.add(j * rows), rows as u32, 1) };
+                        Copy::copy(&col(a, j), &mut dst);
+                    },
+                }
+
+                flat
+            }
+
+            fn from_flat<V: ?Sized + Vector<Self>>(rows: usize, cols: usize, data: &V, layout: Order) -> Mat<$t> {
+                if data.len() as usize != rows * cols {
+                    panic!("Dimension mismatch");
+                }
+
+                let mut result = Mat::fill(Default::zero(), rows, cols);
+                let src = || unsafe { Strided::new(data.as_ptr(), data.len(), data.inc()) };
+                match layout {
+                    Order::RowMajor => for i in 0..rows {
+                        let line = src().skip((i * cols) as u32).take(cols as u32);
+                        Copy::copy(&line, &mut row_mut(&mut result, i));
+                    },
+                    Order::ColMajor => for j in 0..cols {
+                        let line = src().skip((j * rows) as u32).take(rows as u32);
+                        Copy::copy(&line, &mut col_mut(&mut result, j));
+                    },
+                }
+
+                result
+            }
+        }
+    )+
+));
+
+flatten_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod apply_elementwise_tests {
+    use crate::attribute::Order;
+    use crate::math::structure::ApplyElementwise;
+    use crate::math::Mat;
+    use crate::matrix::Matrix;
+
+    struct View<'a> {
+        rows: u32,
+        cols: u32,
+        ld: u32,
+        order: Order,
+        data: &'a mut [f32],
+    }
+
+    impl<'a> Matrix<f32> for View<'a> {
+        fn lead_dim(&self) -> u32 {
+            self.ld
+        }
+
+        fn order(&self) -> Order {
+            self.order
+        }
+
+        fn rows(&self) -> u32 {
+            self.rows
+        }
+
+        fn cols(&self) -> u32 {
+            self.cols
+        }
+
+        fn as_ptr(&self) -> *const f32 {
+            self.data.as_ptr()
+        }
+
+        fn as_mut_ptr(&mut self) -> *mut f32 {
+            self.data.as_mut_ptr()
+        }
+    }
+
+    #[test]
+    fn relu() {
+        let mut a = mat![-1f64, 2.0; 3.0, -4.0];
+
+        ApplyElementwise::apply_elementwise(&mut a, |v| v.max(0.0));
+        assert_eq!(a, mat![0.0, 2.0; 3.0, 0.0]);
+    }
+
+    #[test]
+    fn row_major_view() {
+        // The lower-right 2x2 block of a 3x3 matrix.
+        let mut data = vec![-1f32, -2.0, -3.0, -4.0, -5.0, 6.0, -7.0, 8.0, -9.0];
+        {
+            let mut view = View {
+                rows: 2,
+                cols: 2,
+                ld: 3,
+                order: Order::RowMajor,
+                data: &mut data[4..],
+            };
+            ApplyElementwise::apply_elementwise(&mut view, |v| v.max(0.0));
+        }
+
+        assert_eq!(data, vec![-1.0, -2.0, -3.0, -4.0, 0.0, 6.0, -7.0, 8.0, 0.0]);
+    }
+
+    #[test]
+    fn col_major_view() {
+        // The top 2x2 block of a 3x2 column-major matrix.
+        let mut data = vec![-1f32, 2.0, -3.0, 4.0, -5.0, -6.0];
+        {
+            let mut view = View {
+                rows: 2,
+                cols: 2,
+                ld: 3,
+                order: Order::ColMajor,
+                data: &mut data[..],
+            };
+            ApplyElementwise::apply_elementwise(&mut view, |v| v.max(0.0));
+        }
+
+        assert_eq!(data, vec![0.0, 2.0, -3.0, 4.0, 0.0, -6.0]);
+    }
+}
+
+#[cfg(test)]
+mod row_dots_tests {
+    use crate::math::structure::RowDots;
+    use crate::math::Mat;
+    use crate::matrix::tests::M;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let a = mat![1f32, 2.0; 3.0, 4.0; 5.0, 6.0];
+        let b = mat![1f32, -1.0; 0.5, 2.0; 0.0, 3.0];
+
+        assert_eq!(RowDots::row_dots(&a, &b), vec![-1.0, 9.5, 18.0]);
+    }
+
+    #[test]
+    fn complex() {
+        let i = Complex::new(0f64, 1.0);
+        let a = M(2, 1, vec![i, Complex::new(2.0, 0.0)]);
+        let b = M(2, 1, vec![i, Complex::new(0.0, -1.0)]);
+
+        assert_eq!(
+            RowDots::row_dots(&a, &b),
+            vec![Complex::new(-1.0, 0.0), Complex::new(0.0, -2.0)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_shapes() {
+        let a = mat![1f64, 2.0; 3.0, 4.0];
+        let b = mat![1f64, 2.0];
+
+        let _ = RowDots::row_dots(&a, &b);
+    }
+}
+
+#[cfg(test)]
+mod hadamard_mat_tests {
+    use crate::attribute::Order;
+    use crate::math::structure::HadamardMat;
+    use crate::math::Mat;
+    use crate::matrix::Matrix;
+    use num_complex::Complex;
+
+    struct ColMajor(u32, u32, Vec<f64>);
+
+    impl Matrix<f64> for ColMajor {
+        fn order(&self) -> Order {
+            Order::ColMajor
+        }
+
+        fn rows(&self) -> u32 {
+            self.0
+        }
+
+        fn cols(&self) -> u32 {
+            self.1
+        }
+
+        fn as_ptr(&self) -> *const f64 {
+            self.2.as_ptr()
+        }
+
+        fn as_mut_ptr(&mut self) -> *mut f64 {
+            self.2.as_mut_ptr()
+        }
+    }
+
+    #[test]
+    fn mixed_layouts() {
+        let a = mat![1f64, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let b = ColMajor(2, 3, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+
+        let mut out = Mat::fill(0f64, 2, 3);
+        HadamardMat::hadamard_mat(&a, &b, &mut out);
+        assert_eq!(out, mat![1.0, 4.0, 9.0; 16.0, 25.0, 36.0]);
+
+        let mut col_out = ColMajor(2, 3, vec![0.0; 6]);
+        HadamardMat::hadamard_mat(&b, &a, &mut col_out);
+        assert_eq!(col_out.2, vec![1.0, 16.0, 4.0, 25.0, 9.0, 36.0]);
+    }
+
+    #[test]
+    fn complex() {
+        let i = Complex::new(0f32, 1.0);
+        let a = Mat::new_from_data(1, 2, vec![i, Complex::new(2.0, 0.0)]);
+
+        let mut out = Mat::fill(Complex::new(0f32, 0.0), 1, 2);
+        HadamardMat::hadamard_mat(&a, &a, &mut out);
+        assert_eq!(out[0], [Complex::new(-1.0, 0.0), Complex::new(4.0, 0.0)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_shapes() {
+        let a = mat![1f32, 2.0];
+        let b = mat![1f32; 2.0];
+        let mut out = Mat::fill(0f32, 1, 2);
+
+        HadamardMat::hadamard_mat(&a, &b, &mut out);
+    }
+}
+
+#[cfg(test)]
+mod block_diagonal_tests {
+    use crate::math::structure::BlockDiagonal;
+    use crate::math::Mat;
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn real() {
+        let a = mat![1f32, 2.0; 3.0, 4.0];
+        let b = mat![5f32, 6.0; 7.0, 8.0];
+
+        let d = BlockDiagonal::block_diagonal(&[&a as &dyn Matrix<f32>, &b]);
+        assert_eq!(
+            d,
+            mat![1.0, 2.0, 0.0, 0.0;
+                 3.0, 4.0, 0.0, 0.0;
+                 0.0, 0.0, 5.0, 6.0;
+                 0.0, 0.0, 7.0, 8.0]
+        );
+    }
+
+    #[test]
+    fn rectangular() {
+        let a = mat![1f64, 2.0];
+        let b = mat![3f64; 4.0];
+
+        let d = BlockDiagonal::block_diagonal(&[&a as &dyn Matrix<f64>, &b]);
+        assert_eq!(d, mat![1.0, 2.0, 0.0; 0.0, 0.0, 3.0; 0.0, 0.0, 4.0]);
+    }
+}
+
+#[cfg(test)]
+mod transpose_batched_tests {
+    use crate::attribute::Transpose;
+    use crate::math::structure::TransposeBatched;
+    use crate::math::Mat;
+    use crate::matrix::ops::Gemm;
+    use crate::matrix::Matrix;
+
+    #[test]
+    fn real() {
+        let a = mat![1f64, 2.0; 3.0, 4.0; 5.0, 6.0];
+        let b = mat![-1f64, 0.0; 0.5, 7.0; 2.0, -3.0];
+        let c = mat![0f64, 1.0; 1.0, 0.0; 9.0, 8.0];
+        let src: Vec<&dyn Matrix<f64>> = vec![&a, &b, &c];
+
+        let mut out: Vec<Mat<f64>> = (0..3).map(|_| Mat::fill(0.0, 2, 3)).collect();
+        {
+            let mut dst: Vec<&mut Mat<f64>> = out.iter_mut().collect();
+            TransposeBatched::transpose_batched(&src, &mut dst);
+        }
+
+        // A^T = A^T * I
+        let eye = mat![1f64, 0.0, 0.0; 0.0, 1.0, 0.0; 0.0, 0.0, 1.0];
+        for (m, t) in src.iter().zip(&out) {
+            let mut expected = Mat::fill(0.0, 2, 3);
+            Gemm::gemm(
+                &1.0,
+                Transpose::Trans,
+                *m,
+                Transpose::NoTrans,
+                &eye,
+                &0.0,
+                &mut expected,
+            );
+            assert_eq!(*t, expected);
+        }
+        assert_eq!(out[0], mat![1.0, 3.0, 5.0; 2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn wider_than_a_block() {
+        let a = Mat::new_from_data(2, 40, (0..80).map(|k| k as f32).collect());
+
+        let mut t = Mat::fill(0f32, 40, 2);
+        TransposeBatched::transpose_batched(&[&a as &dyn Matrix<f32>], &mut [&mut t]);
+        for i in 0..2 {
+            for j in 0..40 {
+                assert_eq!(t[j][i], a[i][j]);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_shape() {
+        let a = mat![1f32, 2.0; 3.0, 4.0; 5.0, 6.0];
+
+        let mut t = Mat::fill(0f32, 3, 2);
+        TransposeBatched::transpose_batched(&[&a as &dyn Matrix<f32>], &mut [&mut t]);
+    }
+}
+
+#[cfg(test)]
+mod permute_cols_tests {
+    use crate::math::structure::PermuteCols;
+    use crate::math::Mat;
+
+    #[test]
+    fn real() {
+        let mut a = mat![1f32, 2.0, 3.0; 4.0, 5.0, 6.0];
+
+        PermuteCols::permute_cols(&mut a, &[2, 0, 1]);
+        assert_eq!(a, mat![3.0, 1.0, 2.0; 6.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn several_cycles() {
+        let mut a = Mat::new_from_data(1, 5, vec![0f64, 1.0, 2.0, 3.0, 4.0]);
+
+        PermuteCols::permute_cols(&mut a, &[1, 0, 2, 4, 3]);
+        assert_eq!(a[0], [1.0, 0.0, 2.0, 4.0, 3.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn repeated_index() {
+        let mut a = mat![1f32, 2.0, 3.0];
+
+        PermuteCols::permute_cols(&mut a, &[0, 1, 1]);
+    }
+}
+
+#[cfg(test)]
+mod delete_row_col_tests {
+    use crate::math::structure::DeleteRowCol;
+    use crate::math::Mat;
+    use num_complex::Complex;
+
+    #[test]
+    fn delete_col() {
+        let a = mat![1f64, 2.0, 3.0; 4.0, 5.0, 6.0];
+
+        assert_eq!(DeleteRowCol::delete_col(&a, 1), mat![1.0, 3.0; 4.0, 6.0]);
+        assert_eq!(DeleteRowCol::delete_col(&a, 2), mat![1.0, 2.0; 4.0, 5.0]);
+    }
+
+    #[test]
+    fn delete_row() {
+        let a = mat![1f32, 2.0; 3.0, 4.0; 5.0, 6.0];
+
+        assert_eq!(DeleteRowCol::delete_row(&a, 0), mat![3.0, 4.0; 5.0, 6.0]);
+        assert_eq!(DeleteRowCol::delete_row(&a, 1), mat![1.0, 2.0; 5.0, 6.0]);
+    }
+
+    #[test]
+    fn complex() {
+        let a = Mat::new_from_data(1, 2, vec![Complex::new(1f32, 1.0), Complex::new(2.0, 2.0)]);
+
+        let b = DeleteRowCol::delete_col(&a, 0);
+        assert_eq!(b[0], [Complex::new(2.0, 2.0)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn col_out_of_range() {
+        let a = mat![1f64, 2.0];
+
+        let _ = DeleteRowCol::delete_col(&a, 2);
+    }
+}
+
+#[cfg(test)]
+mod sliding_window_tests {
+    use crate::math::structure::SlidingWindow;
+    use crate::math::Mat;
+    use crate::vector::Strided;
+    use num_complex::Complex;
+
+    #[test]
+    fn hankel() {
+        let x = vec![1f64, 2.0, 3.0, 4.0];
+
+        assert_eq!(
+            SlidingWindow::hankel_matrix(&x, 2),
+            mat![1.0, 2.0; 2.0, 3.0; 3.0, 4.0]
+        );
+        assert_eq!(
+            SlidingWindow::hankel_matrix(&x, 4),
+            mat![1.0, 2.0, 3.0, 4.0]
+        );
+    }
+
+    #[test]
+    fn hankel_strided() {
+        let x = [1f32, 0.0, 2.0, 0.0, 3.0];
+        let evens = unsafe { Strided::new(x.as_ptr(), 3, 2) };
+
+        assert_eq!(
+            SlidingWindow::hankel_matrix(&evens, 2),
+            mat![1.0, 2.0; 2.0, 3.0]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn window_too_long() {
+        let x = vec![1f64, 2.0];
+
+        let _ = SlidingWindow::hankel_matrix(&x, 3);
+    }
+
+    #[test]
+    fn toeplitz() {
+        let c = vec![1f64, 2.0, 3.0];
+        let r = vec![-1f64, 4.0, 5.0, 6.0];
+
+        assert_eq!(
+            SlidingWindow::toeplitz(&c, &r),
+            mat![1.0, 4.0, 5.0, 6.0; 2.0, 1.0, 4.0, 5.0; 3.0, 2.0, 1.0, 4.0]
+        );
+        assert_eq!(
+            SlidingWindow::toeplitz(&r, &c),
+            mat![-1.0, 2.0, 3.0; 4.0, -1.0, 2.0; 5.0, 4.0, -1.0; 6.0, 5.0, 4.0]
+        );
+    }
+
+    #[test]
+    fn toeplitz_complex() {
+        let c = vec![Complex::new(1f32, 1.0), Complex::new(2.0, 0.0)];
+        let r = vec![Complex::new(0f32, 0.0), Complex::new(0.0, 3.0)];
+
+        let t = SlidingWindow::toeplitz(&c, &r);
+        assert_eq!(t[0], [Complex::new(1.0, 1.0), Complex::new(0.0, 3.0)]);
+        assert_eq!(t[1], [Complex::new(2.0, 0.0), Complex::new(1.0, 1.0)]);
+    }
+}
+
+#[cfg(test)]
+mod flatten_tests {
+    use crate::attribute::Order;
+    use crate::math::structure::Flatten;
+    use crate::math::Mat;
+    use crate::matrix::tests::Cm;
+
+    #[test]
+    fn both_layouts() {
+        let a = mat![1f64, 2.0, 3.0; 4.0, 5.0, 6.0];
+
+        assert_eq!(
+            Flatten::to_flat(&a, Order::RowMajor),
+            vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]
+        );
+        assert_eq!(
+            Flatten::to_flat(&a, Order::ColMajor),
+            vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]
+        );
+
+        // The storage order of the input does not matter.
+        let b = Cm(2, 3, vec![1f64, 4.0, 2.0, 5.0, 3.0, 6.0]);
+        assert_eq!(
+            Flatten::to_flat(&b, Order::RowMajor),
+            Flatten::to_flat(&a, Order::RowMajor)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let a = mat![1f32, 2.0; 3.0, 4.0; 5.0, 6.0];
+
+        for &layout in &[Order::RowMajor, Order::ColMajor] {
+            let flat = Flatten::to_flat(&a, layout);
+            assert_eq!(Flatten::from_flat(3, 2, &flat, layout), a);
+        }
+        let flat = vec![1f32, 3.0, 5.0, 2.0, 4.0, 6.0];
+        assert_eq!(Flatten::from_flat(3, 2, &flat, Order::ColMajor), a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_length() {
+        let flat = vec![1f64, 2.0, 3.0];
+
+        let _ = Flatten::from_flat(2, 2, &flat, Order::RowMajor);
+    }
+}
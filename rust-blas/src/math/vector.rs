@@ -9,6 +9,11 @@ use crate::vector::Vector;
 use num_complex::{Complex32, Complex64};
 use std::ops::{Add, Mul};
 
+/// Elementwise sum, computed as a copy of `self` followed by `axpy` with
+/// `alpha = 1`.
+///
+/// Unlike `axpy`, which quietly works on the shorter of its arguments, both
+/// operands must have the same length; a mismatch panics.
 impl<'a, T> Add for &'a dyn Vector<T>
 where
     T: Axpy + Copy + Default,
@@ -16,6 +21,10 @@ where
     type Output = Vec<T>;
 
     fn add(self, x: &dyn Vector<T>) -> Vec<T> {
+        if self.len() != x.len() {
+            panic!("Dimension mismatch");
+        }
+
         let mut result: Vec<_> = self.into();
         let scale = Default::one();
 
@@ -38,6 +47,7 @@ where
     }
 }
 
+/// Scales a copy of the vector by `alpha` with `scal`.
 impl<'a, T> Mul<T> for &'a dyn Vector<T>
 where
     T: Sized + Copy + Scal,
@@ -71,6 +81,7 @@ left_scale!(f32, f64, Complex32, Complex64);
 #[cfg(test)]
 mod tests {
     use crate::math::Marker::{H, T};
+    use crate::vector::ops::{Axpy, Scal};
     use crate::Vector;
     use num_complex::Complex;
 
@@ -84,6 +95,29 @@ mod tests {
         assert_eq!(&z, &vec![4f32, 6f32]);
     }
 
+    #[test]
+    fn add_matches_axpy() {
+        let x = vec![1f64, -2.0, 0.5];
+        let y = vec![4f64, 0.25, -3.0];
+
+        let mut expected = vec![0f64; 3];
+        Axpy::axpy(&1.0, &x, &mut expected);
+        Axpy::axpy(&1.0, &y, &mut expected);
+
+        let z = (&x as &dyn Vector<_>) + &y;
+        assert_eq!(z, expected);
+        assert_eq!(x, vec![1.0, -2.0, 0.5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Dimension mismatch")]
+    fn add_mismatched() {
+        let x = vec![1f32, 2f32];
+        let y = vec![3f32];
+
+        let _ = (&x as &dyn Vector<_>) + &y;
+    }
+
     #[test]
     fn dot() {
         let x = vec![1f32, 2f32];
@@ -120,4 +154,17 @@ mod tests {
         assert_eq!(y, vec![3f32, 6f32]);
         assert_eq!(z, y);
     }
+
+    #[test]
+    fn scale_matches_scal() {
+        let x = vec![Complex::new(1f64, -1.0), Complex::new(0.5, 2.0)];
+        let alpha = Complex::new(2.0, 0.5);
+
+        let mut expected = x.clone();
+        Scal::scal(&alpha, &mut expected);
+
+        let y = (&x as &dyn Vector<_>) * alpha;
+        assert_eq!(y, expected);
+        assert_eq!(x[0], Complex::new(1.0, -1.0));
+    }
 }
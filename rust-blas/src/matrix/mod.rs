@@ -4,6 +4,8 @@
 
 //! Matrix operations.
 use crate::attribute::Order;
+use crate::vector::ops::Swap;
+use crate::vector::Strided;
 
 pub mod ll;
 pub mod ops;
@@ -39,8 +41,101 @@ pub trait BandMatrix<T>: Matrix<T> {
     fn as_matrix(&self) -> &dyn Matrix<T>;
 }
 
+/// Offset of element `(i, j)` from the start of the matrix data, honoring
+/// the order and leading dimension.
+pub(crate) fn offset<T>(a: &dyn Matrix<T>, i: usize, j: usize) -> usize {
+    assert!(
+        i < a.rows() as usize && j < a.cols() as usize,
+        "Index ({}, {}) out of range for {}x{} matrix",
+        i,
+        j,
+        a.rows(),
+        a.cols()
+    );
+
+    let ld = a.lead_dim() as usize;
+    match a.order() {
+        Order::RowMajor => i * ld + j,
+        Order::ColMajor => j * ld + i,
+    }
+}
+
+/// Reads element `(i, j)`.
+pub(crate) fn get<T: Copy>(a: &dyn Matrix<T>, i: usize, j: usize) -> T {
+    unsafe { *a.as_ptr().add(offset(a, i, j)) }
+}
+
+/// Overwrites element `(i, j)`.
+pub(crate) fn set<T>(a: &mut dyn Matrix<T>, i: usize, j: usize, value: T) {
+    let off = offset(a, i, j);
+    unsafe {
+        *a.as_mut_ptr().add(off) = value;
+    }
+}
+
+/// Start offset, length and stride of row `i`.
+fn row_layout<T>(a: &dyn Matrix<T>, i: usize) -> (usize, u32, u32) {
+    let start = if a.cols() == 0 { 0 } else { offset(a, i, 0) };
+    match a.order() {
+        Order::RowMajor => (start, a.cols(), 1),
+        Order::ColMajor => (start, a.cols(), a.lead_dim()),
+    }
+}
+
+/// Start offset, length and stride of column `j`.
+fn col_layout<T>(a: &dyn Matrix<T>, j: usize) -> (usize, u32, u32) {
+    let start = if a.rows() == 0 { 0 } else { offset(a, 0, j) };
+    match a.order() {
+        Order::RowMajor => (start, a.rows(), a.lead_dim()),
+        Order::ColMajor => (start, a.rows(), 1),
+    }
+}
+
+/// Views row `i` of `a` as a vector.
+pub(crate) fn row<T>(a: &dyn Matrix<T>, i: usize) -> Strided<T> {
+    let (start, len, inc) = row_layout(a, i);
+    unsafe { Strided::new(a.as_ptr().add(start), len, inc) }
+}
+
+/// Views column `j` of `a` as a vector.
+pub(crate) fn col<T>(a: &dyn Matrix<T>, j: usize) -> Strided<T> {
+    let (start, len, inc) = col_layout(a, j);
+    unsafe { Strided::new(a.as_ptr().add(start), len, inc) }
+}
+
+/// Views row `i` of `a` as a vector that may be written through.
+pub(crate) fn row_mut<T>(a: &mut dyn Matrix<T>, i: usize) -> Strided<T> {
+    let (start, len, inc) = row_layout(a, i);
+    unsafe { Strided::new(a.as_mut_ptr().add(start), len, inc) }
+}
+
+/// Views column `j` of `a` as a vector that may be written through.
+pub(crate) fn col_mut<T>(a: &mut dyn Matrix<T>, j: usize) -> Strided<T> {
+    let (start, len, inc) = col_layout(a, j);
+    unsafe { Strided::new(a.as_mut_ptr().add(start), len, inc) }
+}
+
+/// Exchanges rows `i` and `j` of `a` in place.
+pub(crate) fn swap_rows<T: Swap>(a: &mut dyn Matrix<T>, i: usize, j: usize) {
+    if i != j {
+        let mut x = row_mut(a, i);
+        let mut y = row_mut(a, j);
+        Swap::swap(&mut x, &mut y);
+    }
+}
+
+/// Exchanges columns `i` and `j` of `a` in place.
+pub(crate) fn swap_cols<T: Swap>(a: &mut dyn Matrix<T>, i: usize, j: usize) {
+    if i != j {
+        let mut x = col_mut(a, i);
+        let mut y = col_mut(a, j);
+        Swap::swap(&mut x, &mut y);
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
+    use crate::attribute::Order;
     use crate::Matrix;
 
     pub struct M<T>(pub u32, pub u32, pub Vec<T>);
@@ -64,4 +159,29 @@ pub mod tests {
             (&mut self.2[..]).as_mut_ptr()
         }
     }
+
+    /// Like `M`, but stored column by column.
+    pub struct Cm<T>(pub u32, pub u32, pub Vec<T>);
+
+    impl<T> Matrix<T> for Cm<T> {
+        fn order(&self) -> Order {
+            Order::ColMajor
+        }
+
+        fn rows(&self) -> u32 {
+            self.0
+        }
+
+        fn cols(&self) -> u32 {
+            self.1
+        }
+
+        fn as_ptr(&self) -> *const T {
+            self.2.as_ptr()
+        }
+
+        fn as_mut_ptr(&mut self) -> *mut T {
+            self.2.as_mut_ptr()
+        }
+    }
 }
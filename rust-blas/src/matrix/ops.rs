@@ -4,13 +4,54 @@
 
 //! Wrappers for matrix functions.
 
-use crate::attribute::{Diagonal, Side, Symmetry, Transpose};
+use crate::attribute::{Diagonal, Order, Side, Symmetry, Transpose};
 use crate::matrix::ll::*;
 use crate::matrix::Matrix;
 use crate::pointer::CPtr;
 use crate::scalar::Scalar;
 use num_complex::{Complex, Complex32, Complex64};
 
+/// `op` for an operand stored in `order`, as seen by a call made in
+/// `layout`.
+///
+/// A matrix read in the other layout is its own transpose, so the operation
+/// flips. `ConjTrans` has no untransposed conjugate to flip to.
+fn relayout(op: Transpose, order: Order, layout: Order) -> Transpose {
+    if order == layout {
+        return op;
+    }
+
+    match op {
+        Transpose::NoTrans => Transpose::Trans,
+        Transpose::Trans => Transpose::NoTrans,
+        Transpose::ConjTrans => panic!("ConjTrans needs operands in the same order as the result"),
+    }
+}
+
+/// The triangle of a symmetric or triangular operand stored in `order`, as
+/// seen by a call made in `layout`.
+fn relayout_uplo(symmetry: Symmetry, order: Order, layout: Order) -> Symmetry {
+    match (order == layout, symmetry) {
+        (true, s) => s,
+        (false, Symmetry::Upper) => Symmetry::Lower,
+        (false, Symmetry::Lower) => Symmetry::Upper,
+    }
+}
+
+/// For operands that cannot be reinterpreted in the other order, such as
+/// Hermitian ones, whose transpose is their conjugate.
+fn same_order(order: Order, layout: Order) {
+    if order != layout {
+        panic!("Operands need to be stored in the same order as the result");
+    }
+}
+
+/// General matrix multiply
+///
+/// C ← αop(A)op(B) + βC
+///
+/// The operands may be stored in different orders; the call is made in the
+/// order of `c`.
 pub trait Gemm: Sized {
     fn gemm(
         alpha: &Self,
@@ -33,13 +74,17 @@ macro_rules! gemm_impl(($($t: ident), +) => (
                         _ => (a.cols(), a.rows()),
                     };
 
-                    let n = match bt {
-                        Transpose::NoTrans => b.cols(),
-                        _ => b.rows(),
+                    let (kb, n) = match bt {
+                        Transpose::NoTrans => (b.rows(), b.cols()),
+                        _ => (b.cols(), b.rows()),
                     };
+                    if k != kb || c.rows() != m || c.cols() != n {
+                        panic!("Dimension mismatch");
+                    }
 
-                    prefix!($t, gemm)(a.order(),
-                        at, bt,
+                    let layout = c.order();
+                    prefix!($t, gemm)(layout,
+                        relayout(at, a.order(), layout), relayout(bt, b.order(), layout),
                         m, n, k,
                         alpha.as_const(),
                         a.as_ptr().as_c_ptr(), a.lead_dim(),
@@ -58,7 +103,8 @@ gemm_impl!(f32, f64, Complex32, Complex64);
 mod gemm_tests {
     use crate::attribute::Transpose;
     use crate::matrix::ops::Gemm;
-    use crate::matrix::tests::M;
+    use crate::matrix::tests::{Cm, M};
+    use crate::matrix::Matrix;
     use std::iter::repeat;
 
     #[test]
@@ -84,8 +130,78 @@ mod gemm_tests {
 
         assert_eq!(c.2, vec![13.0, 9.0, 16.0, 12.0]);
     }
+
+    #[test]
+    fn non_square() {
+        let a = M(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let b = M(3, 2, vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.0]);
+        let t = Transpose::NoTrans;
+
+        let mut c = M(2, 2, vec![1.0; 4]);
+        Gemm::gemm(&1f64, t, &a, t, &b, &0f64, &mut c);
+
+        assert_eq!(c.2, vec![58.0, 64.0, 139.0, 154.0]);
+    }
+
+    #[test]
+    fn mixed_orders() {
+        let rows = (
+            M(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+            M(3, 2, vec![7.0, 8.0, 9.0, 10.0, 11.0, 12.0]),
+        );
+        let cols = (
+            Cm(2, 3, vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]),
+            Cm(3, 2, vec![7.0, 9.0, 11.0, 8.0, 10.0, 12.0]),
+        );
+        let t = Transpose::NoTrans;
+        let operands: [(&dyn Matrix<f64>, &dyn Matrix<f64>); 4] = [
+            (&rows.0, &rows.1),
+            (&rows.0, &cols.1),
+            (&cols.0, &rows.1),
+            (&cols.0, &cols.1),
+        ];
+
+        for &(a, b) in operands.iter() {
+            let mut c = M(2, 2, vec![0.0; 4]);
+            Gemm::gemm(&1f64, t, a, t, b, &0f64, &mut c);
+            assert_eq!(c.2, vec![58.0, 64.0, 139.0, 154.0]);
+
+            let mut c = Cm(2, 2, vec![0.0; 4]);
+            Gemm::gemm(&1f64, t, a, t, b, &0f64, &mut c);
+            assert_eq!(c.2, vec![58.0, 139.0, 64.0, 154.0]);
+        }
+
+        let mut c = Cm(2, 2, vec![0.0; 4]);
+        Gemm::gemm(
+            &1f64,
+            Transpose::Trans,
+            &cols.1,
+            Transpose::Trans,
+            &rows.0,
+            &0f64,
+            &mut c,
+        );
+        assert_eq!(c.2, vec![58.0, 64.0, 139.0, 154.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mismatched_inner() {
+        let a = M(2, 3, vec![0.0; 6]);
+        let b = M(2, 2, vec![0.0; 4]);
+        let t = Transpose::NoTrans;
+
+        let mut c = M(2, 2, vec![0.0; 4]);
+        Gemm::gemm(&1f32, t, &a, t, &b, &0f32, &mut c);
+    }
 }
 
+/// Symmetric matrix multiply
+///
+/// C ← αAB + βC or C ← αBA + βC
+///
+/// The call is made in the order of `c`. `b` must be stored in that order
+/// too, while `a` may use either.
 pub trait Symm: Sized {
     fn symm(
         side: Side,
@@ -98,6 +214,11 @@ pub trait Symm: Sized {
     );
 }
 
+/// Hermitian matrix multiply
+///
+/// C ← αAB + βC or C ← αBA + βC
+///
+/// All three operands must be stored in the same order.
 pub trait Hemm: Sized {
     fn hemm(
         side: Side,
@@ -110,13 +231,19 @@ pub trait Hemm: Sized {
     );
 }
 
-macro_rules! symm_impl(($trait_name: ident, $fn_name: ident, $($t: ident), +) => (
+macro_rules! symm_impl(($trait_name: ident, $fn_name: ident, $symmetric: expr, $($t: ident), +) => (
     $(
         impl $trait_name for $t {
             fn $fn_name(side: Side, symmetry: Symmetry, alpha: &$t, a: &dyn Matrix<$t>, b: &dyn Matrix<$t>, beta: &$t, c: &mut dyn Matrix<$t>) {
+                let layout = c.order();
+                same_order(b.order(), layout);
+                if !$symmetric {
+                    same_order(a.order(), layout);
+                }
+
                 unsafe {
-                    prefix!($t, $fn_name)(a.order(),
-                        side, symmetry,
+                    prefix!($t, $fn_name)(layout,
+                        side, relayout_uplo(symmetry, a.order(), layout),
                         a.rows(), b.cols(),
                         alpha.as_const(),
                         a.as_ptr().as_c_ptr(), a.lead_dim(),
@@ -129,9 +256,36 @@ macro_rules! symm_impl(($trait_name: ident, $fn_name: ident, $($t: ident), +) =>
     )+
 ));
 
-symm_impl!(Symm, symm, f32, f64, Complex32, Complex64);
-symm_impl!(Hemm, hemm, Complex32, Complex64);
+symm_impl!(Symm, symm, true, f32, f64, Complex32, Complex64);
+symm_impl!(Hemm, hemm, false, Complex32, Complex64);
+
+#[cfg(test)]
+mod symm_tests {
+    use crate::attribute::{Side, Symmetry};
+    use crate::matrix::ops::Symm;
+    use crate::matrix::tests::{Cm, M};
+
+    #[test]
+    fn mixed_orders() {
+        // The upper triangle of [1 2; 2 3]; the other entry is never read.
+        let rows = M(2, 2, vec![1.0, 2.0, 99.0, 3.0]);
+        let cols = Cm(2, 2, vec![1.0, 99.0, 2.0, 3.0]);
+        let b = M(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+
+        for a in [&rows as &dyn crate::matrix::Matrix<f64>, &cols].iter() {
+            let mut c = M(2, 2, vec![0.0; 4]);
+            Symm::symm(Side::Left, Symmetry::Upper, &1f64, *a, &b, &0f64, &mut c);
+            assert_eq!(c.2, vec![7.0, 10.0, 11.0, 16.0]);
+        }
+    }
+}
 
+/// Triangular matrix multiply
+///
+/// B ← αop(A)B or B ← αBop(A)
+///
+/// The call is made in the order of `b`; `a` may be stored in either order
+/// unless `trans` is `ConjTrans`.
 pub trait Trmm: Sized {
     fn trmm(
         side: Side,
@@ -144,6 +298,11 @@ pub trait Trmm: Sized {
     );
 }
 
+/// Triangular solve with multiple right-hand sides
+///
+/// B ← αop(A)⁻¹B or B ← αBop(A)⁻¹
+///
+/// Stored orders are handled as for `Trmm`.
 pub trait Trsm: Sized {
     fn trsm(
         side: Side,
@@ -160,8 +319,12 @@ macro_rules! trmm_impl(($trait_name: ident, $fn_name: ident, $($t: ident), +) =>
     $(
         impl $trait_name for $t {
             fn $fn_name(side: Side, symmetry: Symmetry, trans: Transpose, diag: Diagonal, alpha: &$t, a: &dyn Matrix<$t>, b: &mut dyn Matrix<$t>) {
+                let layout = b.order();
+                let symmetry = relayout_uplo(symmetry, a.order(), layout);
+                let trans = relayout(trans, a.order(), layout);
+
                 unsafe {
-                    prefix!($t, $fn_name)(a.order(),
+                    prefix!($t, $fn_name)(layout,
                         side, symmetry, trans, diag,
                         b.rows(), b.cols(),
                         alpha.as_const(),
@@ -176,6 +339,33 @@ macro_rules! trmm_impl(($trait_name: ident, $fn_name: ident, $($t: ident), +) =>
 trmm_impl!(Trmm, trmm, f32, f64, Complex32, Complex64);
 trmm_impl!(Trsm, trsm, Complex32, Complex64);
 
+#[cfg(test)]
+mod trmm_tests {
+    use crate::attribute::{Diagonal, Side, Symmetry, Transpose};
+    use crate::matrix::ops::Trmm;
+    use crate::matrix::tests::{Cm, M};
+
+    #[test]
+    fn mixed_orders() {
+        // The lower triangle of [2 0; 1 3]; the other entry is never read.
+        let a = Cm(2, 2, vec![2.0, 1.0, 99.0, 3.0]);
+        let (l, d) = (Symmetry::Lower, Diagonal::NonUnit);
+
+        let mut b = M(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        Trmm::trmm(Side::Left, l, Transpose::NoTrans, d, &1f64, &a, &mut b);
+        assert_eq!(b.2, vec![2.0, 4.0, 10.0, 14.0]);
+
+        let mut b = M(2, 2, vec![1.0, 2.0, 3.0, 4.0]);
+        Trmm::trmm(Side::Left, l, Transpose::Trans, d, &1f64, &a, &mut b);
+        assert_eq!(b.2, vec![5.0, 8.0, 9.0, 12.0]);
+    }
+}
+
+/// Hermitian rank-k update
+///
+/// C ← αop(A)op(A)ᴴ + βC
+///
+/// Both operands must be stored in the same order.
 pub trait Herk: Sized {
     fn herk(
         symmetry: Symmetry,
@@ -187,6 +377,11 @@ pub trait Herk: Sized {
     );
 }
 
+/// Hermitian rank-2k update
+///
+/// C ← αop(A)op(B)ᴴ + ᾱop(B)op(A)ᴴ + βC
+///
+/// All three operands must be stored in the same order.
 pub trait Her2k: Sized {
     fn her2k(
         symmetry: Symmetry,
@@ -203,10 +398,17 @@ macro_rules! herk_impl(($($t: ident), +) => (
     $(
         impl Herk for $t {
             fn herk(symmetry: Symmetry, trans: Transpose, alpha: &$t, a: &dyn Matrix<Complex<$t>>, beta: &$t, c: &mut dyn Matrix<Complex<$t>>) {
+                let (n, k) = match trans {
+                    Transpose::NoTrans => (a.rows(), a.cols()),
+                    _ => (a.cols(), a.rows()),
+                };
+
+                same_order(a.order(), c.order());
+
                 unsafe {
-                    prefix!(Complex<$t>, herk)(a.order(),
+                    prefix!(Complex<$t>, herk)(c.order(),
                         symmetry, trans,
-                        a.rows(), a.cols(),
+                        n, k,
                         *alpha,
                         a.as_ptr().as_c_ptr(), a.lead_dim(),
                         *beta,
@@ -217,10 +419,18 @@ macro_rules! herk_impl(($($t: ident), +) => (
 
         impl Her2k for $t {
             fn her2k(symmetry: Symmetry, trans: Transpose, alpha: Complex<$t>, a: &dyn Matrix<Complex<$t>>, b: &dyn Matrix<Complex<$t>>, beta: &$t, c: &mut dyn Matrix<Complex<$t>>) {
+                let (n, k) = match trans {
+                    Transpose::NoTrans => (a.rows(), a.cols()),
+                    _ => (a.cols(), a.rows()),
+                };
+
+                same_order(a.order(), c.order());
+                same_order(b.order(), c.order());
+
                 unsafe {
-                    prefix!(Complex<$t>, her2k)(a.order(),
+                    prefix!(Complex<$t>, her2k)(c.order(),
                         symmetry, trans,
-                        a.rows(), a.cols(),
+                        n, k,
                         alpha.as_const(),
                         a.as_ptr().as_c_ptr(), a.lead_dim(),
                         b.as_ptr().as_c_ptr(), b.lead_dim(),
@@ -234,6 +444,29 @@ macro_rules! herk_impl(($($t: ident), +) => (
 
 herk_impl!(f32, f64);
 
+#[cfg(test)]
+mod herk_tests {
+    use crate::attribute::{Symmetry, Transpose};
+    use crate::matrix::ops::Herk;
+    use crate::matrix::tests::{Cm, M};
+    use num_complex::Complex;
+
+    #[test]
+    #[should_panic(expected = "same order")]
+    fn mixed_orders() {
+        let a = Cm(2, 1, vec![Complex::new(1f32, 1.0), Complex::new(0.0, 2.0)]);
+
+        let mut c = M(2, 2, vec![Complex::new(0f32, 0.0); 4]);
+        Herk::herk(Symmetry::Lower, Transpose::NoTrans, &1.0, &a, &0.0, &mut c);
+    }
+}
+
+/// Symmetric rank-k update
+///
+/// C ← αop(A)op(A)ᵀ + βC
+///
+/// The call is made in the order of `c`, and `a` may be stored in either
+/// order.
 pub trait Syrk: Sized {
     fn syrk(
         symmetry: Symmetry,
@@ -245,6 +478,12 @@ pub trait Syrk: Sized {
     );
 }
 
+/// Symmetric rank-2k update
+///
+/// C ← αop(A)op(B)ᵀ + αop(B)op(A)ᵀ + βC
+///
+/// The call is made in the order of `c`. `a` and `b` may be stored in either
+/// order, but both in the same one.
 pub trait Syr2k: Sized {
     fn syr2k(
         symmetry: Symmetry,
@@ -261,10 +500,17 @@ macro_rules! syrk_impl(($($t: ident), +) => (
     $(
         impl Syrk for $t {
             fn syrk(symmetry: Symmetry, trans: Transpose, alpha: &$t, a: &dyn Matrix<$t>, beta: &$t, c: &mut dyn Matrix<$t>) {
+                let (n, k) = match trans {
+                    Transpose::NoTrans => (a.rows(), a.cols()),
+                    _ => (a.cols(), a.rows()),
+                };
+
+                let layout = c.order();
+
                 unsafe {
-                    prefix!($t, syrk)(a.order(),
-                        symmetry, trans,
-                        a.rows(), a.cols(),
+                    prefix!($t, syrk)(layout,
+                        symmetry, relayout(trans, a.order(), layout),
+                        n, k,
                         alpha.as_const(),
                         a.as_ptr().as_c_ptr(), a.lead_dim(),
                         beta.as_const(),
@@ -275,10 +521,18 @@ macro_rules! syrk_impl(($($t: ident), +) => (
 
         impl Syr2k for $t {
             fn syr2k(symmetry: Symmetry, trans: Transpose, alpha: &$t, a: &dyn Matrix<$t>, b: &dyn Matrix<$t>, beta: &$t, c: &mut dyn Matrix<$t>) {
+                let (n, k) = match trans {
+                    Transpose::NoTrans => (a.rows(), a.cols()),
+                    _ => (a.cols(), a.rows()),
+                };
+
+                let layout = c.order();
+                same_order(b.order(), a.order());
+
                 unsafe {
-                    prefix!($t, syr2k)(a.order(),
-                        symmetry, trans,
-                        a.rows(), a.cols(),
+                    prefix!($t, syr2k)(layout,
+                        symmetry, relayout(trans, a.order(), layout),
+                        n, k,
                         alpha.as_const(),
                         a.as_ptr().as_c_ptr(), a.lead_dim(),
                         b.as_ptr().as_c_ptr(), b.lead_dim(),
@@ -291,3 +545,50 @@ macro_rules! syrk_impl(($($t: ident), +) => (
 ));
 
 syrk_impl!(f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod syrk_tests {
+    use crate::attribute::{Symmetry, Transpose};
+    use crate::matrix::ops::Syrk;
+    use crate::matrix::tests::{Cm, M};
+
+    #[test]
+    fn transpose() {
+        let a = M(3, 2, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+        let mut c = M(2, 2, vec![0.0; 4]);
+        Syrk::syrk(Symmetry::Lower, Transpose::Trans, &1f32, &a, &0f32, &mut c);
+
+        assert_eq!(c.2[0], 35.0);
+        assert_eq!(c.2[2..], [44.0, 56.0]);
+    }
+
+    #[test]
+    fn mixed_orders() {
+        let a = Cm(3, 2, vec![1.0, 3.0, 5.0, 2.0, 4.0, 6.0]);
+
+        let mut c = M(3, 3, vec![0.0; 9]);
+        Syrk::syrk(
+            Symmetry::Lower,
+            Transpose::NoTrans,
+            &1f64,
+            &a,
+            &0f64,
+            &mut c,
+        );
+        assert_eq!([c.2[0], c.2[3], c.2[4]], [5.0, 11.0, 25.0]);
+        assert_eq!([c.2[6], c.2[7], c.2[8]], [17.0, 39.0, 61.0]);
+
+        let mut c = Cm(3, 3, vec![0.0; 9]);
+        Syrk::syrk(
+            Symmetry::Lower,
+            Transpose::NoTrans,
+            &1f64,
+            &a,
+            &0f64,
+            &mut c,
+        );
+        assert_eq!([c.2[0], c.2[1], c.2[2]], [5.0, 11.0, 17.0]);
+        assert_eq!([c.2[4], c.2[5], c.2[8]], [25.0, 39.0, 61.0]);
+    }
+}
@@ -11,10 +11,16 @@ use crate::pointer::CPtr;
 use crate::scalar::Scalar;
 use crate::vector::Vector;
 use num_complex::{Complex, Complex32, Complex64};
+use std::cmp;
 
 /// General multiply with vector
 ///
 /// A ← αA<sup>OP</sup>x + βy
+///
+/// Like the vector ops, mismatched lengths are cut to the shorter one:
+/// only the leading block of A<sup>OP</sup> with `min(x.len(), cols)`
+/// columns and `min(y.len(), rows)` rows is used, and the rest of `x` and
+/// `y` is left alone.
 pub trait Gemv: Sized {
     fn gemv<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
         trans: Transpose,
@@ -30,9 +36,15 @@ macro_rules! gemv_impl(($($t: ident), +) => (
     $(
         impl Gemv for $t {
             fn gemv<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(trans: Transpose, alpha: &$t, a: &dyn Matrix<$t>, x: &V, beta: &$t, y: &mut W){
+                // The leading block keeps the pointer and leading dimension.
+                let (rows, cols) = match trans {
+                    Transpose::NoTrans => (cmp::min(y.len(), a.rows()), cmp::min(x.len(), a.cols())),
+                    _ => (cmp::min(x.len(), a.rows()), cmp::min(y.len(), a.cols())),
+                };
+
                 unsafe {
                     prefix!($t, gemv)(a.order(), trans,
-                        a.rows(), a.cols(),
+                        rows, cols,
                         alpha.as_const(),
                         a.as_ptr().as_c_ptr(), a.lead_dim(),
                         x.as_ptr().as_c_ptr(), x.inc(),
@@ -64,6 +76,16 @@ mod gemv_tests {
         assert_eq!(y, vec![0.0, 0.0]);
     }
 
+    #[test]
+    fn identity() {
+        let a = M(3, 3, vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]);
+        let x = vec![4.0, -1.0, 2.5];
+        let mut y = vec![0.0; 3];
+
+        Gemv::gemv(Transpose::NoTrans, &1f64, &a, &x, &0f64, &mut y);
+        assert_eq!(y, x);
+    }
+
     #[test]
     fn non_square() {
         let a = M(2, 3, vec![1.0, -3.0, 1.0, 2.0, -6.0, 2.0]);
@@ -87,11 +109,37 @@ mod gemv_tests {
 
         assert_eq!(y, vec![0.0, 0.0]);
     }
+
+    #[test]
+    fn longer_vectors() {
+        let a = M(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let x = vec![1.0, 1.0, 1.0, 100.0];
+        let mut y = vec![1.0, 1.0, 7.0];
+
+        Gemv::gemv(Transpose::NoTrans, &1f32, &a, &x, &1f32, &mut y);
+        assert_eq!(y, vec![7.0, 16.0, 7.0]);
+    }
+
+    #[test]
+    fn shorter_vectors() {
+        let a = M(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let x = vec![1.0, 1.0];
+
+        let mut y = vec![0.0; 2];
+        Gemv::gemv(Transpose::NoTrans, &1f32, &a, &x, &0f32, &mut y);
+        assert_eq!(y, vec![3.0, 9.0]);
+
+        let mut y = vec![0.0; 2];
+        Gemv::gemv(Transpose::Trans, &1f32, &a, &x, &0f32, &mut y);
+        assert_eq!(y, vec![5.0, 7.0]);
+    }
 }
 
 /// Symmetric multiply with vector
 ///
 /// A ← αAx + βy
+///
+/// Only the triangle of `a` named by `symmetry` is read.
 pub trait Symv: Sized {
     fn symv<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
         symmetry: Symmetry,
@@ -106,6 +154,9 @@ pub trait Symv: Sized {
 /// Hermitian multiply with vector
 ///
 /// A ← αAx + βy
+///
+/// Only the triangle of `a` named by `symmetry` is read, and the imaginary
+/// parts of its diagonal are taken to be zero.
 pub trait Hemv: Sized {
     fn hemv<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
         symmetry: Symmetry,
@@ -121,6 +172,10 @@ macro_rules! symv_impl(($trait_name: ident, $fn_name: ident, $($t: ident), +) =>
     $(
         impl $trait_name for $t {
             fn $fn_name<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(symmetry: Symmetry, alpha: &$t, a: &dyn Matrix<$t>, x: &V, beta: &$t, y: &mut W){
+                if x.len() < a.rows() || y.len() < a.rows() {
+                    panic!("Dimension mismatch");
+                }
+
                 unsafe {
                     prefix!($t, $fn_name)(a.order(), symmetry,
                         a.rows(),
@@ -140,7 +195,7 @@ symv_impl!(Hemv, hemv, Complex32, Complex64);
 
 #[cfg(test)]
 mod symv_tests {
-    use crate::attribute::{Symmetry, Transpose};
+    use crate::attribute::{Symmetry, Transpose, Uplo};
     use crate::matrix::tests::M;
     use crate::matrix_vector::ops::{Gemv, Symv};
 
@@ -179,11 +234,99 @@ mod symv_tests {
         assert_eq!(gemv, symv_upper);
         assert_eq!(gemv, symv_lower);
     }
+
+    #[test]
+    fn upper_triangle_only() {
+        let full = M(3, 3, vec![2.0, -1.0, 3.0, -1.0, 4.0, 0.5, 3.0, 0.5, 1.0]);
+        let upper = M(3, 3, vec![2.0, -1.0, 3.0, 0.0, 4.0, 0.5, 0.0, 0.0, 1.0]);
+        let x = vec![1.0, -2.0, 0.5];
+
+        let mut expected = vec![0.0; 3];
+        Gemv::gemv(Transpose::NoTrans, &1f64, &full, &x, &0.0, &mut expected);
+
+        let mut y = vec![1.0; 3];
+        Symv::symv(Uplo::Upper, &1f64, &upper, &x, &0.0, &mut y);
+        assert_eq!(y, expected);
+    }
+}
+
+#[cfg(test)]
+mod hemv_tests {
+    use crate::attribute::{Transpose, Uplo};
+    use crate::matrix::tests::M;
+    use crate::matrix_vector::ops::{Gemv, Hemv};
+    use crate::vector::ops::Dotc;
+    use num_complex::Complex;
+
+    #[test]
+    fn ignores_diagonal_imaginary() {
+        let full = M(
+            2,
+            2,
+            vec![
+                Complex::new(2.0, 0.0),
+                Complex::new(1.0, -2.0),
+                Complex::new(1.0, 2.0),
+                Complex::new(3.0, 0.0),
+            ],
+        );
+        let stored = M(
+            2,
+            2,
+            vec![
+                Complex::new(2.0, 5.0),
+                Complex::new(1.0, -2.0),
+                Complex::new(9.0, 9.0),
+                Complex::new(3.0, -7.0),
+            ],
+        );
+        let x = vec![Complex::new(1.0, 1.0), Complex::new(-2.0, 0.5)];
+        let one = Complex::new(1f64, 0.0);
+        let zero = Complex::new(0f64, 0.0);
+
+        let mut expected = vec![zero; 2];
+        Gemv::gemv(Transpose::NoTrans, &one, &full, &x, &zero, &mut expected);
+
+        let mut y = vec![zero; 2];
+        Hemv::hemv(Uplo::Upper, &one, &stored, &x, &zero, &mut y);
+        assert_eq!(y, expected);
+    }
+
+    #[test]
+    fn real_quadratic_form() {
+        let a = M(
+            2,
+            2,
+            vec![
+                Complex::new(4.0, 0.0),
+                Complex::new(0.0, 0.0),
+                Complex::new(2.0, 3.0),
+                Complex::new(-1.0, 0.0),
+            ],
+        );
+        let x = vec![Complex::new(1f32, 0.0), Complex::new(2.0, 0.0)];
+
+        // For real x, Ax itself is complex, but x^H A x is real.
+        let mut y = vec![Complex::new(0f32, 0.0); 2];
+        Hemv::hemv(
+            Uplo::Lower,
+            &Complex::new(1.0, 0.0),
+            &a,
+            &x,
+            &Complex::new(0.0, 0.0),
+            &mut y,
+        );
+        assert_eq!(y, vec![Complex::new(8.0, -6.0), Complex::new(0.0, 3.0)]);
+        assert_eq!(Dotc::dotc(&x, &y), Complex::new(8.0, 0.0));
+    }
 }
 
 /// General rank-1 update
 ///
 /// A ← A + αxy<sup>T</sup>
+///
+/// The shape comes from `a`; panics if `x` has fewer than `rows` or `y`
+/// fewer than `cols` elements.
 pub trait Ger: Sized {
     fn ger<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
         alpha: &Self,
@@ -193,6 +336,23 @@ pub trait Ger: Sized {
     );
 }
 
+/// General rank-1 update without conjugation
+///
+/// A ← A + αxy<sup>T</sup>
+///
+/// The same as `ger`, under the name BLAS gives it for complex types so it
+/// can be written next to `gerc`.
+pub trait Geru: Ger {
+    fn geru<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        alpha: &Self,
+        x: &V,
+        y: &W,
+        a: &mut dyn Matrix<Self>,
+    ) {
+        Ger::ger(alpha, x, y, a);
+    }
+}
+
 /// General rank-1 update (using hermitian conjugate)
 ///
 /// A ← A + αxy<sup>H</sup>
@@ -211,6 +371,10 @@ macro_rules! ger_impl(
     ($trait_name: ident, $fn_name: ident, $t: ty, $ger_fn: expr) => (
         impl $trait_name for $t {
             fn $fn_name<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(alpha: &$t, x: &V, y: &W, a: &mut dyn Matrix<$t>) {
+                if x.len() < a.rows() || y.len() < a.cols() {
+                    panic!("Dimension mismatch");
+                }
+
                 unsafe {
                     $ger_fn(a.order(),
                         a.rows(), a.cols(),
@@ -229,6 +393,11 @@ ger_impl!(Ger, ger, f64, cblas_d::ger);
 ger_impl!(Ger, ger, Complex32, cblas_c::geru);
 ger_impl!(Ger, ger, Complex64, cblas_z::geru);
 
+impl Geru for f32 {}
+impl Geru for f64 {}
+impl Geru for Complex32 {}
+impl Geru for Complex64 {}
+
 impl Gerc for f32 {}
 impl Gerc for f64 {}
 ger_impl!(Gerc, gerc, Complex32, cblas_c::gerc);
@@ -237,7 +406,8 @@ ger_impl!(Gerc, gerc, Complex64, cblas_z::gerc);
 #[cfg(test)]
 mod ger_tests {
     use crate::matrix::tests::M;
-    use crate::matrix_vector::ops::Ger;
+    use crate::matrix_vector::ops::{Ger, Gerc, Geru};
+    use num_complex::Complex;
     use std::iter::repeat;
 
     #[test]
@@ -251,6 +421,42 @@ mod ger_tests {
         let result = vec![6.0, 12.0, -2.0, 3.0, 6.0, -1.0, 12.0, 24.0, -4.0];
         assert_eq!(a.2, result);
     }
+
+    #[test]
+    fn outer_product() {
+        let mut a = M(2, 3, vec![0.0; 6]);
+        let x = vec![1.0, 2.0];
+        let y = vec![3.0, 4.0, 5.0];
+
+        Ger::ger(&1f64, &x, &y, &mut a);
+        assert_eq!(a.2, vec![3.0, 4.0, 5.0, 6.0, 8.0, 10.0]);
+    }
+
+    #[test]
+    fn complex_conjugation() {
+        let i = Complex::new(0f64, 1.0);
+        let one = Complex::new(1f64, 0.0);
+        let x = vec![one, i];
+        let y = vec![i, one];
+
+        let mut u = M(2, 2, vec![Complex::new(0.0, 0.0); 4]);
+        Geru::geru(&one, &x, &y, &mut u);
+        assert_eq!(u.2, vec![i, one, -one, i]);
+
+        let mut c = M(2, 2, vec![Complex::new(0.0, 0.0); 4]);
+        Gerc::gerc(&one, &x, &y, &mut c);
+        assert_eq!(c.2, vec![-i, one, one, i]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_y() {
+        let mut a = M(2, 3, vec![0.0; 6]);
+        let x = vec![1.0, 2.0];
+        let y = vec![3.0, 4.0];
+
+        Ger::ger(&1f32, &x, &y, &mut a);
+    }
 }
 
 /// Symmetric rank-1 update
@@ -443,6 +649,125 @@ macro_rules! sbmv_impl(($trait_name: ident, $fn_name: ident, $($t: ident), +) =>
 sbmv_impl!(Sbmv, sbmv, f32, f64);
 sbmv_impl!(Hbmv, hbmv, Complex32, Complex64);
 
+/// Triangular matrix multiply with vector
+///
+/// x ← A<sup>OP</sup>x
+///
+/// Only the triangle of `a` named by `symmetry` is read, and with
+/// `Diagonal::Unit` not its diagonal either.
+pub trait Trmv: Sized {
+    fn trmv<V: ?Sized + Vector<Self>>(
+        symmetry: Symmetry,
+        trans: Transpose,
+        diagonal: Diagonal,
+        a: &dyn Matrix<Self>,
+        x: &mut V,
+    );
+}
+
+/// Solve triangular matrix system
+///
+/// x ← A<sup>-1 OP</sup>x
+///
+/// Overwrites the right-hand side `x` with the solution. As BLAS does not
+/// check for singularity, a zero on the diagonal gives infinities or NaNs.
+pub trait Trsv: Sized {
+    fn trsv<V: ?Sized + Vector<Self>>(
+        symmetry: Symmetry,
+        trans: Transpose,
+        diagonal: Diagonal,
+        a: &dyn Matrix<Self>,
+        x: &mut V,
+    );
+}
+
+macro_rules! trmv_impl(($trait_name: ident, $fn_name: ident, $($t: ident), +) => (
+    $(
+        impl $trait_name for $t {
+            fn $fn_name<V: ?Sized + Vector<Self>>(symmetry: Symmetry, trans: Transpose, diagonal: Diagonal, a: &dyn Matrix<$t>, x: &mut V) {
+                if a.rows() != a.cols() || x.len() < a.rows() {
+                    panic!("Dimension mismatch");
+                }
+
+                unsafe {
+                    prefix!($t, $fn_name)(a.order(), symmetry,
+                        trans, diagonal,
+                        a.rows(),
+                        a.as_ptr().as_c_ptr(), a.lead_dim(),
+                        x.as_mut_ptr().as_c_ptr(), x.inc());
+                }
+            }
+        }
+    )+
+));
+
+trmv_impl!(Trmv, trmv, f32, f64, Complex32, Complex64);
+trmv_impl!(Trsv, trsv, f32, f64, Complex32, Complex64);
+
+#[cfg(test)]
+mod trmv_tests {
+    use crate::attribute::{Diagonal, Symmetry, Transpose};
+    use crate::matrix::tests::M;
+    use crate::matrix_vector::ops::{Trmv, Trsv};
+
+    #[test]
+    fn upper_solve() {
+        // The strict lower triangle is never read.
+        let a = M(3, 3, vec![2.0, 1.0, -1.0, 9.0, 4.0, 3.0, 9.0, 9.0, 0.5]);
+        let mut x = vec![2.0, 14.0, 1.0];
+
+        Trsv::trsv(
+            Symmetry::Upper,
+            Transpose::NoTrans,
+            Diagonal::NonUnit,
+            &a,
+            &mut x,
+        );
+        assert_eq!(x, vec![1.0, 2.0, 2.0]);
+
+        Trmv::trmv(
+            Symmetry::Upper,
+            Transpose::NoTrans,
+            Diagonal::NonUnit,
+            &a,
+            &mut x,
+        );
+        assert_eq!(x, vec![2.0, 14.0, 1.0]);
+    }
+
+    #[test]
+    fn inverse_operations() {
+        let a = M(3, 3, vec![1.0, 0.0, 0.0, 0.5, 3.0, 0.0, -2.0, 1.5, 4.0]);
+        let b = vec![1f64, -1.0, 2.5];
+
+        for &trans in &[Transpose::NoTrans, Transpose::Trans] {
+            for &diagonal in &[Diagonal::NonUnit, Diagonal::Unit] {
+                let mut x = b.clone();
+                Trsv::trsv(Symmetry::Lower, trans, diagonal, &a, &mut x);
+                Trmv::trmv(Symmetry::Lower, trans, diagonal, &a, &mut x);
+                for (xi, bi) in x.iter().zip(&b) {
+                    assert!((xi - bi).abs() < 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn short_x() {
+        let a = M(2, 2, vec![1f32, 0.0, 0.0, 1.0]);
+        let mut x = vec![1f32];
+
+        Trmv::trmv(
+            Symmetry::Upper,
+            Transpose::NoTrans,
+            Diagonal::Unit,
+            &a,
+            &mut x,
+        );
+    }
+}
+
 /// Triangular band matrix multiply with vector
 ///
 /// A ← A<sup>OP</sup>x
@@ -0,0 +1,207 @@
+// Copyright 2015 Michael Yang. All rights reserved.
+// Use of this source code is governed by a MIT-style
+// license that can be found in the LICENSE file.
+
+//! Level 1 routines that reject operands of different lengths.
+//!
+//! The routines in `vector::ops` quietly work on the shorter of their two
+//! vectors. The functions here check first and return
+//! `BlasError::DimensionMismatch` instead, with `expected` the length of the
+//! first vector argument and `found` the length of the second. Vectors that
+//! are written to must also have a non-zero stride, or the routine returns
+//! `BlasError::InvalidIncrement`. Nothing is read or written when a check
+//! fails.
+
+use crate::error::BlasError;
+use crate::vector::ops::{Axpy, Copy, Dot, Dotc, Rot, Swap};
+use crate::vector::Vector;
+
+fn same_len<T, V, W>(x: &V, y: &W) -> Result<(), BlasError>
+where
+    V: ?Sized + Vector<T>,
+    W: ?Sized + Vector<T>,
+{
+    if x.len() == y.len() {
+        Ok(())
+    } else {
+        Err(BlasError::DimensionMismatch {
+            expected: x.len() as usize,
+            found: y.len() as usize,
+        })
+    }
+}
+
+fn writable<T, V>(x: &V) -> Result<(), BlasError>
+where
+    V: ?Sized + Vector<T>,
+{
+    if x.inc() == 0 {
+        Err(BlasError::InvalidIncrement)
+    } else {
+        Ok(())
+    }
+}
+
+/// `Copy::copy`, requiring `dst` to be as long as `src`.
+pub fn copy<T, V, W>(src: &V, dst: &mut W) -> Result<(), BlasError>
+where
+    T: Copy,
+    V: ?Sized + Vector<T>,
+    W: ?Sized + Vector<T>,
+{
+    same_len(src, dst)?;
+    writable(dst)?;
+    Copy::copy(src, dst);
+    Ok(())
+}
+
+/// `Axpy::axpy`, requiring `y` to be as long as `x`.
+pub fn axpy<T, V, W>(alpha: &T, x: &V, y: &mut W) -> Result<(), BlasError>
+where
+    T: Axpy,
+    V: ?Sized + Vector<T>,
+    W: ?Sized + Vector<T>,
+{
+    same_len(x, y)?;
+    writable(y)?;
+    Axpy::axpy(alpha, x, y);
+    Ok(())
+}
+
+/// `Swap::swap`, requiring `y` to be as long as `x`.
+pub fn swap<T, V, W>(x: &mut V, y: &mut W) -> Result<(), BlasError>
+where
+    T: Swap,
+    V: ?Sized + Vector<T>,
+    W: ?Sized + Vector<T>,
+{
+    same_len(x, y)?;
+    writable(x)?;
+    writable(y)?;
+    Swap::swap(x, y);
+    Ok(())
+}
+
+/// `Dot::dot`, requiring `y` to be as long as `x`.
+pub fn dot<T, V, W>(x: &V, y: &W) -> Result<T, BlasError>
+where
+    T: Dot,
+    V: ?Sized + Vector<T>,
+    W: ?Sized + Vector<T>,
+{
+    same_len(x, y)?;
+    Ok(Dot::dot(x, y))
+}
+
+/// `Dotc::dotc`, requiring `y` to be as long as `x`.
+pub fn dotc<T, V, W>(x: &V, y: &W) -> Result<T, BlasError>
+where
+    T: Dotc,
+    V: ?Sized + Vector<T>,
+    W: ?Sized + Vector<T>,
+{
+    same_len(x, y)?;
+    Ok(Dotc::dotc(x, y))
+}
+
+/// `Rot::rot`, requiring `y` to be as long as `x`.
+pub fn rot<T, V, W>(x: &mut V, y: &mut W, cos: &T::Real, sin: &T::Real) -> Result<(), BlasError>
+where
+    T: Rot,
+    V: ?Sized + Vector<T>,
+    W: ?Sized + Vector<T>,
+{
+    same_len(x, y)?;
+    writable(x)?;
+    writable(y)?;
+    Rot::rot(x, y, cos, sin);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::BlasError;
+    use crate::vector::checked;
+    use crate::vector::Strided;
+    use num_complex::Complex;
+
+    #[test]
+    fn dot() {
+        let x = vec![1f32, 2.0, 3.0];
+
+        assert_eq!(checked::dot(&x, &vec![4.0, 5.0, 6.0]), Ok(32.0));
+        assert_eq!(
+            checked::dot(&x, &vec![4.0, 5.0]),
+            Err(BlasError::DimensionMismatch {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn dotc() {
+        let x = vec![Complex::new(0f64, 1.0)];
+
+        assert_eq!(checked::dotc(&x, &x), Ok(Complex::new(1.0, 0.0)));
+        assert!(checked::dotc(&x, &vec![]).is_err());
+    }
+
+    #[test]
+    fn axpy() {
+        let x = vec![1f64, 2.0];
+        let mut y = vec![10f64, 20.0];
+
+        assert_eq!(checked::axpy(&2.0, &x, &mut y), Ok(()));
+        assert_eq!(y, vec![12.0, 24.0]);
+
+        let mut longer = vec![0f64; 3];
+        assert_eq!(
+            checked::axpy(&2.0, &x, &mut longer),
+            Err(BlasError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(longer, vec![0.0; 3]);
+    }
+
+    #[test]
+    fn strided() {
+        let x = [1f32, 0.0, 2.0, 0.0];
+        let evens = unsafe { Strided::new(x.as_ptr(), 2, 2) };
+        let mut y = vec![0f32; 2];
+
+        assert_eq!(checked::copy(&evens, &mut y), Ok(()));
+        assert_eq!(y, vec![1.0, 2.0]);
+        assert!(checked::copy(&x[..], &mut y).is_err());
+    }
+
+    #[test]
+    fn swap_and_rot() {
+        let mut x = vec![1f32, 2.0];
+        let mut y = vec![3f32];
+
+        assert!(checked::swap(&mut x, &mut y).is_err());
+        assert!(checked::rot(&mut x, &mut y, &0.0, &1.0).is_err());
+        assert_eq!((x, y), (vec![1.0, 2.0], vec![3.0]));
+    }
+
+    #[test]
+    fn zero_increment() {
+        let x = vec![1f32, 2.0];
+        let mut y = [5f32];
+        let mut same = unsafe { Strided::new(y.as_mut_ptr(), 2, 0) };
+
+        assert_eq!(
+            checked::copy(&x, &mut same),
+            Err(BlasError::InvalidIncrement)
+        );
+        assert_eq!(
+            checked::axpy(&1.0, &x, &mut same),
+            Err(BlasError::InvalidIncrement)
+        );
+        assert_eq!(checked::dot(&x, &same), Ok(15.0));
+        assert_eq!(y, [5.0]);
+    }
+}
@@ -165,6 +165,7 @@ pub mod cblas_c {
     pub use self::cblas_cdotc_sub as dotc_sub;
     pub use self::cblas_cdotu_sub as dotu_sub;
     pub use self::cblas_cscal as scal;
+    pub use self::cblas_csrot as rot;
     pub use self::cblas_csscal as sscal;
     pub use self::cblas_cswap as swap;
 
@@ -181,6 +182,15 @@ pub mod cblas_c {
         pub fn cblas_cscal(n: u32, alpha: *const c_void, x: *mut c_void, inc_x: u32);
         pub fn cblas_csscal(n: u32, alpha: c_float, x: *mut c_void, inc_x: u32);
         pub fn cblas_cswap(n: u32, x: *mut c_void, inc_x: u32, y: *mut c_void, inc_y: u32);
+        pub fn cblas_csrot(
+            n: u32,
+            x: *mut c_void,
+            inc_x: u32,
+            y: *mut c_void,
+            inc_y: u32,
+            c: c_float,
+            s: c_float,
+        );
         pub fn cblas_cdotu_sub(
             n: u32,
             x: *const c_void,
@@ -207,6 +217,7 @@ pub mod cblas_z {
     pub use self::cblas_zcopy as copy;
     pub use self::cblas_zdotc_sub as dotc_sub;
     pub use self::cblas_zdotu_sub as dotu_sub;
+    pub use self::cblas_zdrot as rot;
     pub use self::cblas_zdscal as dscal;
     pub use self::cblas_zscal as scal;
     pub use self::cblas_zswap as swap;
@@ -224,6 +235,15 @@ pub mod cblas_z {
         pub fn cblas_zscal(n: u32, alpha: *const c_void, x: *mut c_void, inc_x: u32);
         pub fn cblas_zdscal(n: u32, alpha: c_double, x: *mut c_void, inc_x: u32);
         pub fn cblas_zswap(n: u32, x: *mut c_void, inc_x: u32, y: *mut c_void, inc_y: u32);
+        pub fn cblas_zdrot(
+            n: u32,
+            x: *mut c_void,
+            inc_x: u32,
+            y: *mut c_void,
+            inc_y: u32,
+            c: c_double,
+            s: c_double,
+        );
         pub fn cblas_zdotu_sub(
             n: u32,
             x: *const c_void,
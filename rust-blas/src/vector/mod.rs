@@ -6,7 +6,10 @@
 use crate::vector::ops::{Asum, Axpy, Copy, Dot, Iamax, Nrm2, Scal};
 use num::traits::NumCast;
 use num_complex::{Complex32, Complex64};
+use std::marker::PhantomData;
+use std::ops::Deref;
 
+pub mod checked;
 pub mod ll;
 pub mod ops;
 
@@ -27,6 +30,245 @@ pub trait Vector<T> {
     fn is_empty(&self) -> bool {
         self.len() == 0u32
     }
+    /// Splits the vector into read-only views of elements `[0, mid)` and
+    /// `[mid, len)`. Panics if `mid > len` or `inc` is not 1.
+    fn split_at(&self, mid: usize) -> (VectorView<'_, T>, VectorView<'_, T>) {
+        VectorView::new(self).split_at(mid)
+    }
+    /// Like `split_at`, but both views may be written through.
+    fn split_at_mut(&mut self, mid: usize) -> (VectorViewMut<'_, T>, VectorViewMut<'_, T>) {
+        VectorViewMut::new(self).split(mid)
+    }
+}
+
+/// A strided window into memory owned by something else.
+///
+/// Used internally to hand rows and columns of a matrix to the vector
+/// routines without copying them out first.
+pub(crate) struct Strided<T> {
+    ptr: *mut T,
+    len: u32,
+    inc: u32,
+}
+
+impl<T> Strided<T> {
+    /// Creates a view of `len` elements starting at `ptr`, `inc` apart.
+    ///
+    /// # Safety
+    /// The caller guarantees the whole range is valid for as long as the
+    /// view is used, and only writes through views of mutable memory.
+    pub(crate) unsafe fn new(ptr: *const T, len: u32, inc: u32) -> Strided<T> {
+        Strided {
+            ptr: ptr as *mut T,
+            len,
+            inc,
+        }
+    }
+
+    /// Drops the first `n` elements of the view.
+    pub(crate) fn skip(self, n: u32) -> Strided<T> {
+        let n = if n > self.len { self.len } else { n };
+
+        Strided {
+            ptr: unsafe { self.ptr.add((n * self.inc) as usize) },
+            len: self.len - n,
+            inc: self.inc,
+        }
+    }
+
+    /// Keeps only the first `n` elements of the view.
+    pub(crate) fn take(self, n: u32) -> Strided<T> {
+        Strided {
+            len: if n < self.len { n } else { self.len },
+            ..self
+        }
+    }
+
+    pub(crate) fn get(&self, i: u32) -> T
+    where
+        T: std::marker::Copy,
+    {
+        assert!(
+            i < self.len,
+            "Index {} out of range for length {}",
+            i,
+            self.len
+        );
+        unsafe { *self.ptr.add((i * self.inc) as usize) }
+    }
+
+    pub(crate) fn set(&mut self, i: u32, value: T) {
+        assert!(
+            i < self.len,
+            "Index {} out of range for length {}",
+            i,
+            self.len
+        );
+        unsafe {
+            *self.ptr.add((i * self.inc) as usize) = value;
+        }
+    }
+}
+
+impl<T> Vector<T> for Strided<T> {
+    fn inc(&self) -> u32 {
+        self.inc
+    }
+
+    fn len(&self) -> u32 {
+        self.len
+    }
+
+    fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr
+    }
+}
+
+/// A borrowed, read-only view of a vector.
+///
+/// The view only derefs to `&dyn Vector`, so it can be passed where BLAS
+/// reads (`&*view`) but never where it writes.
+pub struct VectorView<'a, T> {
+    inner: Strided<T>,
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> VectorView<'a, T> {
+    /// Views all of `x`, with its stride.
+    pub fn new<V: ?Sized + Vector<T>>(x: &'a V) -> VectorView<'a, T> {
+        VectorView {
+            inner: unsafe { Strided::new(x.as_ptr(), x.len(), x.inc()) },
+            marker: PhantomData,
+        }
+    }
+
+    /// Splits the view into elements `[0, mid)` and `[mid, len)`.
+    ///
+    /// Panics if `mid > len` or the view is not contiguous.
+    pub fn split_at(&self, mid: usize) -> (VectorView<'a, T>, VectorView<'a, T>) {
+        let Strided { ptr, len, .. } = self.inner;
+        let mid = split_point(len, self.inner.inc, mid);
+        let head = VectorView {
+            inner: Strided {
+                ptr,
+                len: mid,
+                inc: 1,
+            },
+            marker: PhantomData,
+        };
+        let tail = VectorView {
+            inner: Strided {
+                ptr: unsafe { ptr.add(mid as usize) },
+                len: len - mid,
+                inc: 1,
+            },
+            marker: PhantomData,
+        };
+
+        (head, tail)
+    }
+}
+
+impl<'a, T> Clone for VectorView<'a, T> {
+    fn clone(&self) -> Self {
+        let Strided { ptr, len, inc } = self.inner;
+
+        VectorView {
+            inner: Strided { ptr, len, inc },
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T: 'a> Deref for VectorView<'a, T> {
+    type Target = dyn Vector<T> + 'a;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// A borrowed view of a vector that may be written through.
+pub struct VectorViewMut<'a, T> {
+    ptr: *mut T,
+    len: u32,
+    inc: u32,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> VectorViewMut<'a, T> {
+    /// Views all of `x`, with its stride.
+    pub fn new<V: ?Sized + Vector<T>>(x: &'a mut V) -> VectorViewMut<'a, T> {
+        VectorViewMut {
+            ptr: x.as_mut_ptr(),
+            len: x.len(),
+            inc: x.inc(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Splits the view into elements `[0, mid)` and `[mid, len)`, both
+    /// writable.
+    ///
+    /// Panics if `mid > len` or the view is not contiguous.
+    pub fn split_at_mut(&mut self, mid: usize) -> (VectorViewMut<'_, T>, VectorViewMut<'_, T>) {
+        let view = VectorViewMut {
+            marker: PhantomData,
+            ..*self
+        };
+        view.split(mid)
+    }
+
+    fn split(self, mid: usize) -> (VectorViewMut<'a, T>, VectorViewMut<'a, T>) {
+        let mid = split_point(self.len, self.inc, mid);
+        let head = VectorViewMut {
+            ptr: self.ptr,
+            len: mid,
+            inc: 1,
+            marker: PhantomData,
+        };
+        let tail = VectorViewMut {
+            ptr: unsafe { self.ptr.add(mid as usize) },
+            len: self.len - mid,
+            inc: 1,
+            marker: PhantomData,
+        };
+
+        (head, tail)
+    }
+}
+
+impl<'a, T> Vector<T> for VectorViewMut<'a, T> {
+    fn inc(&self) -> u32 {
+        self.inc
+    }
+
+    fn len(&self) -> u32 {
+        self.len
+    }
+
+    fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr
+    }
+}
+
+fn split_point(len: u32, inc: u32, mid: usize) -> u32 {
+    assert_eq!(inc, 1, "Only contiguous vectors can be split");
+    assert!(
+        mid <= len as usize,
+        "Split point {} out of range for length {}",
+        mid,
+        len
+    );
+    mid as u32
 }
 
 impl<'a, T> Into<Vec<T>> for &'a dyn Vector<T>
@@ -124,3 +366,72 @@ operations_impl!(Vec, f32, f64, Complex32, Complex64);
 //impl<'a> VectorOperations<f64> for &'a [f64] {}
 //impl<'a> VectorOperations<Complex32> for &'a [Complex32] {}
 //impl<'a> VectorOperations<Complex64> for &'a [Complex64] {}
+
+#[cfg(test)]
+mod view_tests {
+    use crate::vector::ops::{Asum, Scal};
+    use crate::vector::{Strided, Vector, VectorView, VectorViewMut};
+
+    #[test]
+    fn split_at() {
+        let x = vec![1f32, -2.0, 3.0, -4.0, 5.0];
+
+        let (a, b) = Vector::split_at(&x, 2);
+        assert_eq!((a.len(), b.len()), (2, 3));
+        assert_eq!(Asum::asum(&*a), 3.0);
+        assert_eq!(Asum::asum(&*b), 12.0);
+        assert_eq!(Asum::asum(&*a) + Asum::asum(&*b), Asum::asum(&x));
+    }
+
+    #[test]
+    fn split_at_ends() {
+        let x = vec![1f64, 2.0];
+
+        let (a, b) = VectorView::new(&x).split_at(0);
+        assert_eq!((a.len(), b.len()), (0, 2));
+        let (a, b) = VectorView::new(&x).split_at(2);
+        assert_eq!((a.len(), b.len()), (2, 0));
+    }
+
+    #[test]
+    fn split_at_mut() {
+        let mut x = vec![1f64, 2.0, 3.0, 4.0];
+
+        {
+            let mut view = VectorViewMut::new(&mut x);
+            let (mut a, mut b) = view.split_at_mut(1);
+            Scal::scal(&-1.0, &mut a);
+            Scal::scal(&10.0, &mut b);
+        }
+        assert_eq!(x, vec![-1.0, 20.0, 30.0, 40.0]);
+    }
+
+    #[test]
+    fn vector_split_at_mut() {
+        let mut x = vec![1f32, 2.0, 3.0];
+
+        {
+            let (mut a, mut b) = Vector::split_at_mut(&mut x, 2);
+            Scal::scal(&2.0, &mut a);
+            Scal::scal(&0.0, &mut b);
+        }
+        assert_eq!(x, vec![2.0, 4.0, 0.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_past_end() {
+        let x = vec![1f32, 2.0];
+
+        let _ = VectorView::new(&x).split_at(3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_strided() {
+        let x = [1f32, 2.0, 3.0, 4.0];
+        let evens = unsafe { Strided::new(x.as_ptr(), 2, 2) };
+
+        let _ = VectorView::new(&evens).split_at(1);
+    }
+}
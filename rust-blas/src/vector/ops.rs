@@ -4,19 +4,69 @@
 
 //! Wrappers for vector functions.
 
+use crate::attribute::Order;
 use crate::default::Default;
-use crate::matrix::Matrix;
+use crate::matrix::{col, col_mut, row, row_mut, Matrix};
 use crate::pointer::CPtr;
 use crate::scalar::Scalar;
 use crate::vector::ll::*;
-use crate::vector::Vector;
+use crate::vector::{Strided, Vector};
 use num_complex::{Complex, Complex32, Complex64};
 use std::cmp;
+use std::mem;
+
+/// Whether copying `n` elements from `src` to `dst` with the given strides
+/// would read some element of `src` through a different index than the one
+/// writing it, so that the result depends on the order BLAS copies in.
+///
+/// Copying a vector onto itself is harmless and not reported.
+fn aliases<T>(src: *const T, inc_src: u32, dst: *const T, inc_dst: u32, n: u32) -> bool {
+    let size = mem::size_of::<T>();
+    if n == 0 || size == 0 {
+        return false;
+    }
+
+    let (s, d) = (src as usize, dst as usize);
+    let span = |inc: u32| ((n as usize - 1) * inc as usize + 1) * size;
+    if s + span(inc_src) <= d || d + span(inc_dst) <= s {
+        return false;
+    }
+
+    (0..n as usize).any(|i| {
+        let addr = d + i * inc_dst as usize * size;
+        if addr + size <= s || addr >= s + span(inc_src) {
+            return false;
+        }
+        if addr < s {
+            // Straddles the first element of `src`.
+            return true;
+        }
+
+        let (off, misaligned) = ((addr - s) / size, (addr - s) % size);
+        match inc_src as usize {
+            _ if misaligned != 0 => true,
+            0 => off == 0 && n > 1,
+            inc => {
+                let (j, rem) = (off / inc, off % inc);
+                rem == 0 && j < n as usize && j != i
+            }
+        }
+    })
+}
 
 pub trait Copy: Sized {
     /// Copies `src.len()` elements of `src` into `dst`.
+    ///
+    /// In debug builds this panics if `src` and `dst` overlap in a way that
+    /// makes the result depend on the order the elements are copied in,
+    /// which BLAS leaves unspecified.
     fn copy<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(src: &V, dst: &mut W);
-    /// Copies the entire matrix `dst` into `src`.
+    /// Copies the entire matrix `src` into `dst`, which must have the same
+    /// shape.
+    ///
+    /// Both matrices are addressed through their order and leading
+    /// dimension, so either may be a block of a larger allocation; elements
+    /// of the allocation outside the block are not touched.
     fn copy_mat(src: &dyn Matrix<Self>, dst: &mut dyn Matrix<Self>);
 }
 
@@ -24,6 +74,11 @@ macro_rules! copy_impl(($($t: ident), +) => (
     $(
         impl Copy for $t {
             fn copy<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(src: &V, dst: &mut W) {
+                debug_assert!(
+                    !aliases(src.as_ptr(), src.inc(), dst.as_ptr(), dst.inc(), dst.len()),
+                    "Overlapping src and dst passed to copy"
+                );
+
                 unsafe {
                     prefix!($t, copy)(dst.len(),
                         src.as_ptr().as_c_ptr(),  src.inc(),
@@ -32,12 +87,17 @@ macro_rules! copy_impl(($($t: ident), +) => (
             }
 
             fn copy_mat(src: &dyn Matrix<Self>, dst: &mut dyn Matrix<Self>) {
-                let len = dst.rows() * dst.cols();
+                if src.rows() != dst.rows() || src.cols() != dst.cols() {
+                    panic!("Dimension mismatch");
+                }
 
-                unsafe {
-                    prefix!($t, copy)(len,
-                        src.as_ptr().as_c_ptr(),  1,
-                        dst.as_mut_ptr().as_c_ptr(), 1);
+                match dst.order() {
+                    Order::RowMajor => for i in 0..dst.rows() as usize {
+                        Self::copy(&row(src, i), &mut row_mut(dst, i));
+                    },
+                    Order::ColMajor => for j in 0..dst.cols() as usize {
+                        Self::copy(&col(src, j), &mut col_mut(dst, j));
+                    },
                 }
             }
         }
@@ -46,9 +106,69 @@ macro_rules! copy_impl(($($t: ident), +) => (
 
 copy_impl!(f32, f64, Complex32, Complex64);
 
+#[cfg(test)]
+mod copy_tests {
+    use crate::vector::ops::Copy;
+    use crate::vector::Strided;
+
+    #[test]
+    fn disjoint_strides_of_one_buffer() {
+        let mut x = vec![1f32, 0.0, 2.0, 0.0, 3.0, 0.0];
+
+        let ptr = x.as_mut_ptr();
+        let evens = unsafe { Strided::new(ptr, 3, 2) };
+        let mut odds = unsafe { Strided::new(ptr.add(1), 3, 2) };
+        Copy::copy(&evens, &mut odds);
+
+        assert_eq!(x, vec![1.0, 1.0, 2.0, 2.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn onto_itself() {
+        let mut x = vec![1f64, 2.0, 3.0];
+
+        let ptr = x.as_mut_ptr();
+        let src = unsafe { Strided::new(ptr, 3, 1) };
+        let mut dst = unsafe { Strided::new(ptr, 3, 1) };
+        Copy::copy(&src, &mut dst);
+
+        assert_eq!(x, vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Overlapping src and dst")]
+    fn overlapping_strides() {
+        let mut x = vec![1f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+
+        // dst[0] is src[0], which is fine, but dst[2] is src[1].
+        let ptr = x.as_mut_ptr();
+        let src = unsafe { Strided::new(ptr, 3, 2) };
+        let mut dst = unsafe { Strided::new(ptr, 3, 1) };
+        Copy::copy(&src, &mut dst);
+    }
+}
+
 /// Computes `a * x + y` and stores the result in `y`.
 pub trait Axpy: Sized {
     fn axpy<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(alpha: &Self, x: &V, y: &mut W);
+    /// Like `axpy`, but leaves `y` alone and returns `a * x + y` as a new
+    /// contiguous vector of `y.len()` elements.
+    fn axpy_into<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        alpha: &Self,
+        x: &V,
+        y: &W,
+    ) -> Vec<Self>
+    where
+        Self: Copy + Default + Clone,
+    {
+        let mut result = vec![Self::zero(); y.len() as usize];
+        Copy::copy(y, &mut result);
+        Self::axpy(alpha, x, &mut result);
+        result
+    }
+    /// Matrix version of `axpy`. `x` and `y` must have the same shape and
+    /// are addressed through their order and leading dimension.
     fn axpy_mat(alpha: &Self, x: &dyn Matrix<Self>, y: &mut dyn Matrix<Self>);
 }
 
@@ -67,15 +187,17 @@ macro_rules! axpy_impl(($($t: ident), +) => (
             }
 
             fn axpy_mat(alpha: &$t, x: &dyn Matrix<$t>, y: &mut dyn Matrix<$t>) {
-                unsafe {
-                    let x_len = x.rows() * x.cols();
-                    let y_len = y.rows() * y.cols();
-                    let n = cmp::min(x_len, y_len);
+                if x.rows() != y.rows() || x.cols() != y.cols() {
+                    panic!("Dimension mismatch");
+                }
 
-                    prefix!($t, axpy)(n,
-                        alpha.as_const(),
-                        x.as_ptr().as_c_ptr(), 1,
-                        y.as_mut_ptr().as_c_ptr(), 1);
+                match y.order() {
+                    Order::RowMajor => for i in 0..y.rows() as usize {
+                        Self::axpy(alpha, &row(x, i), &mut row_mut(y, i));
+                    },
+                    Order::ColMajor => for j in 0..y.cols() as usize {
+                        Self::axpy(alpha, &col(x, j), &mut col_mut(y, j));
+                    },
                 }
             }
         }
@@ -84,9 +206,48 @@ macro_rules! axpy_impl(($($t: ident), +) => (
 
 axpy_impl!(f32, f64, Complex32, Complex64);
 
+/// Computes `alpha * x + beta * y` and stores the result in `y`.
+///
+/// `?axpby` is an extension only some CBLAS libraries have, so this is a
+/// `scal` of `y` by `beta` followed by an `axpy`. Like `axpy`, it works on
+/// the shorter of the two vectors and leaves the rest of `y` alone. With
+/// `beta` zero the old values of `y` are not read, so NaN or infinity there
+/// does not reach the result.
+pub trait Axpby: Sized {
+    fn axpby<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        alpha: &Self,
+        x: &V,
+        beta: &Self,
+        y: &mut W,
+    );
+}
+
+macro_rules! axpby_impl(($($t: ident), +) => (
+    $(
+        impl Axpby for $t {
+            fn axpby<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(alpha: &$t, x: &V, beta: &$t, y: &mut W) {
+                let n = cmp::min(x.len(), y.len());
+                let mut y = unsafe { Strided::new(y.as_mut_ptr(), n, y.inc()) };
+
+                if *beta == Default::zero() {
+                    for i in 0..n {
+                        y.set(i, Default::zero());
+                    }
+                } else {
+                    Scal::scal(beta, &mut y);
+                }
+                Axpy::axpy(alpha, x, &mut y);
+            }
+        }
+    )+
+));
+
+axpby_impl!(f32, f64, Complex32, Complex64);
+
 #[cfg(test)]
 mod axpy_tests {
     use crate::vector::ops::Axpy;
+    use crate::vector::Strided;
     use num_complex::Complex;
 
     #[test]
@@ -120,11 +281,92 @@ mod axpy_tests {
         Axpy::axpy(&Complex::new(-1f32, 1f32), &y, &mut z);
         assert_eq!(z, vec![Complex::new(0f32, 6f32), Complex::new(-4f32, 2f32)]);
     }
+
+    #[test]
+    fn into() {
+        let x = vec![1f64, -2.0, 3.0];
+        let y = [5f64, 0.0, 7.0, 0.0, 9.0, 0.0];
+        let y_odd = unsafe { Strided::new(y.as_ptr(), 3, 2) };
+
+        let z = Axpy::axpy_into(&2.0, &x, &y_odd);
+        assert_eq!(z, vec![7.0, 3.0, 15.0]);
+        assert_eq!(x, vec![1.0, -2.0, 3.0]);
+        assert_eq!(y, [5.0, 0.0, 7.0, 0.0, 9.0, 0.0]);
+    }
+}
+
+#[cfg(test)]
+mod axpby_tests {
+    use crate::vector::ops::{Axpby, Axpy, Scal};
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let x = vec![1f32, -2.0, 3.0, 4.0];
+        let mut y = vec![3f32, 7.0, -2.0, 2.0];
+
+        let mut expected = y.clone();
+        Scal::scal(&0.5, &mut expected);
+        Axpy::axpy(&-2.0, &x, &mut expected);
+
+        Axpby::axpby(&-2.0, &x, &0.5, &mut y);
+        assert_eq!(y, expected);
+    }
+
+    #[test]
+    fn complex() {
+        let x = vec![Complex::new(1f64, 1.0), Complex::new(1.0, 3.0)];
+        let mut y = vec![Complex::new(3f64, -2.0), Complex::new(2.0, 3.0)];
+        let (alpha, beta) = (Complex::new(0.0, 1.0), Complex::new(2.0, -1.0));
+
+        let mut expected = y.clone();
+        Scal::scal(&beta, &mut expected);
+        Axpy::axpy(&alpha, &x, &mut expected);
+
+        Axpby::axpby(&alpha, &x, &beta, &mut y);
+        assert_eq!(y, expected);
+    }
+
+    #[test]
+    fn longer_y() {
+        let x = vec![1f64, 1.0];
+        let mut y = vec![1f64, 2.0, 3.0];
+
+        Axpby::axpby(&1.0, &x, &10.0, &mut y);
+        assert_eq!(y, vec![11.0, 21.0, 3.0]);
+    }
+
+    #[test]
+    fn zero_beta_ignores_y() {
+        let x = vec![1f64, -2.0];
+        let mut y = vec![f64::NAN, f64::INFINITY];
+
+        Axpby::axpby(&3.0, &x, &0.0, &mut y);
+        assert_eq!(y, vec![3.0, -6.0]);
+
+        let x = vec![Complex::new(1f32, 2.0)];
+        let mut y = vec![Complex::new(f32::NAN, 0.0)];
+        Axpby::axpby(&Complex::new(0.0, 1.0), &x, &Complex::new(0.0, 0.0), &mut y);
+        assert_eq!(y, vec![Complex::new(-2.0, 1.0)]);
+    }
 }
 
 /// Computes `a * x` and stores the result in `x`.
 pub trait Scal: Sized {
     fn scal<V: ?Sized + Vector<Self>>(alpha: &Self, x: &mut V);
+    /// Like `scal`, but returns `a * x` as a new contiguous vector instead
+    /// of overwriting `x`.
+    fn scal_into<V: ?Sized + Vector<Self>>(alpha: &Self, x: &V) -> Vec<Self>
+    where
+        Self: Copy + Default + Clone,
+    {
+        let mut result = vec![Self::zero(); x.len() as usize];
+        Copy::copy(x, &mut result);
+        Self::scal(alpha, &mut result);
+        result
+    }
+    /// Matrix version of `scal`, addressing `x` through its order and
+    /// leading dimension.
     fn scal_mat(alpha: &Self, x: &mut dyn Matrix<Self>);
 }
 
@@ -141,10 +383,13 @@ macro_rules! scal_impl(($($t: ident), +) => (
             }
 
             fn scal_mat(alpha: &$t, x: &mut dyn Matrix<$t>) {
-                unsafe {
-                    prefix!($t, scal)(x.rows() * x.cols(),
-                        alpha.as_const(),
-                        x.as_mut_ptr().as_c_ptr(), 1);
+                match x.order() {
+                    Order::RowMajor => for i in 0..x.rows() as usize {
+                        Self::scal(alpha, &mut row_mut(x, i));
+                    },
+                    Order::ColMajor => for j in 0..x.cols() as usize {
+                        Self::scal(alpha, &mut col_mut(x, j));
+                    },
                 }
             }
         }
@@ -189,6 +434,15 @@ mod scal_tests {
         Scal::scal(&Complex::new(2f32, 0f32), &mut x);
         assert_eq!(x, vec![Complex::new(2f32, 2f32), Complex::new(2f32, 6f32)]);
     }
+
+    #[test]
+    fn into() {
+        let x = vec![Complex::new(1f32, 1f32), Complex::new(1f32, 3f32)];
+
+        let y = Scal::scal_into(&Complex::new(1f32, 1f32), &x);
+        assert_eq!(y, vec![Complex::new(0f32, 2f32), Complex::new(-2f32, 4f32)]);
+        assert_eq!(x, vec![Complex::new(1f32, 1f32), Complex::new(1f32, 3f32)]);
+    }
 }
 
 /// Swaps the content of `x` and `y`.
@@ -215,6 +469,161 @@ macro_rules! swap_impl(($($t: ident), +) => (
 
 swap_impl!(f32, f64, Complex32, Complex64);
 
+#[cfg(test)]
+mod mat_tests {
+    use crate::attribute::Order;
+    use crate::matrix::Matrix;
+    use crate::vector::ops::{Axpy, Copy, Scal};
+
+    /// A `rows x cols` block of a larger buffer, `ld` elements per line.
+    struct Block<'a> {
+        rows: u32,
+        cols: u32,
+        ld: u32,
+        order: Order,
+        data: &'a mut [f64],
+    }
+
+    impl<'a> Matrix<f64> for Block<'a> {
+        fn lead_dim(&self) -> u32 {
+            self.ld
+        }
+
+        fn order(&self) -> Order {
+            self.order
+        }
+
+        fn rows(&self) -> u32 {
+            self.rows
+        }
+
+        fn cols(&self) -> u32 {
+            self.cols
+        }
+
+        fn as_ptr(&self) -> *const f64 {
+            self.data.as_ptr()
+        }
+
+        fn as_mut_ptr(&mut self) -> *mut f64 {
+            self.data.as_mut_ptr()
+        }
+    }
+
+    #[test]
+    fn copy_top_left_block() {
+        let mut src: Vec<f64> = (0..100).map(|i| i as f64).collect();
+        let mut dst = vec![-1f64; 100];
+
+        {
+            let a = Block {
+                rows: 3,
+                cols: 3,
+                ld: 10,
+                order: Order::RowMajor,
+                data: &mut src[..],
+            };
+            let mut b = Block {
+                rows: 3,
+                cols: 3,
+                ld: 10,
+                order: Order::RowMajor,
+                data: &mut dst[..],
+            };
+            Copy::copy_mat(&a, &mut b);
+        }
+
+        for i in 0..10 {
+            for j in 0..10 {
+                let expected = if i < 3 && j < 3 {
+                    (i * 10 + j) as f64
+                } else {
+                    -1.0
+                };
+                assert_eq!(dst[i * 10 + j], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn copy_into_packed() {
+        let mut src: Vec<f64> = (0..12).map(|i| i as f64).collect();
+        let mut dst = vec![0f64; 4];
+
+        {
+            // The middle 2x2 of a 3x4 column-major buffer, copied into a
+            // packed row-major 2x2.
+            let a = Block {
+                rows: 2,
+                cols: 2,
+                ld: 3,
+                order: Order::ColMajor,
+                data: &mut src[4..],
+            };
+            let mut b = Block {
+                rows: 2,
+                cols: 2,
+                ld: 2,
+                order: Order::RowMajor,
+                data: &mut dst[..],
+            };
+            Copy::copy_mat(&a, &mut b);
+        }
+
+        assert_eq!(dst, vec![4.0, 7.0, 5.0, 8.0]);
+    }
+
+    #[test]
+    fn axpy_and_scal_block() {
+        let mut x = [1f64; 8];
+        let mut y = vec![0f64; 8];
+
+        {
+            let a = Block {
+                rows: 2,
+                cols: 2,
+                ld: 4,
+                order: Order::ColMajor,
+                data: &mut x[..],
+            };
+            let mut b = Block {
+                rows: 2,
+                cols: 2,
+                ld: 4,
+                order: Order::ColMajor,
+                data: &mut y[1..],
+            };
+            Axpy::axpy_mat(&2.0, &a, &mut b);
+            Scal::scal_mat(&3.0, &mut b);
+        }
+
+        assert_eq!(y, vec![0.0, 6.0, 6.0, 0.0, 0.0, 6.0, 6.0, 0.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn copy_mismatched() {
+        let mut src = [0f64; 6];
+        let mut dst = [0f64; 6];
+
+        let a = Block {
+            rows: 2,
+            cols: 3,
+            ld: 3,
+            order: Order::RowMajor,
+            data: &mut src[..],
+        };
+        let mut b = Block {
+            rows: 3,
+            cols: 2,
+            ld: 2,
+            order: Order::RowMajor,
+            data: &mut dst[..],
+        };
+        Copy::copy_mat(&a, &mut b);
+    }
+}
+
 #[cfg(test)]
 mod swap_tests {
     use crate::vector::ops::Swap;
@@ -382,52 +791,150 @@ mod dotc_tests {
     }
 }
 
+/// Dot products of single precision vectors accumulated in double
+/// precision.
+///
+/// Long sums and sums with cancellation keep their accuracy without
+/// storing the vectors as `f64`. As with `dot`, the shorter length is used.
+pub trait DotExt: Sized {
+    /// Computes `alpha + x^T * y`, accumulated in `f64` and rounded once.
+    fn sdsdot<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        alpha: Self,
+        x: &V,
+        y: &W,
+    ) -> Self;
+    /// Computes `x^T * y`, accumulated and returned in `f64`.
+    fn dsdot<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, y: &W) -> f64;
+}
+
+impl DotExt for f32 {
+    fn sdsdot<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(alpha: f32, x: &V, y: &W) -> f32 {
+        unsafe {
+            cblas_s::cblas_sdsdot(
+                cmp::min(x.len(), y.len()),
+                alpha,
+                x.as_ptr(),
+                x.inc(),
+                y.as_ptr(),
+                y.inc(),
+            )
+        }
+    }
+
+    fn dsdot<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &V, y: &W) -> f64 {
+        unsafe {
+            cblas_d::cblas_dsdot(
+                cmp::min(x.len(), y.len()),
+                x.as_ptr(),
+                x.inc(),
+                y.as_ptr(),
+                y.inc(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod dot_ext_tests {
+    use crate::vector::ops::DotExt;
+    use crate::vector::Strided;
+
+    #[test]
+    fn cancellation() {
+        let x = vec![1e8f32, 1.0, -1e8, 0.5];
+        let y = vec![1f32; 4];
+
+        // Summed in order in f32, the 1 is lost against 1e8.
+        let naive = x.iter().zip(&y).fold(0f32, |acc, (a, b)| acc + a * b);
+        assert_eq!(naive, 0.5);
+
+        let reference: f64 = x.iter().map(|&v| v as f64).sum();
+        assert_eq!(DotExt::dsdot(&x, &y), reference);
+        assert_eq!(DotExt::sdsdot(0.25, &x, &y), 1.75);
+    }
+
+    #[test]
+    fn strided() {
+        let x = [1f32, 9.0, 2.0, 9.0, 3.0];
+        let evens = unsafe { Strided::new(x.as_ptr(), 3, 2) };
+
+        assert_eq!(DotExt::dsdot(&evens, &vec![1f32, -1.0, 2.0]), 5.0);
+        assert_eq!(DotExt::sdsdot(-5.0, &evens, &vec![1f32, -1.0, 2.0]), 0.0);
+    }
+}
+
 /// Computes the sum of the absolute values of elements in a vector.
 ///
 /// Complex vectors use `||Re(x)||_1 + ||Im(x)||_1`
 pub trait Asum: Sized {
+    /// The type of the sum: `Self` for real vectors and the underlying real
+    /// type for complex ones.
+    type Output;
+
+    /// Returns the sum as `Self`, so complex vectors get it as the real part
+    /// of a complex number. Kept for compatibility; prefer `asum_real`.
     fn asum<V: ?Sized + Vector<Self>>(x: &V) -> Self;
+    /// Returns the sum as a plain real number.
+    fn asum_real<V: ?Sized + Vector<Self>>(x: &V) -> Self::Output;
 }
 
 /// Computes the L2 norm (Euclidian length) of a vector.
 pub trait Nrm2: Sized {
+    /// The type of the norm: `Self` for real vectors and the underlying real
+    /// type for complex ones.
+    type Output;
+
+    /// Returns the norm as `Self`, so complex vectors get it as the real part
+    /// of a complex number. Kept for compatibility; prefer `nrm2_real`.
     fn nrm2<V: ?Sized + Vector<Self>>(x: &V) -> Self;
+    /// Returns the norm as a plain real number.
+    fn nrm2_real<V: ?Sized + Vector<Self>>(x: &V) -> Self::Output;
 }
 
-macro_rules! real_norm_impl(($trait_name: ident, $fn_name: ident, $($t: ident), +) => (
+macro_rules! real_norm_impl(($trait_name: ident, $fn_name: ident, $real_fn: ident, $($t: ident), +) => (
     $(
         impl $trait_name for $t {
+            type Output = $t;
+
             fn $fn_name<V: ?Sized + Vector<Self>>(x: &V) -> $t {
                 unsafe {
                     prefix!($t, $fn_name)(x.len(),
                         x.as_ptr().as_c_ptr(), x.inc())
                 }
             }
+
+            fn $real_fn<V: ?Sized + Vector<Self>>(x: &V) -> $t {
+                Self::$fn_name(x)
+            }
         }
     )+
 ));
 
 macro_rules! complex_norm_impl(
-    ($trait_name: ident, $fn_name: ident, $t: ty, $norm_fn: expr) => (
+    ($trait_name: ident, $fn_name: ident, $real_fn: ident, $t: ty, $r: ty, $norm_fn: expr) => (
         impl $trait_name for $t {
+            type Output = $r;
+
             fn $fn_name<V: ?Sized + Vector<Self>>(x: &V) -> $t {
-                let re = unsafe {
+                Complex { im: 0.0, re: Self::$real_fn(x) }
+            }
+
+            fn $real_fn<V: ?Sized + Vector<Self>>(x: &V) -> $r {
+                unsafe {
                     $norm_fn(x.len(),
                         x.as_ptr().as_c_ptr(), x.inc())
-                };
-
-                Complex { im: 0.0, re: re }
+                }
             }
         }
     );
 );
 
-real_norm_impl!(Asum, asum, f32, f64);
-real_norm_impl!(Nrm2, nrm2, f32, f64);
-complex_norm_impl!(Asum, asum, Complex32, cblas_s::casum);
-complex_norm_impl!(Asum, asum, Complex64, cblas_d::zasum);
-complex_norm_impl!(Nrm2, nrm2, Complex32, cblas_s::cnrm2);
-complex_norm_impl!(Nrm2, nrm2, Complex64, cblas_d::znrm2);
+real_norm_impl!(Asum, asum, asum_real, f32, f64);
+real_norm_impl!(Nrm2, nrm2, nrm2_real, f32, f64);
+complex_norm_impl!(Asum, asum, asum_real, Complex32, f32, cblas_s::casum);
+complex_norm_impl!(Asum, asum, asum_real, Complex64, f64, cblas_d::zasum);
+complex_norm_impl!(Nrm2, nrm2, nrm2_real, Complex32, f32, cblas_s::cnrm2);
+complex_norm_impl!(Nrm2, nrm2, nrm2_real, Complex64, f64, cblas_d::znrm2);
 
 #[cfg(test)]
 mod asum_tests {
@@ -457,6 +964,23 @@ mod asum_tests {
         let r: Complex<f32> = Asum::asum(&x);
         assert_eq!(r, Complex { im: 0.0, re: 7f32 });
     }
+
+    #[test]
+    fn complex_real() {
+        let x = vec![Complex::new(3f32, 4f32), Complex::new(-1f32, 0.5f32)];
+
+        let r: f32 = Asum::asum_real(&x);
+        assert_eq!(r, 8.5f32);
+        let r: f64 = Asum::asum_real(&vec![Complex::new(-2f64, 1f64)]);
+        assert_eq!(r, 3f64);
+    }
+
+    #[test]
+    fn real_output() {
+        let x = vec![1f64, -2f64];
+
+        assert_eq!(Asum::asum_real(&x), Asum::asum(&x));
+    }
 }
 
 #[cfg(test)]
@@ -487,6 +1011,14 @@ mod nrm2_tests {
         let xr: Complex<f32> = Nrm2::nrm2(&x);
         assert_eq!(xr, Complex { im: 0.0, re: 5f32 });
     }
+
+    #[test]
+    fn complex_real() {
+        let x = vec![Complex::new(3f64, 4f64)];
+
+        let xr: f64 = Nrm2::nrm2_real(&x);
+        assert_eq!(xr, 5f64);
+    }
 }
 
 /// Finds the index of the element with maximum absolute value in a vector.
@@ -516,6 +1048,105 @@ iamax_impl!(f64, cblas_i::damax);
 iamax_impl!(Complex32, cblas_i::camax);
 iamax_impl!(Complex64, cblas_i::zamax);
 
+/// Finds the index of the element with minimum absolute value in a vector.
+///
+/// Complex vectors minimize `|Re(x_k)| + |Im(x_k)|`, as `Iamax` does. The
+/// first index with a minimum is returned, and `0` for an empty vector.
+///
+/// `i?amin` is only an extension in some CBLAS libraries, so this is a plain
+/// scan over the strided elements rather than a BLAS call.
+pub trait Iamin: Sized {
+    fn iamin<V: ?Sized + Vector<Self>>(x: &V) -> usize;
+}
+
+macro_rules! iamin_impl(
+    ($t: ty, $r: ty, $abs1: expr) => (
+        impl Iamin for $t {
+            fn iamin<V: ?Sized + Vector<Self>>(x: &V) -> usize {
+                let abs1: fn(&$t) -> $r = $abs1;
+                let v = unsafe { Strided::new(x.as_ptr(), x.len(), x.inc()) };
+
+                let mut best = 0;
+                for i in 1..v.len() {
+                    if abs1(&v.get(i)) < abs1(&v.get(best)) {
+                        best = i;
+                    }
+                }
+                best as usize
+            }
+        }
+    );
+);
+
+iamin_impl!(f32, f32, |v| v.abs());
+iamin_impl!(f64, f64, |v| v.abs());
+iamin_impl!(Complex32, f32, |v| v.re.abs() + v.im.abs());
+iamin_impl!(Complex64, f64, |v| v.re.abs() + v.im.abs());
+
+/// Computes the infinity norm of a vector, the largest absolute value of its
+/// elements.
+///
+/// The element is the one `Iamax` picks, so complex vectors measure
+/// `|Re(x_k)| + |Im(x_k)|` rather than the modulus. An empty vector has norm
+/// `0`.
+pub trait NrmInf: Sized {
+    type Output;
+
+    fn nrminf<V: ?Sized + Vector<Self>>(x: &V) -> Self::Output;
+}
+
+macro_rules! nrminf_impl(
+    ($t: ty, $r: ty, $abs1: expr) => (
+        impl NrmInf for $t {
+            type Output = $r;
+
+            fn nrminf<V: ?Sized + Vector<Self>>(x: &V) -> $r {
+                let abs1: fn(&$t) -> $r = $abs1;
+                if x.len() == 0 {
+                    return 0.0;
+                }
+
+                let v = unsafe { Strided::new(x.as_ptr(), x.len(), x.inc()) };
+                abs1(&v.get(Iamax::iamax(x) as u32))
+            }
+        }
+    );
+);
+
+nrminf_impl!(f32, f32, |v| v.abs());
+nrminf_impl!(f64, f64, |v| v.abs());
+nrminf_impl!(Complex32, f32, |v| v.re.abs() + v.im.abs());
+nrminf_impl!(Complex64, f64, |v| v.re.abs() + v.im.abs());
+
+/// A vector norm, for routines that let the caller choose how to measure
+/// size.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Norm {
+    /// Sum of absolute values, as `Asum`.
+    L1,
+    /// Euclidean length, as `Nrm2`.
+    L2,
+    /// Largest absolute value, as `NrmInf`.
+    Inf,
+}
+
+/// Computes the `kind` norm of `x` as a real number.
+///
+/// Complex vectors measure each element by `|Re(x_k)| + |Im(x_k)|` for
+/// `Norm::L1` and `Norm::Inf`, following `Asum` and `Iamax`, and by the
+/// modulus for `Norm::L2`.
+pub fn norm<T, V>(kind: Norm, x: &V) -> <T as NrmInf>::Output
+where
+    T: Asum + Nrm2<Output = <T as Asum>::Output> + NrmInf<Output = <T as Asum>::Output>,
+    V: ?Sized + Vector<T>,
+{
+    match kind {
+        Norm::L1 => Asum::asum_real(x),
+        Norm::L2 => Nrm2::nrm2_real(x),
+        Norm::Inf => NrmInf::nrminf(x),
+    }
+}
+
 #[cfg(test)]
 mod iamax_tests {
     use crate::vector::ops::Iamax;
@@ -546,22 +1177,149 @@ mod iamax_tests {
     }
 }
 
+#[cfg(test)]
+mod iamin_tests {
+    use crate::vector::ops::Iamin;
+    use crate::vector::Strided;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let x = vec![3f32, -2f32, 1f32, -1f32];
+
+        let xr = Iamin::iamin(&x);
+        assert_eq!(xr, 2usize);
+    }
+
+    #[test]
+    fn slice() {
+        let x = [-4f64, 3f64, 2f64, 5f64];
+
+        let xr = Iamin::iamin(&x[..2]);
+        assert_eq!(xr, 1usize);
+    }
+
+    #[test]
+    fn strided() {
+        let x = [5f32, 0f32, -3f32, 0f32, 4f32, 0f32];
+        let evens = unsafe { Strided::new(x.as_ptr(), 3, 2) };
+
+        let xr = Iamin::iamin(&evens);
+        assert_eq!(xr, 1usize);
+    }
+
+    #[test]
+    fn complex() {
+        // The second element has the smaller modulus, but not the smaller
+        // |re| + |im|.
+        let x = vec![Complex::new(0f32, 5f32), Complex::new(3f32, 3.5f32)];
+
+        let xr = Iamin::iamin(&x);
+        assert_eq!(xr, 0usize);
+    }
+
+    #[test]
+    fn empty() {
+        let x: Vec<f64> = vec![];
+
+        assert_eq!(Iamin::iamin(&x), 0usize);
+    }
+}
+
+#[cfg(test)]
+mod nrminf_tests {
+    use crate::vector::ops::NrmInf;
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let x = vec![1f32, -7f32, 3f32];
+
+        let xr = NrmInf::nrminf(&x);
+        assert_eq!(xr, 7f32);
+    }
+
+    #[test]
+    fn complex() {
+        let x = vec![
+            Complex::new(3f64, 4f64),
+            Complex::new(-1f64, -6.5f64),
+            Complex::new(0f64, 5f64),
+        ];
+
+        let xr = NrmInf::nrminf(&x);
+        assert_eq!(xr, 7.5f64);
+    }
+
+    #[test]
+    fn empty() {
+        let x: Vec<f64> = Vec::new();
+
+        assert_eq!(NrmInf::nrminf(&x), 0f64);
+    }
+}
+
+#[cfg(test)]
+mod norm_tests {
+    use crate::vector::ops::{norm, Norm};
+    use num_complex::Complex;
+
+    #[test]
+    fn real() {
+        let x = vec![3f32, -4f32, 0f32];
+
+        assert_eq!(norm(Norm::L1, &x), 7f32);
+        assert_eq!(norm(Norm::L2, &x), 5f32);
+        assert_eq!(norm(Norm::Inf, &x), 4f32);
+    }
+
+    #[test]
+    fn slice() {
+        let x = [1f64, -2f64, 2f64];
+
+        assert_eq!(norm(Norm::L1, &x[..]), 5f64);
+        assert_eq!(norm(Norm::L2, &x[..]), 3f64);
+        assert_eq!(norm(Norm::Inf, &x[..]), 2f64);
+    }
+
+    #[test]
+    fn complex() {
+        let x = vec![Complex::new(3f32, 4f32), Complex::new(0f32, -1f32)];
+        let y = vec![Complex::new(1f64, -2f64), Complex::new(-2f64, 4f64)];
+
+        assert_eq!(norm(Norm::L1, &x), 8f32);
+        assert!((norm(Norm::L2, &x) - 26f32.sqrt()).abs() < 1e-6);
+        assert_eq!(norm(Norm::Inf, &x), 7f32);
+        assert_eq!(norm(Norm::L1, &y), 9f64);
+        assert!((norm(Norm::L2, &y) - 5f64).abs() < 1e-12);
+        assert_eq!(norm(Norm::Inf, &y), 6f64);
+    }
+}
+
 /// Applies a Givens rotation matrix to a pair of vectors, where `cos` is
 /// the value of the cosine of the angle in the Givens matrix, and `sin` is
 /// the sine.
+///
+/// The rotation is always real: for complex vectors `cos` and `sin` are of
+/// the underlying real type (`?srot`/`?drot` in BLAS).
 pub trait Rot: Sized {
+    /// The scalar type of `cos` and `sin`.
+    type Real;
+
     fn rot<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
         x: &mut V,
         y: &mut W,
-        cos: &Self,
-        sin: &Self,
+        cos: &Self::Real,
+        sin: &Self::Real,
     );
 }
 
-macro_rules! rot_impl(($($t: ident), +) => (
+macro_rules! rot_impl(($($t: ident: $r: ident), +) => (
     $(
         impl Rot for $t {
-            fn rot<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &mut V, y: &mut W, cos: &$t, sin: &$t) {
+            type Real = $r;
+
+            fn rot<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &mut V, y: &mut W, cos: &$r, sin: &$r) {
                 unsafe {
                     prefix!($t, rot)(cmp::min(x.len(), y.len()),
                         x.as_mut_ptr().as_c_ptr(), x.inc(),
@@ -573,11 +1331,96 @@ macro_rules! rot_impl(($($t: ident), +) => (
     )+
 ));
 
-rot_impl!(f32, f64);
+rot_impl!(f32: f32, f64: f64, Complex32: f32, Complex64: f64);
+
+/// Constructs the Givens rotation that zeroes `b` against `a`.
+///
+/// Returns `(cos, sin)` such that `[c s; -s c] * [a; b] = [r; 0]`, ready to
+/// be passed to `rot`. As in BLAS, `a` is overwritten with `r` and `b` with
+/// `z`, a compact encoding from which `cos` and `sin` can be recovered.
+pub trait Rotg: Sized {
+    fn rotg(a: &mut Self, b: &mut Self) -> (Self, Self);
+}
+
+macro_rules! rotg_impl(($($t: ident), +) => (
+    $(
+        impl Rotg for $t {
+            fn rotg(a: &mut $t, b: &mut $t) -> ($t, $t) {
+                let mut c = 0.0;
+                let mut s = 0.0;
+                unsafe {
+                    prefix!($t, rotg)(a, b, &mut c, &mut s);
+                }
+                (c, s)
+            }
+        }
+    )+
+));
+
+rotg_impl!(f32, f64);
+
+/// Applies the modified Givens transformation `H` described by `param` to
+/// the pairs `(x_i, y_i)`, replacing them with `H * [x_i; y_i]`.
+///
+/// `param[0]` is the flag BLAS uses to encode `H` and is passed through
+/// as-is: `-1` means `H = [p1 p3; p2 p4]`, `0` means `H = [1 p3; p2 1]`,
+/// `1` means `H = [p1 1; -1 p4]` and `-2` means `H` is the identity, so
+/// nothing is touched. Entries of `param` implied by the flag are not read.
+pub trait Rotm: Sized {
+    fn rotm<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(
+        x: &mut V,
+        y: &mut W,
+        param: &[Self; 5],
+    );
+}
+
+macro_rules! rotm_impl(($($t: ident), +) => (
+    $(
+        impl Rotm for $t {
+            fn rotm<V: ?Sized + Vector<Self>, W: ?Sized + Vector<Self>>(x: &mut V, y: &mut W, param: &[$t; 5]) {
+                unsafe {
+                    prefix!($t, rotm)(cmp::min(x.len(), y.len()),
+                        x.as_mut_ptr().as_c_ptr(), x.inc(),
+                        y.as_mut_ptr().as_c_ptr(), y.inc(),
+                        param.as_ptr());
+                }
+            }
+        }
+    )+
+));
+
+rotm_impl!(f32, f64);
+
+/// Constructs the modified Givens transformation that zeroes the second
+/// component of `(sqrt(d1) * x1, sqrt(d2) * y1)`, returning it in the
+/// `param` form taken by `rotm`.
+///
+/// As in BLAS, `d1`, `d2` and `x1` are overwritten with the updated scale
+/// factors and the rotated first component, and no square roots are taken.
+pub trait Rotmg: Sized {
+    fn rotmg(d1: &mut Self, d2: &mut Self, x1: &mut Self, y1: &Self) -> [Self; 5];
+}
+
+macro_rules! rotmg_impl(($($t: ident), +) => (
+    $(
+        impl Rotmg for $t {
+            fn rotmg(d1: &mut $t, d2: &mut $t, x1: &mut $t, y1: &$t) -> [$t; 5] {
+                let mut param = [0.0; 5];
+                unsafe {
+                    prefix!($t, rotmg)(d1, d2, x1, *y1, param.as_mut_ptr());
+                }
+                param
+            }
+        }
+    )+
+));
+
+rotmg_impl!(f32, f64);
 
 #[cfg(test)]
 mod rot_tests {
     use crate::vector::ops::{Rot, Scal};
+    use num_complex::Complex;
 
     #[test]
     fn real() {
@@ -594,4 +1437,122 @@ mod rot_tests {
         assert_eq!(x, xr);
         assert_eq!(y, yr);
     }
+
+    #[test]
+    fn complex() {
+        let mut x = vec![Complex::new(1f32, -2f32), Complex::new(3f32, 4f32)];
+        let mut y = vec![Complex::new(0f32, 1f32), Complex::new(-2f32, 2f32)];
+        let cos = 0f32;
+        let sin = 1f32;
+
+        let xr = y.clone();
+        let mut yr = x.clone();
+        Scal::scal(&Complex::new(-1f32, 0f32), &mut yr);
+
+        Rot::rot(&mut x, &mut y, &cos, &sin);
+        assert_eq!(x, xr);
+        assert_eq!(y, yr);
+    }
+
+    #[test]
+    fn complex_angle() {
+        let mut x = vec![Complex::new(3f64, 1f64)];
+        let mut y = vec![Complex::new(4f64, -1f64)];
+
+        Rot::rot(&mut x, &mut y, &0.6, &0.8);
+        assert!((x[0] - Complex::new(5.0, -0.2)).norm() < 1e-12);
+        assert!((y[0] - Complex::new(0.0, -1.4)).norm() < 1e-12);
+    }
+}
+
+#[cfg(test)]
+mod rotg_tests {
+    use crate::vector::ops::{Rot, Rotg};
+
+    #[test]
+    fn three_four() {
+        let (mut a, mut b) = (3f64, 4f64);
+
+        let (c, s) = Rotg::rotg(&mut a, &mut b);
+        assert!((c - 0.6).abs() < 1e-15);
+        assert!((s - 0.8).abs() < 1e-15);
+        assert!((a - 5.0).abs() < 1e-15);
+        // |a| <= |b|, so z encodes 1 / c.
+        assert!((b - 1.0 / c).abs() < 1e-15);
+    }
+
+    #[test]
+    fn zeroes_with_rot() {
+        let mut x = vec![3f32, 1.0];
+        let mut y = vec![-4f32, 2.0];
+        let (mut a, mut b) = (x[0], y[0]);
+
+        let (c, s) = Rotg::rotg(&mut a, &mut b);
+        Rot::rot(&mut x, &mut y, &c, &s);
+        assert!((x[0] - a).abs() < 1e-6);
+        assert!(y[0].abs() < 1e-6);
+    }
+
+    #[test]
+    fn zero_input() {
+        let (mut a, mut b) = (0f32, 0f32);
+
+        assert_eq!(Rotg::rotg(&mut a, &mut b), (1.0, 0.0));
+        assert_eq!((a, b), (0.0, 0.0));
+    }
+}
+
+#[cfg(test)]
+mod rotm_tests {
+    use crate::vector::ops::{Rotm, Rotmg};
+
+    /// Generates the transformation for `(x[0], y[0])` with unit scales,
+    /// applies it to both vectors and checks that `y[0]` is zeroed.
+    fn round_trip(mut x: Vec<f64>, mut y: Vec<f64>) -> (f64, Vec<f64>, Vec<f64>) {
+        let (mut d1, mut d2, mut x1) = (1f64, 1f64, x[0]);
+        let norm = x[0].hypot(y[0]);
+
+        let param = Rotmg::rotmg(&mut d1, &mut d2, &mut x1, &y[0]);
+        Rotm::rotm(&mut x, &mut y, &param);
+
+        assert!((x[0] - x1).abs() < 1e-12);
+        assert!(y[0].abs() < 1e-12);
+        assert!((d1.sqrt() * x1.abs() - norm).abs() < 1e-12);
+        (param[0], x, y)
+    }
+
+    #[test]
+    fn x_dominant() {
+        let (flag, x, y) = round_trip(vec![4.0, 1.0], vec![2.0, -1.0]);
+        assert_eq!(flag, 0.0);
+        // H = [1 h12; h21 1] with h21 = -1/2 and h12 = 1/2.
+        assert_eq!((x[1], y[1]), (0.5, -1.5));
+    }
+
+    #[test]
+    fn y_dominant() {
+        let (flag, _, _) = round_trip(vec![1.0, 0.0], vec![3.0, 0.0]);
+        assert_eq!(flag, 1.0);
+    }
+
+    #[test]
+    fn nothing_to_zero() {
+        let (mut d1, mut d2, mut x1) = (1f32, 1f32, 5f32);
+        let param = Rotmg::rotmg(&mut d1, &mut d2, &mut x1, &0.0);
+        assert_eq!(param[0], -2.0);
+
+        let mut x = vec![1f32, 2.0];
+        let mut y = vec![3f32, 4.0];
+        Rotm::rotm(&mut x, &mut y, &param);
+        assert_eq!((x, y), (vec![1.0, 2.0], vec![3.0, 4.0]));
+    }
+
+    #[test]
+    fn full_matrix_flag() {
+        let mut x = vec![1f32, 0.0];
+        let mut y = vec![0f32, 1.0];
+
+        Rotm::rotm(&mut x, &mut y, &[-1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!((x, y), (vec![2.0, 4.0], vec![3.0, 5.0]));
+    }
 }